@@ -0,0 +1,122 @@
+#![feature(const_option, const_trait_impl, const_mut_refs, nonzero_ops)]
+
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use exchange_backtester::prelude::*;
+
+const RUNS: u32 = 5;
+/// History orders put to each of the two bid levels
+const LEVEL_DEPTH: u64 = 5_000;
+
+/// Fills two deep bid levels, adds the second half of the best level after the trader order, if any,
+/// and then decreases every history order and cancels it, so that the resizes hit the middle of the level queues
+struct DeepLevelHistory(VecDeque<HistoryEvent>);
+
+impl DeepLevelHistory {
+    fn new() -> Self {
+        let start = DateTime::parse_from_str("2021-06-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let mut events = VecDeque::new();
+        let mut push = |datetime: DateTime, size: i64, direction: Direction, price: i64, order_id: u64| {
+            events.push_back(HistoryEvent {
+                datetime,
+                instrument: InstrumentID(0),
+                event: HistoryEventBody::OrderBookDiff(Size(size), direction, Price(price), OrderID(order_id)),
+            })
+        };
+        push(start, 10, Direction::Sell, 101, 0);
+        for order_id in 1..=LEVEL_DEPTH / 2 {
+            push(start, 10, Direction::Buy, 100, order_id)
+        }
+        for order_id in LEVEL_DEPTH + 1..=2 * LEVEL_DEPTH {
+            push(start, 10, Direction::Buy, 99, order_id)
+        }
+        for order_id in LEVEL_DEPTH / 2 + 1..=LEVEL_DEPTH {
+            push(start + chrono::Duration::seconds(2), 10, Direction::Buy, 100, order_id)
+        }
+        let mut datetime = start + chrono::Duration::seconds(3);
+        for size in [5, 0] {
+            // Visits every order once in a scattered order
+            for step in 0..2 * LEVEL_DEPTH {
+                let order_id = step * 7919 % (2 * LEVEL_DEPTH) + 1;
+                let price = if order_id <= LEVEL_DEPTH { 100 } else { 99 };
+                push(datetime, size, Direction::Buy, price, order_id);
+                datetime += chrono::Duration::microseconds(100);
+            }
+        }
+        DeepLevelHistory(events)
+    }
+}
+
+impl EventProcessor for DeepLevelHistory {
+    fn yield_next_event(&mut self) -> Option<HistoryEvent> { self.0.pop_front() }
+}
+
+/// Places one limit order at the best bid on the first wakeup
+struct RestingTrader {
+    is_placed: bool,
+}
+
+impl HandleSubscriptionUpdates for RestingTrader {
+    fn handle_order_book_snapshot(&mut self, _: DateTime, _: DateTime, _: OrderBookSnapshot) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_trade_info_update(&mut self, _: DateTime, _: DateTime, _: Vec<OrderBookDiff>) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_wakeup(&mut self, _: DateTime) -> Vec<TraderRequest> {
+        if self.is_placed {
+            return vec![];
+        }
+        self.is_placed = true;
+        vec![TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(0), Size(1), Direction::Buy, Price(100)))]
+    }
+}
+
+impl const Trader for RestingTrader {
+    fn exchange_to_trader_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn trader_to_exchange_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn handle_exchange_reply(&mut self, _: DateTime, _: DateTime, _: ExchangeReply) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn exchange_open(&mut self, _: DateTime, _: DateTime) {}
+    fn exchange_closed(&mut self, _: DateTime, _: DateTime) {}
+}
+
+fn get_next_open_dt(datetime: DateTime) -> DateTime { datetime.date().and_hms(10, 0, 0) }
+
+fn get_next_close_dt(datetime: DateTime) -> DateTime { datetime.date().and_hms(10, 1, 0) }
+
+fn history_levels() {
+    let mut trader = examples::VoidTrader;
+    ExchangeBuilder::new::<false>(DeepLevelHistory::new(), &mut trader, get_next_open_dt, get_next_close_dt)
+        .run_trades()
+}
+
+fn trader_in_queue() {
+    let mut trader = RestingTrader { is_placed: false };
+    ExchangeBuilder::new::<false>(DeepLevelHistory::new(), &mut trader, get_next_open_dt, get_next_close_dt)
+        .with_periodic_wakeup(lags::constant::ONE_SECOND)
+        .run_trades()
+}
+
+fn bench(name: &str, run: fn()) {
+    let mut elapsed = Vec::with_capacity(RUNS as usize);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run();
+        elapsed.push(start.elapsed());
+    }
+    let total: Duration = elapsed.iter().sum();
+    println!(
+        "{:<16} mean: {:>10.3?}  min: {:>10.3?}  max: {:>10.3?}",
+        name,
+        total / RUNS,
+        elapsed.iter().min().unwrap(),
+        elapsed.iter().max().unwrap()
+    )
+}
+
+fn main() {
+    bench("history_levels", history_levels);
+    bench("trader_in_queue", trader_in_queue);
+}
@@ -0,0 +1,103 @@
+#![feature(const_option, const_trait_impl, const_mut_refs, nonzero_ops)]
+
+use std::{path::{Path, PathBuf}, time::{Duration, Instant}};
+
+use exchange_backtester::prelude::*;
+
+const RUNS: u32 = 5;
+
+/// Re-quotes the limit order at the best bid on every order book snapshot,
+/// so that the trader requests exercise cancels and insertions in the middle of the book
+struct QuotingTrader {
+    next_order_id: u64,
+    size: Size,
+}
+
+impl HandleSubscriptionUpdates for QuotingTrader {
+    fn handle_order_book_snapshot(&mut self,
+                                  _: DateTime,
+                                  _: DateTime,
+                                  ob_snapshot: OrderBookSnapshot) -> Vec<TraderRequest>
+    {
+        let (best_bid, _) = match ob_snapshot.bids.first() {
+            Some(level) => { *level }
+            None => { return vec![]; }
+        };
+        let mut requests = vec![TraderRequest::CancelLimitOrder(OrderID(self.next_order_id))];
+        self.next_order_id += 1;
+        requests.push(
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(self.next_order_id), self.size, Direction::Buy, best_bid)
+            )
+        );
+        requests
+    }
+    fn handle_trade_info_update(&mut self, _: DateTime, _: DateTime, _: Vec<OrderBookDiff>) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_wakeup(&mut self, _: DateTime) -> Vec<TraderRequest> {
+        vec![]
+    }
+}
+
+impl const Trader for QuotingTrader {
+    fn exchange_to_trader_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn trader_to_exchange_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn handle_exchange_reply(&mut self, _: DateTime, _: DateTime, _: ExchangeReply) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn exchange_open(&mut self, _: DateTime, _: DateTime) {}
+    fn exchange_closed(&mut self, _: DateTime, _: DateTime) {}
+}
+
+fn get_next_open_dt(datetime: DateTime) -> DateTime { datetime.date().and_hms(7, 0, 0) }
+
+fn get_next_close_dt(datetime: DateTime) -> DateTime { datetime.date().and_hms(23, 50, 0) }
+
+fn get_input(path: &PathBuf) -> StaticInput {
+    StaticInput::new()
+        .with_ob_diff_history_files(path.join("PRL.txt").to_str().unwrap())
+        .with_trade_history_files(path.join("TRD.txt").to_str().unwrap())
+}
+
+fn history_replay(path: &PathBuf) {
+    let input = get_input(path);
+    let mut trader = examples::VoidTrader;
+    ExchangeBuilder::new::<false>(HistoryParser::new(&input), &mut trader, get_next_open_dt, get_next_close_dt)
+        .run_trades()
+}
+
+fn quoting_trader(path: &PathBuf) {
+    let input = get_input(path);
+    let mut trader = QuotingTrader { next_order_id: 0, size: Size(1) };
+    ExchangeBuilder::new::<false>(HistoryParser::new(&input), &mut trader, get_next_open_dt, get_next_close_dt)
+        .ob_level_subscription_depth(lags::constant::ONE_SECOND, 1)
+        .run_trades()
+}
+
+fn bench(name: &str, path: &PathBuf, run: fn(&PathBuf)) {
+    let mut elapsed = Vec::with_capacity(RUNS as usize);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        run(path);
+        elapsed.push(start.elapsed());
+    }
+    let total: Duration = elapsed.iter().sum();
+    println!(
+        "{:<16} mean: {:>10.3?}  min: {:>10.3?}  max: {:>10.3?}",
+        name,
+        total / RUNS,
+        elapsed.iter().min().unwrap(),
+        elapsed.iter().max().unwrap()
+    )
+}
+
+fn main() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join("integration")
+        .join("two_days");
+    bench("history_replay", &path, history_replay);
+    bench("quoting_trader", &path, quoting_trader);
+}
@@ -0,0 +1,119 @@
+use std::cmp::{min, Ordering, Reverse};
+
+use crate::types::{Direction, Duration, InstrumentID, Price, Size};
+
+/// Call phase during which the orders accumulate without matching and get executed at the single uncrossing price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CallAuction {
+    pub duration: Duration,
+    /// Interval of the indicative price updates published to the trader, none if they are not published
+    pub indicative_interval: Option<Duration>,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum AuctionPhase {
+    Opening,
+    /// Call phase of the trading halt
+    Reopening,
+    Closing,
+}
+
+/// Result of the uncrossing if the auction ended at the moment
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub struct AuctionInfo {
+    pub instrument: InstrumentID,
+    pub phase: AuctionPhase,
+    /// None if the orders do not cross
+    pub price: Option<Price>,
+    pub matched_size: Size,
+    /// Volume that would stay unexecuted on the side with the surplus
+    pub imbalance: Size,
+    pub imbalance_side: Option<Direction>,
+}
+
+pub(crate) struct Uncrossing {
+    pub(crate) price: Price,
+    /// Volume of the buy orders that accept the price
+    pub(crate) buy_size: Size,
+    /// Volume of the sell orders that accept the price
+    pub(crate) sell_size: Size,
+}
+
+impl Uncrossing {
+    pub(crate) fn get_matched_size(&self) -> Size { min(self.buy_size, self.sell_size) }
+
+    pub(crate) fn to_auction_info(uncrossing: Option<Uncrossing>, instrument: InstrumentID, phase: AuctionPhase) -> AuctionInfo {
+        match uncrossing {
+            Some(uncrossing) => {
+                let matched_size = uncrossing.get_matched_size();
+                let imbalance_side = match uncrossing.buy_size.cmp(&uncrossing.sell_size) {
+                    Ordering::Greater => { Some(Direction::Buy) }
+                    Ordering::Less => { Some(Direction::Sell) }
+                    Ordering::Equal => { None }
+                };
+                AuctionInfo {
+                    instrument,
+                    phase,
+                    price: Some(uncrossing.price),
+                    matched_size,
+                    imbalance: uncrossing.buy_size + uncrossing.sell_size - matched_size - matched_size,
+                    imbalance_side,
+                }
+            }
+            None => {
+                AuctionInfo { instrument, phase, price: None, matched_size: Size(0), imbalance: Size(0), imbalance_side: None }
+            }
+        }
+    }
+}
+
+/// Chooses the price that maximizes the executed volume, then minimizes the imbalance, then is the closest to the
+/// reference price, then is the lowest one. Limit price levels are given with their sizes from the best one,
+/// and the market orders accept any price. Returns None if nothing can be executed
+pub(crate) fn get_uncrossing(bids: &[(Price, Size)],
+                             asks: &[(Price, Size)],
+                             market_buy_size: Size,
+                             market_sell_size: Size,
+                             reference_price: Option<Price>) -> Option<Uncrossing> {
+    let mut candidates: Vec<Price> = bids.iter().chain(asks.iter()).map(|(price, _)| *price).collect();
+    // Market orders alone are executed at the reference price
+    if candidates.is_empty() {
+        candidates.extend(reference_price);
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    // Buy volume at the candidate price includes the bids at this price or higher
+    let mut buy_sizes = vec![market_buy_size; candidates.len()];
+    let mut accumulated = market_buy_size;
+    let mut bids_iter = bids.iter().peekable();
+    for (index, price) in candidates.iter().enumerate().rev() {
+        while let Some((_, size)) = bids_iter.next_if(|(bid_price, _)| bid_price >= price) {
+            accumulated += *size;
+        }
+        buy_sizes[index] = accumulated;
+    }
+    // Sell volume at the candidate price includes the asks at this price or lower
+    let mut sell_sizes = vec![market_sell_size; candidates.len()];
+    let mut accumulated = market_sell_size;
+    let mut asks_iter = asks.iter().peekable();
+    for (index, price) in candidates.iter().enumerate() {
+        while let Some((_, size)) = asks_iter.next_if(|(ask_price, _)| ask_price <= price) {
+            accumulated += *size;
+        }
+        sell_sizes[index] = accumulated;
+    }
+
+    candidates.into_iter()
+        .zip(buy_sizes.into_iter().zip(sell_sizes))
+        .map(|(price, (buy_size, sell_size))| Uncrossing { price, buy_size, sell_size })
+        .filter(|uncrossing| uncrossing.get_matched_size() != Size(0))
+        .min_by_key(
+            |uncrossing| (
+                Reverse(uncrossing.get_matched_size()),
+                (uncrossing.buy_size - uncrossing.sell_size).0.abs(),
+                reference_price.map_or(0, |reference_price| (uncrossing.price.0 - reference_price.0).abs()),
+                uncrossing.price,
+            )
+        )
+}
@@ -1,11 +1,19 @@
-use std::collections::{HashMap, HashSet, LinkedList};
+use std::collections::HashMap;
 
-use crate::exchange::{trades::history::TradesHistory, types::{EventQueue, OrderBookLevel}};
+use crate::auction::CallAuction;
+use crate::exchange::types::{EventQueue, ExchangePolicies, HostedTrader, InstrumentBooks, TraderExecution};
+use crate::fees::interface::FeeSchedule;
+use crate::halt::{PriceBand, TradingHalt};
 use crate::history::parser::EventProcessor;
+use crate::impact::MarketImpact;
 use crate::lags::interface::NanoSecondGenerator;
-use crate::order::MarketOrder;
+use crate::matching::interface::MatchingPolicy;
+use crate::order::{GroupOrder, MarketOrderPolicy, SelfTradePrevention};
+use crate::queue_position::interface::QueuePositionModel;
+use crate::risk::{LossLimits, RiskLimits};
+use crate::throttle::{RateLimit, ThrottlePolicy};
 use crate::trader::Trader;
-use crate::types::{DateTime, Direction, OrderID, Price, StdRng};
+use crate::types::{DateTime, InstrumentID, OrderID, StdRng, TraderID};
 
 pub(crate) mod interface;
 pub(crate) mod types;
@@ -13,11 +21,14 @@ pub(crate) mod trades;
 
 pub struct Exchange<
     'a,
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     ObLagGen: NanoSecondGenerator,
     TrdLagGen: NanoSecondGenerator,
     WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
     const OB_SUBSCRIPTION: bool,
@@ -28,17 +39,31 @@ pub struct Exchange<
     event_queue: EventQueue,
     event_processor: E,
     has_history_events_in_queue: bool,
-    history_order_ids: HashSet<OrderID>,
 
-    bids: LinkedList<OrderBookLevel>,
-    asks: LinkedList<OrderBookLevel>,
+    books: InstrumentBooks,
 
-    trader: &'a mut T,
-    trader_pending_market_orders: LinkedList<MarketOrder>,
-    trader_pending_limit_orders: HashMap<OrderID, (Price, Direction)>,
-    trader_submitted_orders: HashSet<OrderID>,
-
-    executed_trades: TradesHistory,
+    // Hosted traders and the owners of the orders with the order IDs
+    // in the owner namespaces by the exchange order IDs, which are unique among all the traders
+    traders: Vec<HostedTrader<'a, T>>,
+    order_owners: HashMap<OrderID, (TraderID, OrderID)>,
+    trader_oco_orders: HashMap<OrderID, OrderID>,
+    trader_bracket_orders: HashMap<OrderID, (GroupOrder, GroupOrder)>,
+    trader_submitted_orders: HashMap<OrderID, InstrumentID>,
+    // Executions of the trader orders since the order groups and the limits have been last enforced
+    trader_executions: Vec<TraderExecution>,
+    market_order_policy: MarketOrderPolicy,
+    self_trade_prevention: Option<SelfTradePrevention>,
+    market_impact: Option<MarketImpact>,
+    opening_auction: Option<CallAuction>,
+    closing_auction: Option<CallAuction>,
+    price_band: Option<PriceBand>,
+    trading_halts: Vec<TradingHalt>,
+    // Limits applied to each trader on its own
+    rate_limits: Vec<RateLimit>,
+    throttle_policy: ThrottlePolicy,
+    risk_limits: RiskLimits,
+    loss_limits: Option<LossLimits>,
+    policies: ExchangePolicies<M, Q, F>,
 
     current_dt: DateTime,
     exchange_closed: bool,
@@ -1,5 +1,12 @@
 pub(crate) mod trader;
 pub(crate) mod history;
+pub(crate) mod auction;
+pub(crate) mod halt;
+pub(crate) mod throttle;
+pub(crate) mod risk;
+pub(crate) mod instruments;
+pub(crate) mod traders;
+pub(crate) mod venue;
 
 pub(crate) mod private;
 pub mod public;
\ No newline at end of file
@@ -0,0 +1,277 @@
+use std::cmp::min;
+use std::collections::BTreeMap;
+
+use crate::auction::{AuctionPhase, get_uncrossing, Uncrossing};
+use crate::exchange::{
+    Exchange,
+    interface::trader::remove_market_order,
+    trades::history::OrderBookDiff,
+    types::{Event, EventBody},
+};
+use crate::fees::interface::{FeeSchedule, Liquidity};
+use crate::halt::HaltResumption;
+use crate::history::{parser::EventProcessor, types::OrderOrigin};
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::SubscriptionSchedule;
+use crate::order::{LimitOrder, Order};
+use crate::queue_position::interface::QueuePositionModel;
+use crate::trader::Trader;
+use crate::types::{Direction, Duration, InstrumentID, OrderID, Price, Size};
+
+/// Order taking part in the uncrossing. Market orders have no location in the order book
+struct AuctionParticipant {
+    order_id: OrderID,
+    from: OrderOrigin,
+    location: Option<(Price, usize)>,
+    remaining: Size,
+    executed: Size,
+}
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    /// Starts the call phase. Pending market orders and, in the closing auction, at-the-close orders join it
+    pub(crate) fn start_auction(&mut self, instrument: InstrumentID, phase: AuctionPhase) {
+        let book = &mut self.books[instrument];
+        book.auction_phase = Some(phase);
+        book.trader_auction_market_orders.append(&mut book.trader_pending_market_orders);
+        if phase == AuctionPhase::Closing {
+            book.trader_auction_market_orders.append(&mut book.trader_close_market_orders);
+            for order in std::mem::take(&mut book.trader_close_limit_orders) {
+                self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, order)
+            }
+        }
+        if let Some(interval) = self.get_indicative_interval(instrument, phase) {
+            self.event_queue.push(
+                Event {
+                    datetime: self.current_dt + interval,
+                    body: EventBody::SubscriptionSchedule(SubscriptionSchedule::AuctionInfo(instrument, phase)),
+                }
+            )
+        }
+    }
+
+    pub(crate) fn get_indicative_interval(&self, instrument: InstrumentID, phase: AuctionPhase) -> Option<Duration> {
+        match phase {
+            AuctionPhase::Opening => { self.opening_auction.and_then(|auction| auction.indicative_interval) }
+            AuctionPhase::Reopening => {
+                match self.books[instrument].halt.map(|halt| halt.resumption) {
+                    Some(HaltResumption::Auction { indicative_interval }) => { indicative_interval }
+                    _ => { None }
+                }
+            }
+            AuctionPhase::Closing => { self.closing_auction.and_then(|auction| auction.indicative_interval) }
+        }
+    }
+
+    /// Uncrosses the orders and ends the call phase. Unexecuted market orders go on to the continuous trading
+    /// after the opening and reopening auctions and stay pending until the exchange closes after the closing one
+    pub(crate) fn finish_auction(&mut self, instrument: InstrumentID) {
+        self.uncross_auction(instrument);
+        let phase = self.books[instrument].auction_phase.take();
+        if phase == Some(AuctionPhase::Reopening) {
+            self.finish_halt(instrument)
+        }
+        let market_orders = std::mem::take(&mut self.books[instrument].trader_auction_market_orders);
+        match phase {
+            Some(AuctionPhase::Opening | AuctionPhase::Reopening) => {
+                for order in market_orders {
+                    self.insert_trader_market_order(instrument, order)
+                }
+            }
+            _ => {
+                let mut market_orders = market_orders;
+                self.books[instrument].trader_pending_market_orders.append(&mut market_orders)
+            }
+        }
+    }
+
+    pub(crate) fn get_auction_uncrossing(&self, instrument: InstrumentID) -> Option<Uncrossing> {
+        let get_levels = |direction: Direction| {
+            let side = match direction {
+                Direction::Buy => { &self.books[instrument].bids }
+                Direction::Sell => { &self.books[instrument].asks }
+            };
+            side.iter()
+                .map(|level| (level.price, level.iter().map(|entry| entry.size + entry.hidden_size).sum()))
+                .collect::<Vec<_>>()
+        };
+        let get_market_size = |direction: Direction| {
+            self.books[instrument].trader_auction_market_orders.iter()
+                .filter(|order| order.get_order_direction() == direction)
+                .map(|order| order.get_order_size())
+                .sum()
+        };
+        get_uncrossing(
+            &get_levels(Direction::Buy),
+            &get_levels(Direction::Sell),
+            get_market_size(Direction::Buy),
+            get_market_size(Direction::Sell),
+            self.books[instrument].traded_price_range.get_last_price(),
+        )
+    }
+
+    /// Orders accepting the uncrossing price in the priority order: market orders first,
+    /// then limit orders from the best price in the queue order
+    fn get_auction_participants(&self, instrument: InstrumentID, direction: Direction, price: Price) -> Vec<AuctionParticipant> {
+        let side = match direction {
+            Direction::Buy => { &self.books[instrument].bids }
+            Direction::Sell => { &self.books[instrument].asks }
+        };
+        let market_orders = self.books[instrument].trader_auction_market_orders.iter()
+            .filter(|order| order.get_order_direction() == direction)
+            .map(
+                |order| AuctionParticipant {
+                    order_id: order.get_order_id(),
+                    from: OrderOrigin::Trader,
+                    location: None,
+                    remaining: order.get_order_size(),
+                    executed: Size(0),
+                }
+            );
+        let limit_orders = side.iter()
+            .take_while(
+                |level| match direction {
+                    Direction::Buy => { level.price >= price }
+                    Direction::Sell => { level.price <= price }
+                }
+            )
+            .flat_map(
+                |level| level.entries()
+                    .map(
+                        move |(slot, entry)| AuctionParticipant {
+                            order_id: entry.order_id,
+                            from: entry.from,
+                            location: Some((level.price, slot)),
+                            remaining: entry.size + entry.hidden_size,
+                            executed: Size(0),
+                        }
+                    )
+            );
+        market_orders.chain(limit_orders).collect()
+    }
+
+    /// Executes the crossing orders at the single uncrossing price. Buy and sell orders are paired in the priority
+    /// order, and the history orders paired with each other are only decreased if the trades update the order book
+    fn uncross_auction(&mut self, instrument: InstrumentID) {
+        let uncrossing = match self.get_auction_uncrossing(instrument) {
+            Some(uncrossing) => { uncrossing }
+            None => { return; }
+        };
+        let price = uncrossing.price;
+        let mut buys = self.get_auction_participants(instrument, Direction::Buy, price);
+        let mut sells = self.get_auction_participants(instrument, Direction::Sell, price);
+        let (mut i, mut j) = (0, 0);
+        while i < buys.len() && j < sells.len() {
+            let size = min(buys[i].remaining, sells[j].remaining);
+            let involves_trader = buys[i].from == OrderOrigin::Trader || sells[j].from == OrderOrigin::Trader;
+            for participant in [&mut buys[i], &mut sells[j]] {
+                participant.remaining -= size;
+                if involves_trader || TRD_UPDATES_OB {
+                    participant.executed += size;
+                }
+            }
+            if buys[i].remaining == Size(0) {
+                i += 1;
+            }
+            if sells[j].remaining == Size(0) {
+                j += 1;
+            }
+        }
+
+        let mut level_executions: BTreeMap<(Direction, Price), Vec<(usize, Size)>> = BTreeMap::new();
+        for (direction, participants) in [(Direction::Buy, buys), (Direction::Sell, sells)] {
+            for participant in participants.into_iter().filter(|participant| participant.executed != Size(0)) {
+                match participant.location {
+                    Some((level_price, slot)) => {
+                        level_executions.entry((direction, level_price)).or_default().push((slot, participant.executed))
+                    }
+                    None => { self.execute_auction_market_order(instrument, participant.order_id, participant.executed, price) }
+                }
+            }
+        }
+        for ((direction, level_price), executions) in level_executions {
+            self.execute_auction_level(instrument, direction, level_price, executions, price)
+        }
+
+        self.books[instrument].traded_price_range.update(price);
+        if TRD_SUBSCRIPTION {
+            self.books[instrument].executed_trades.push(OrderBookDiff {
+                datetime: self.current_dt,
+                instrument,
+                price,
+                size: uncrossing.get_matched_size(),
+                direction: if uncrossing.buy_size >= uncrossing.sell_size { Direction::Buy } else { Direction::Sell },
+            })
+        }
+    }
+
+    fn execute_auction_market_order(&mut self, instrument: InstrumentID, order_id: OrderID, size: Size, price: Price) {
+        let is_executed = match self.books[instrument].trader_auction_market_orders.iter_mut().find(|order| order.get_order_id() == order_id) {
+            Some(order) => {
+                *order.mut_order_size() -= size;
+                order.get_order_size() == Size(0)
+            }
+            None => { return; }
+        };
+        if is_executed {
+            remove_market_order(&mut self.books[instrument].trader_auction_market_orders, |order| order.get_order_id() == order_id);
+        }
+        self.report_trader_execution(instrument, order_id, size, price, Liquidity::Auction, is_executed);
+    }
+
+    fn execute_auction_level(&mut self,
+                             instrument: InstrumentID,
+                             direction: Direction,
+                             level_price: Price,
+                             executions: Vec<(usize, Size)>,
+                             price: Price) {
+        let book = &mut self.books[instrument];
+        let side = match direction {
+            Direction::Buy => { &mut book.bids }
+            Direction::Sell => { &mut book.asks }
+        };
+        let level = match side.get_level_mut(level_price) {
+            Some(level) => { level }
+            None => { return; }
+        };
+        let mut trader_executions = Vec::new();
+        for (slot, size) in executions {
+            let entry = level.get_mut(slot);
+            let visible_size = min(entry.size, size);
+            entry.size -= visible_size;
+            entry.hidden_size -= size - visible_size;
+            if entry.size == Size(0) && entry.hidden_size != Size(0) {
+                entry.refill()
+            }
+            if entry.from == OrderOrigin::Trader {
+                if entry.size == Size(0) {
+                    book.trader_pending_limit_orders.remove(&entry.order_id);
+                }
+                trader_executions.push((entry.order_id, size, entry.size == Size(0)));
+            }
+            if entry.size == Size(0) {
+                level.remove(slot);
+            }
+        }
+        side.remove_level_if_empty(level_price);
+        for (order_id, size, is_executed) in trader_executions {
+            self.report_trader_execution(instrument, order_id, size, price, Liquidity::Auction, is_executed)
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::cmp::min;
+
+use crate::auction::AuctionPhase;
+use crate::exchange::{Exchange, types::{Event, EventBody}};
+use crate::fees::interface::FeeSchedule;
+use crate::halt::{HaltResumption, TradingHalt};
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::ExchangeReply;
+use crate::queue_position::interface::QueuePositionModel;
+use crate::trader::Trader;
+use crate::types::{InstrumentID, Price};
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    /// Halts the trading if the prices traded at the moment trigger any of the configured halts
+    pub(crate) fn check_trading_halts(&mut self, instrument: InstrumentID) {
+        if let Some(traded_range) = self.books[instrument].traded_price_range.get_range() {
+            self.check_traded_range(instrument, traded_range);
+        }
+    }
+
+    /// Halts the trading if the traded price range triggers any of the configured halts.
+    /// Returns whether the halt has started
+    pub(crate) fn check_traded_range(&mut self, instrument: InstrumentID, traded_range: (Price, Price)) -> bool {
+        if self.trading_halts.is_empty()
+            || self.exchange_closed
+            || self.books[instrument].auction_phase.is_some()
+            || self.books[instrument].halt.is_some()
+        {
+            return false;
+        }
+        match self.books[instrument].price_monitor.update(&self.trading_halts, self.current_dt, traded_range) {
+            Some(halt) => {
+                self.start_halt(instrument, halt);
+                true
+            }
+            None => { false }
+        }
+    }
+
+    fn start_halt(&mut self, instrument: InstrumentID, halt: TradingHalt) {
+        if DEBUG {
+            eprintln!("{} :: start_halt :: {:?}", self.current_dt, halt)
+        }
+        self.books[instrument].halt = Some(halt);
+        let reply = ExchangeReply::TradingHalted(instrument, halt.get_reason());
+        self.event_queue.schedule_reply_for_trader(reply);
+        if let HaltResumption::Auction { .. } = halt.resumption {
+            self.start_auction(instrument, AuctionPhase::Reopening)
+        }
+        self.event_queue.push(
+            Event {
+                datetime: min(self.current_dt + halt.duration, (self.get_next_close_dt)(self.current_dt)),
+                body: EventBody::TradingResumption(instrument),
+            }
+        )
+    }
+
+    /// Called when the reopening call phase is finished or, without it, when the halt is over
+    pub(crate) fn finish_halt(&mut self, instrument: InstrumentID) {
+        if self.books[instrument].halt.take().is_some() {
+            self.event_queue.schedule_reply_for_trader(ExchangeReply::TradingResumed(instrument))
+        }
+    }
+
+    /// Order placements are discarded during the halts resuming with the continuous trading
+    pub(crate) fn are_placements_halted(&self, instrument: InstrumentID) -> bool {
+        matches!(self.books[instrument].halt, Some(TradingHalt { resumption: HaltResumption::Continuous, .. }))
+    }
+
+    /// Orders rest without matching during the auction call phases and the halts
+    pub(crate) fn is_matching_stopped(&self, instrument: InstrumentID) -> bool {
+        self.books[instrument].auction_phase.is_some() || self.books[instrument].halt.is_some()
+    }
+
+    /// Limit prices are accepted if there is no price band or no trade to refer to yet
+    pub(crate) fn is_within_price_band(&self, instrument: InstrumentID, price: Price) -> bool {
+        match (self.price_band, self.books[instrument].traded_price_range.get_last_price()) {
+            (Some(band), Some(reference_price)) => { band.contains(reference_price, price) }
+            _ => { true }
+        }
+    }
+}
@@ -1,12 +1,18 @@
-use crate::exchange::{Exchange, interface::private::AggressiveOrderType};
+use std::cmp::{max, min};
+
+use crate::exchange::{Exchange, interface::private::AggressiveOrderType, types::{Event, EventBody}};
+use crate::fees::interface::FeeSchedule;
 use crate::history::{
     parser::EventProcessor,
     types::{HistoryEventBody, OrderOrigin},
 };
+use crate::impact::MarketImpact;
 use crate::lags::interface::NanoSecondGenerator;
-use crate::order::{LimitOrder, Order};
+use crate::matching::interface::MatchingPolicy;
+use crate::order::{LimitOrder, Order, TimeInForce};
+use crate::queue_position::interface::QueuePositionModel;
 use crate::trader::Trader;
-use crate::types::{Direction, OrderID, Price, Size};
+use crate::types::{Direction, InstrumentID, OrderID, Price, Size};
 
 struct TRDummyOrder {
     size: Size,
@@ -18,31 +24,46 @@ impl const Order for TRDummyOrder {
     fn get_order_size(&self) -> Size { self.size }
     fn mut_order_size(&mut self) -> &mut Size { &mut self.size }
     fn get_order_direction(&self) -> Direction { self.direction }
+    fn get_time_in_force(&self) -> TimeInForce { unreachable!() }
 }
 
 impl<
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     ObLagGen: NanoSecondGenerator,
     TrdLagGen: NanoSecondGenerator,
     WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
     const OB_SUBSCRIPTION: bool,
     const TRD_SUBSCRIPTION: bool,
     const WAKEUP_SUBSCRIPTION: bool
 >
-Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
 {
     pub(crate)
-    fn handle_history_event(&mut self, event: HistoryEventBody)
+    fn handle_history_event(&mut self, instrument: InstrumentID, event: HistoryEventBody)
     {
         match event {
+            _ if !self.books.contains(instrument) => {
+                if DEBUG {
+                    eprintln!(
+                        "{} :: \
+                        handle_history_event :: \
+                        Instrument has not been added to the exchange, skipping its history event: {:?}",
+                        self.current_dt,
+                        instrument
+                    )
+                }
+            }
             HistoryEventBody::OrderBookDiff(size, direction, price, order_id) => {
-                self.handle_ob_diff_event(size, direction, price, order_id)
+                self.handle_ob_diff_event(instrument, size, direction, price, order_id)
             }
             HistoryEventBody::Trade(size, direction) => {
-                self.handle_trd_event(size, direction)
+                self.handle_trd_event(instrument, size, direction)
             }
         }
         if let Some(event) = self.event_processor.yield_next_event() {
@@ -52,89 +73,75 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
         }
     }
 
-    fn handle_ob_diff_event(&mut self, size: Size, direction: Direction, price: Price, order_id: OrderID)
+    fn handle_ob_diff_event(&mut self, instrument: InstrumentID, size: Size, direction: Direction, price: Price, order_id: OrderID)
     {
-        if size == Size(0) {
-            self.remove_ob_entry(direction, price, order_id)
-        } else if self.history_order_ids.contains(&order_id) {
-            self.update_traded_ob_entry(size, direction, price, order_id)
+        if let Some(consumed_order) = self.books[instrument].consumed_history_orders.get_mut(&order_id) {
+            consumed_order.history_size = size;
+            let filled = self.books[instrument].history_fills.remove(&order_id).unwrap_or_default();
+            self.apply_consumed_order(instrument, order_id, direction, price, filled)
+        } else if size == Size(0) {
+            self.remove_ob_entry(instrument, order_id)
+        } else if self.books[instrument].history_order_locations.contains_key(&order_id) {
+            self.update_traded_ob_entry(instrument, size, direction, price, order_id)
         } else {
             self.insert_limit_order::<LimitOrder, { OrderOrigin::History }>(
+                instrument,
                 LimitOrder::new(order_id, size, direction, price)
             );
-            self.history_order_ids.insert(order_id);
+            self.books[instrument].history_order_locations.insert(order_id, (direction, price));
         }
     }
 
-    fn remove_ob_entry(&mut self, direction: Direction, price: Price, order_id: OrderID)
+    fn remove_ob_entry(&mut self, instrument: InstrumentID, order_id: OrderID)
     {
-        let mut side_cursor = match direction {
-            Direction::Buy => { self.bids.cursor_front_mut() }
-            Direction::Sell => { self.asks.cursor_front_mut() }
-        };
-
-        while let Some(ob_level) = side_cursor.current()
-        {
-            if ob_level.price != price {
-                side_cursor.move_next();
-                continue;
-            }
-            let mut level_cursor = ob_level.queue.cursor_front_mut();
-            while let Some(limit_order) = level_cursor.current()
-            {
-                if limit_order.order_id == order_id && limit_order.from == OrderOrigin::History {
-                    break;
-                }
-                level_cursor.move_next();
-            }
-            if let None = level_cursor.remove_current() {
+        let (direction, price) = match self.books[instrument].history_order_locations.remove(&order_id) {
+            Some(location) => { location }
+            None => {
                 if DEBUG {
                     eprintln!(
                         "{} :: \
                         remove_ob_entry :: ERROR in case of non-trading Trader :: \
-                        Order with such ID {:?} does not exist at the OB level with corresponding price: {:?}",
+                        History order index does not contain such ID: {:?}",
                         self.current_dt,
-                        order_id,
-                        price
+                        order_id
                     )
                 }
-                break;
-            }
-            if ob_level.queue.is_empty() {
-                side_cursor.remove_current();
+                return;
             }
-            if !self.history_order_ids.remove(&order_id) && DEBUG {
-                eprintln!(
-                    "{} :: \
-                    remove_ob_entry :: ERROR in case of non-trading Trader :: \
-                    History order HashSet does not contain such ID: {:?}",
-                    self.current_dt,
-                    order_id
-                )
+        };
+        let filled = self.books[instrument].history_fills.remove(&order_id).unwrap_or_default();
+        let side = match direction {
+            Direction::Buy => { &mut self.books[instrument].bids }
+            Direction::Sell => { &mut self.books[instrument].asks }
+        };
+        let is_removed = match side.get_level_mut(price) {
+            Some(ob_level) => {
+                ob_level.resize_history_order(order_id, Size(0), filled, &mut self.policies.queue_position, &mut self.rng)
             }
-            return;
-        }
-        if DEBUG {
+            None => { false }
+        };
+        if is_removed {
+            side.remove_level_if_empty(price)
+        } else if DEBUG {
             eprintln!(
-                "{} :: remove_ob_entry :: ERROR in case of non-trading Trader \
-                :: History order has not been deleted: {:?}",
+                "{} :: \
+                remove_ob_entry :: ERROR in case of non-trading Trader :: \
+                Order with such ID {:?} does not exist at the OB level with corresponding price: {:?}",
                 self.current_dt,
-                order_id
+                order_id,
+                price
             )
         }
     }
 
-    fn update_traded_ob_entry(&mut self, size: Size, direction: Direction, price: Price, order_id: OrderID)
+    fn update_traded_ob_entry(&mut self, instrument: InstrumentID, size: Size, direction: Direction, price: Price, order_id: OrderID)
     {
+        let filled = self.books[instrument].history_fills.remove(&order_id).unwrap_or_default();
         let side = match direction {
-            Direction::Buy => { &mut self.bids }
-            Direction::Sell => { &mut self.asks }
+            Direction::Buy => { &mut self.books[instrument].bids }
+            Direction::Sell => { &mut self.books[instrument].asks }
         };
-        let ob_level = match side
-            .iter_mut()
-            .skip_while(|level| level.price != price)
-            .next()
-        {
+        let ob_level = match side.get_level_mut(price) {
             Some(ob_level) => { ob_level }
             None => {
                 if DEBUG {
@@ -149,33 +156,98 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                 return;
             }
         };
-        let order = match ob_level.queue
-            .iter_mut()
-            .skip_while(|order|
-                order.order_id != order_id || order.from != OrderOrigin::History
+        if !ob_level.resize_history_order(order_id, size, filled, &mut self.policies.queue_position, &mut self.rng) && DEBUG {
+            eprintln!(
+                "{} \
+                 :: update_traded_ob_entry :: ERROR in case of non-trading Trader \
+                 :: OB level does not contain history order with such ID: {:?}",
+                self.current_dt,
+                order_id
+            );
+        }
+    }
+
+    pub(crate) fn refill_consumed_order(&mut self, instrument: InstrumentID, order_id: OrderID) {
+        let (refill_size, refill_interval) = match self.market_impact {
+            Some(MarketImpact::Resiliency { refill_size, refill_interval }) => { (refill_size, refill_interval) }
+            _ => { return; }
+        };
+        let consumed_order = match self.books[instrument].consumed_history_orders.get_mut(&order_id) {
+            // Refills scheduled before the last consumption are skipped
+            Some(consumed_order) if consumed_order.consumed_dt + refill_interval == self.current_dt => { consumed_order }
+            _ => { return; }
+        };
+        consumed_order.consumed -= min(consumed_order.consumed, refill_size);
+        consumed_order.consumed_dt = self.current_dt;
+        let (direction, price) = (consumed_order.direction, consumed_order.price);
+        if consumed_order.consumed != Size(0) {
+            self.event_queue.push(
+                Event {
+                    datetime: self.current_dt + refill_interval,
+                    body: EventBody::HistoryOrderRefill(instrument, order_id),
+                }
             )
-            .next()
-        {
-            Some(order) => { order }
-            None => {
-                if DEBUG {
-                    eprintln!(
-                        "{} \
-                         :: update_traded_ob_entry :: ERROR in case of non-trading Trader \
-                         :: OB level does not contain history order with such ID: {:?}",
-                        self.current_dt,
-                        order_id
-                    );
+        }
+        self.apply_consumed_order(instrument, order_id, direction, price, Size(0))
+    }
+
+    /// Sets the size of the consumed history order in the order book to its history size
+    /// reduced by the consumed volume that has not recovered yet. `filled` is the volume of the order
+    /// executed by the history trades since its last order book diff. The order that has moved to another
+    /// price joins the back of the queue there
+    fn apply_consumed_order(&mut self,
+                            instrument: InstrumentID,
+                            order_id: OrderID,
+                            direction: Direction,
+                            price: Price,
+                            filled: Size) {
+        let book = &mut self.books[instrument];
+        let (impact, consumed_order) = match (self.market_impact, book.consumed_history_orders.get_mut(&order_id)) {
+            (Some(impact), Some(consumed_order)) => { (impact, consumed_order) }
+            _ => { return; }
+        };
+        let unrecovered = impact.get_unrecovered(consumed_order.consumed, self.current_dt - consumed_order.consumed_dt);
+        let (old_direction, old_price) = (consumed_order.direction, consumed_order.price);
+        let is_moved = (old_direction, old_price) != (direction, price);
+        (consumed_order.direction, consumed_order.price) = (direction, price);
+        let history_size = consumed_order.history_size;
+        if history_size == Size(0) || unrecovered == Size(0) {
+            book.consumed_history_orders.remove(&order_id);
+        }
+        if history_size == Size(0) {
+            book.history_order_locations.remove(&order_id);
+        } else if is_moved {
+            book.history_order_locations.insert(order_id, (direction, price));
+        }
+        let size = max(history_size - unrecovered, Size(0));
+
+        let side = match old_direction {
+            Direction::Buy => { &mut book.bids }
+            Direction::Sell => { &mut book.asks }
+        };
+        if let Some(ob_level) = side.get_level_mut(old_price) {
+            let new_size = if is_moved { Size(0) } else { size };
+            if ob_level.resize_history_order(order_id, new_size, filled, &mut self.policies.queue_position, &mut self.rng) {
+                side.remove_level_if_empty(old_price);
+                if !is_moved {
+                    return;
                 }
-                return;
             }
-        };
-        order.size = size
+        }
+        // The order has been consumed in full or has moved
+        if size != Size(0) {
+            self.insert_limit_order::<LimitOrder, { OrderOrigin::History }>(instrument, LimitOrder::new(order_id, size, direction, price))
+        }
     }
 
-    fn handle_trd_event(&mut self, size: Size, direction: Direction)
+    fn handle_trd_event(&mut self, instrument: InstrumentID, size: Size, direction: Direction)
     {
+        // The exchange uncrosses the auction orders itself, and nothing is matched during the halts
+        if self.is_matching_stopped(instrument) {
+            return;
+        }
         self.insert_aggressive_order::<TRDummyOrder, { AggressiveOrderType::HistoryMarketOrder }>(
+            instrument,
             TRDummyOrder { size, direction }
         )
     }
@@ -0,0 +1,65 @@
+use crate::exchange::Exchange;
+use crate::fees::interface::FeeSchedule;
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::{DiscardingReason, ExchangeReply, TraderRequest};
+use crate::order::Order;
+use crate::queue_position::interface::QueuePositionModel;
+use crate::trader::Trader;
+use crate::types::{Direction, OrderID, Price, TraderID};
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    /// Discards the placements for the instruments not added to the exchange. Returns whether the request
+    /// should be handled
+    pub(crate) fn check_instrument(&mut self, request: &TraderRequest) -> bool {
+        let (order_id, instrument) = match request {
+            TraderRequest::PlaceLimitOrder(order) => { (order.get_order_id(), order.get_instrument()) }
+            TraderRequest::PlaceMarketOrder(order) => { (order.get_order_id(), order.get_instrument()) }
+            TraderRequest::PlaceStopOrder(order) => { (order.get_order_id(), order.get_instrument()) }
+            _ => { return true; }
+        };
+        if !self.books.contains(instrument) {
+            let reply = ExchangeReply::OrderPlacementDiscarded(order_id, DiscardingReason::UnknownInstrument);
+            self.event_queue.schedule_reply_for_trader(reply);
+            return false;
+        }
+        true
+    }
+
+    pub(crate) fn is_trader_order_active(&self, order_id: OrderID) -> bool {
+        self.books.iter().any(|book| book.has_active_trader_order(order_id))
+    }
+
+    pub(crate) fn get_open_trader_order_count(&self, trader: TraderID) -> usize {
+        self.books.iter()
+            .map(|book| book.get_open_trader_order_count(|order_id| self.get_order_owner(order_id) == Some(trader)))
+            .sum()
+    }
+
+    /// Returns the IDs of the trader orders of all the instruments on the given side, if any, and of the limit orders
+    /// in the given price range, if any
+    pub(crate) fn get_active_trader_order_ids(&self,
+                                              side: Option<Direction>,
+                                              price_range: Option<(Price, Price)>) -> Vec<OrderID> {
+        self.books.iter()
+            .flat_map(|book| book.get_active_trader_order_ids(side, price_range))
+            .collect()
+    }
+}
@@ -1,28 +1,67 @@
-use std::{cmp::Ordering, collections::LinkedList, iter::FromIterator};
+use std::{cmp::{max, min, Ordering}, collections::{HashMap, HashSet, LinkedList}};
 
 use AggressiveOrderType::*;
 
+use crate::auction::{AuctionPhase, Uncrossing};
 use crate::exchange::{
     Exchange,
+    interface::trader::remove_market_order,
     trades::history::OrderBookDiff,
-    types::{Event, EventBody, OrderBookEntry, OrderBookLevel},
+    types::{ConsumedOrder, Event, EventBody, OrderBookEntry, OrderBookLevel, OrderBookSide, TraderExecution},
 };
+use crate::fees::interface::{Fee, FeeSchedule, Liquidity};
 use crate::history::{parser::EventProcessor, types::OrderOrigin};
+use crate::impact::MarketImpact;
 use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
 use crate::message::{
     CancellationReason,
     DiscardingReason::ZeroSize,
-    ExchangeReply::{OrderCancelled, OrderExecuted, OrderPartiallyExecuted, OrderPlacementDiscarded},
+    DiscardingReason,
+    ExchangeReply::{
+        CannotModifyOrder,
+        OrderCancelled,
+        OrderConvertedToLimit,
+        OrderExecuted,
+        OrderPartiallyExecuted,
+        OrderPlacementDiscarded,
+        OrderRemainderPending,
+        OrderRepriced,
+    },
     ExchangeReply,
+    InabilityToModifyReason,
     SubscriptionSchedule::{OrderBook, TradeInfo},
     SubscriptionSchedule,
     SubscriptionUpdate,
-    TraderRequest::{CancelLimitOrder, CancelMarketOrder, PlaceLimitOrder, PlaceMarketOrder},
+    TraderRequest::{
+        CancelAll,
+        CancelAllOnSide,
+        CancelInPriceRange,
+        CancelLimitOrder,
+        CancelMarketOrder,
+        CancelStopOrder,
+        ModifyLimitOrder,
+        PlaceBracketOrder,
+        PlaceLimitOrder,
+        PlaceMarketOrder,
+        PlaceOcoOrders,
+        PlaceStopOrder,
+    },
     TraderRequest,
 };
-use crate::order::{MarketOrder, Order, PricedOrder};
+use crate::order::{
+    LimitOrder,
+    MarketOrder,
+    MarketOrderPolicy,
+    Order,
+    PegReference,
+    PricedOrder,
+    SelfTradePrevention,
+    TimeInForce,
+};
+use crate::queue_position::interface::QueuePositionModel;
 use crate::trader::{subscriptions::OrderBookSnapshot, Trader};
-use crate::types::{DateTime, Direction, Duration, Size};
+use crate::types::{DateTime, Direction, Duration, InstrumentID, OrderID, Price, Size, TraderID};
 use crate::utils::ExpectWith;
 
 #[derive(Eq, PartialEq)]
@@ -34,211 +73,495 @@ pub(crate) enum AggressiveOrderType {
 }
 
 impl<
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     ObLagGen: NanoSecondGenerator,
     TrdLagGen: NanoSecondGenerator,
     WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
     const OB_SUBSCRIPTION: bool,
     const TRD_SUBSCRIPTION: bool,
     const WAKEUP_SUBSCRIPTION: bool
 >
-Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
 {
-    fn cleanup<const END_OF_TRADES: bool>(&mut self) {
-        self.history_order_ids.clear();
-        self.bids.clear();
-        self.asks.clear();
+    fn cleanup<const END_OF_TRADES: bool>(&mut self, instrument: InstrumentID) {
+        let book = &mut self.books[instrument];
+        book.history_order_locations.clear();
+        book.consumed_history_orders.clear();
+        book.history_fills.clear();
 
         if END_OF_TRADES {
-            self.trader_submitted_orders.clear();
-            for id in self.trader_pending_market_orders.iter()
+            // Trader orders with GoodTillCancel or GoodTillTime time in force are suspended until the next session
+            for (side, direction) in [(&book.bids, Direction::Buy), (&book.asks, Direction::Sell)] {
+                for level in side.iter() {
+                    for order in level.iter().filter(|order| order.from == OrderOrigin::Trader) {
+                        match book.trader_pending_limit_orders.get(&order.order_id) {
+                            Some((_, _, time_in_force)) if time_in_force.outlives_session() => {
+                                book.trader_suspended_limit_orders.push(
+                                    order.to_limit_order(order.size + order.hidden_size, direction, level.price, *time_in_force)
+                                )
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            book.bids.clear();
+            book.asks.clear();
+
+            let (mut suspended, cancelled): (LinkedList<_>, LinkedList<_>) = std::mem::take(&mut book.trader_pending_market_orders)
+                .into_iter()
+                .partition(|order| order.get_time_in_force().outlives_session());
+            book.trader_suspended_market_orders.append(&mut suspended);
+            let (stop_orders, cancelled_stop_orders): (Vec<_>, Vec<_>) = std::mem::take(&mut book.trader_pending_stop_orders)
+                .into_iter()
+                .partition(|order| order.get_time_in_force().outlives_session());
+            book.trader_pending_stop_orders = stop_orders;
+
+            // Only the suspended orders of the instrument stay submitted
+            let suspended_orders: HashSet<_> = book.trader_suspended_market_orders.iter()
+                .map(|order| order.get_order_id())
+                .chain(book.trader_suspended_limit_orders.iter().map(|order| order.get_order_id()))
+                .chain(book.trader_pending_stop_orders.iter().map(|order| order.get_order_id()))
+                .collect();
+            self.trader_submitted_orders.retain(
+                |order_id, order_instrument| *order_instrument != instrument || suspended_orders.contains(order_id)
+            );
+            for hosted in self.traders.iter_mut() {
+                hosted.fee_account.retain_orders(|order_id| self.trader_submitted_orders.contains_key(&order_id));
+                hosted.risk_gate.retain_orders(|order_id| self.trader_submitted_orders.contains_key(&order_id))
+            }
+            // Suspended orders carry their pegs
+            book.trader_pegged_orders.clear();
+            for id in cancelled.iter()
                 .map(|order| order.get_order_id())
-                .chain(self.trader_pending_limit_orders.keys().map(|id| *id))
+                .chain(cancelled_stop_orders.iter().map(|order| order.get_order_id()))
+                .chain(
+                    book.trader_pending_limit_orders.iter()
+                        .filter(|(_, (_, _, time_in_force))| !time_in_force.outlives_session())
+                        .map(|(id, _)| *id)
+                )
             {
                 let reply = OrderCancelled(id, CancellationReason::ExchangeClosed);
-                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                self.event_queue.schedule_reply_for_trader(reply);
+            }
+            book.trader_pending_limit_orders.clear();
+        } else {
+            book.bids.clear();
+            book.asks.clear();
+
+            for order in std::mem::take(&mut book.trader_suspended_limit_orders) {
+                self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, order)
             }
-            self.trader_pending_market_orders.clear();
-            self.trader_pending_limit_orders.clear();
+            let book = &mut self.books[instrument];
+            let market_orders = match book.auction_phase {
+                Some(_) => { &mut book.trader_auction_market_orders }
+                None => { &mut book.trader_pending_market_orders }
+            };
+            market_orders.append(&mut book.trader_suspended_market_orders);
         }
     }
 
-    const fn react_with_history_limit_orders<const ORDER_TYPE: AggressiveOrderType>() -> bool {
-        match (ORDER_TYPE, TRD_UPDATES_OB) {
-            (TraderMarketOrder | TraderIntersectingLimitOrder, _) | (_, true) => { true }
-            _ => { false }
+    pub(crate) fn find_trader_limit_order(&mut self,
+                                          instrument: InstrumentID,
+                                          order_id: OrderID,
+                                          price: Price,
+                                          direction: Direction) -> Option<&mut OrderBookEntry> {
+        let side = match direction {
+            Direction::Buy => { &mut self.books[instrument].bids }
+            Direction::Sell => { &mut self.books[instrument].asks }
+        };
+        let level = side.get_level_mut(price)?;
+        let slot = level.find(OrderOrigin::Trader, order_id)?;
+        Some(level.get_mut(slot))
+    }
+
+    pub(crate) fn remove_trader_limit_order_from_ob(&mut self, instrument: InstrumentID, order_id: OrderID, price: Price, direction: Direction) {
+        let side = match direction {
+            Direction::Buy => { &mut self.books[instrument].bids }
+            Direction::Sell => { &mut self.books[instrument].asks }
+        };
+        if let Some(level) = side.get_level_mut(price) {
+            if let Some(slot) = level.find(OrderOrigin::Trader, order_id) {
+                level.remove(slot);
+            }
+            side.remove_level_if_empty(price)
         }
     }
 
-    const fn is_trader_aggressive_order<const ORDER_TYPE: AggressiveOrderType>() -> bool {
-        if let TraderMarketOrder | TraderIntersectingLimitOrder = ORDER_TYPE {
-            true
-        } else {
-            false
+    fn trigger_stop_orders(&mut self, instrument: InstrumentID) {
+        // The prices traded before the halt trigger the orders when the trading resumes
+        if self.are_placements_halted(instrument) {
+            return;
+        }
+        // Executions of the triggered orders may trigger the other ones
+        let mut is_cascade = false;
+        while let Some((min_price, max_price)) = self.books[instrument].traded_price_range.get_range() {
+            // The halt started by the executions of the triggered orders stops the cascade,
+            // and the prices traded before it trigger the rest when the trading resumes
+            if is_cascade && self.check_traded_range(instrument, (min_price, max_price)) {
+                return;
+            }
+            is_cascade = true;
+            self.books[instrument].traded_price_range.take();
+            if self.exchange_closed || self.books[instrument].trader_pending_stop_orders.is_empty() {
+                return;
+            }
+            let (triggered, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.books[instrument].trader_pending_stop_orders)
+                .into_iter()
+                .partition(
+                    |order| match order.get_order_direction() {
+                        Direction::Buy => { max_price >= order.get_stop_price() }
+                        Direction::Sell => { min_price <= order.get_stop_price() }
+                    }
+                );
+            self.books[instrument].trader_pending_stop_orders = pending;
+            for order in triggered {
+                let order_id = order.get_order_id();
+                // The other order of the pair may have been executed by the previously triggered ones
+                if is_oco_pair_executed(&self.trader_oco_orders, &self.trader_executions, order_id) {
+                    self.reply_order_cancelled(order_id, CancellationReason::OneCancelsOther);
+                    continue;
+                }
+                let reply = ExchangeReply::OrderTriggered(order_id);
+                self.event_queue.schedule_reply_for_trader(reply);
+                let is_fill_or_kill = order.get_time_in_force() == TimeInForce::FillOrKill;
+                match order.get_limit_price() {
+                    Some(price) if !self.is_within_price_band(instrument, price) => {
+                        self.reply_order_cancelled(order_id, CancellationReason::OutsidePriceBand)
+                    }
+                    Some(price) => {
+                        let limit_order = LimitOrder::new(order_id, order.get_order_size(), order.get_order_direction(), price)
+                            .with_time_in_force(order.get_time_in_force());
+                        if is_fill_or_kill && self.get_limit_order_available_liquidity(instrument, &limit_order) < order.get_order_size() {
+                            self.reply_order_cancelled(order_id, CancellationReason::InsufficientLiquidity)
+                        } else {
+                            self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, limit_order)
+                        }
+                    }
+                    None => {
+                        let market_order = MarketOrder::new(order_id, order.get_order_size(), order.get_order_direction())
+                            .with_time_in_force(order.get_time_in_force());
+                        if is_fill_or_kill && self.get_market_order_available_liquidity(instrument, &market_order) < order.get_order_size() {
+                            self.reply_order_cancelled(order_id, CancellationReason::InsufficientLiquidity)
+                        } else {
+                            self.insert_trader_market_order(instrument, market_order)
+                        }
+                    }
+                }
+            }
         }
     }
 
-    pub(crate)
-    fn insert_aggressive_order<O, const ORDER_TYPE: AggressiveOrderType>(&mut self, mut order: O)
-        where O: Order
-    {
-        let mut side_cursor = match order.get_order_direction() {
-            Direction::Buy => { self.asks.cursor_front_mut() }
-            Direction::Sell => { self.bids.cursor_front_mut() }
+    /// Returns the protection price of a trader market order if it has to be converted to a limit order.
+    /// The price of a sell order is at least the minimum tick
+    pub(crate) fn get_market_order_protection_price(&self, instrument: InstrumentID, direction: Direction) -> Option<Price> {
+        let spec = &self.books[instrument].spec;
+        match self.market_order_policy {
+            MarketOrderPolicy::ConvertToLimit { protection_ticks } => {
+                match direction {
+                    Direction::Buy => {
+                        self.get_best_price(instrument, Direction::Sell)
+                            .map(|price| spec.round_up(spec.shift_by_ticks(price, protection_ticks)))
+                    }
+                    Direction::Sell => {
+                        let min_price = spec.round_up(Price(1));
+                        self.get_best_price(instrument, Direction::Buy)
+                            .map(|price| max(spec.round_down(spec.shift_by_ticks(price, -protection_ticks)), min_price))
+                    }
+                }
+            }
+            _ => { None }
+        }
+    }
+
+    pub(crate) fn insert_trader_market_order(&mut self, instrument: InstrumentID, order: MarketOrder) {
+        if self.books[instrument].auction_phase.is_some() {
+            if let TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill = order.get_time_in_force() {
+                let reply = OrderCancelled(order.get_order_id(), CancellationReason::ImmediateOrCancel);
+                self.event_queue.schedule_reply_for_trader(reply);
+            } else {
+                self.books[instrument].trader_auction_market_orders.push_back(order);
+            }
+            return;
+        }
+        match self.get_market_order_protection_price(instrument, order.get_order_direction()) {
+            Some(price) => {
+                let order_id = order.get_order_id();
+                self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(
+                    instrument,
+                    LimitOrder::new(order_id, order.get_order_size(), order.get_order_direction(), price)
+                        .with_time_in_force(order.get_time_in_force())
+                );
+                if self.books[instrument].trader_pending_limit_orders.contains_key(&order_id) {
+                    let reply = OrderConvertedToLimit(order_id, price);
+                    self.event_queue.schedule_reply_for_trader(reply)
+                }
+            }
+            None => {
+                self.insert_aggressive_order::<MarketOrder, { TraderMarketOrder }>(instrument, order)
+            }
+        }
+    }
+
+    /// Best price on the given side not set by the trader pegged orders themselves
+    fn get_peg_reference_price(&self, instrument: InstrumentID, side: Direction) -> Option<Price> {
+        let levels = match side {
+            Direction::Buy => { &self.books[instrument].bids }
+            Direction::Sell => { &self.books[instrument].asks }
         };
+        levels.iter()
+            .find(
+                |level| level.iter().any(
+                    |order| order.from == OrderOrigin::History || !self.books[instrument].trader_pegged_orders.contains_key(&order.order_id)
+                )
+            )
+            .map(|level| level.price)
+    }
 
-        while let Some(level) = side_cursor.current()
-        {
-            let price = level.price;
-            let mut level_cursor = level.queue.cursor_front_mut();
-            let mut limit_order = level_cursor.current().expect_with(
-                || format!("Level at price {:?} does not have any orders", price)
-            );
+    /// Returns the current price of the pegged order on the tick grid. Pegged orders never take liquidity
+    pub(crate) fn get_peg_price(&self, instrument: InstrumentID, direction: Direction, (reference, offset): (PegReference, i64)) -> Option<Price> {
+        let best_bid = self.get_peg_reference_price(instrument, Direction::Buy);
+        let best_ask = self.get_peg_reference_price(instrument, Direction::Sell);
+        let reference_price = match (reference, direction) {
+            (PegReference::Primary, Direction::Buy) | (PegReference::Market, Direction::Sell) => { best_bid? }
+            (PegReference::Primary, Direction::Sell) | (PegReference::Market, Direction::Buy) => { best_ask? }
+            (PegReference::Midpoint, Direction::Buy) => { Price((best_bid?.0 + best_ask?.0).div_euclid(2)) }
+            (PegReference::Midpoint, Direction::Sell) => { Price((best_bid?.0 + best_ask?.0 + 1).div_euclid(2)) }
+        };
+        let spec = &self.books[instrument].spec;
+        let price = spec.shift_by_ticks(reference_price, offset);
+        Some(
+            match (direction, best_bid, best_ask) {
+                (Direction::Buy, _, Some(best_ask)) if price >= best_ask => { spec.round_down(Price(best_ask.0 - 1)) }
+                (Direction::Sell, Some(best_bid), _) if price <= best_bid => { spec.round_up(Price(best_bid.0 + 1)) }
+                (Direction::Buy, _, _) => { spec.round_down(price) }
+                (Direction::Sell, _, _) => { spec.round_up(price) }
+            }
+        )
+    }
 
-            loop {
-                match order.get_order_size().cmp(&limit_order.size)
-                {
-                    Ordering::Less => {
-                        // (OrderExecuted, OrderPartiallyExecuted)
-                        let exec_size = order.get_order_size();
-                        if TRD_SUBSCRIPTION {
-                            self.executed_trades.push(OrderBookDiff {
-                                datetime: self.current_dt,
-                                price,
-                                size: exec_size,
-                                direction: order.get_order_direction(),
-                            })
-                        }
-                        match ORDER_TYPE {
-                            TraderMarketOrder => {
-                                let reply = OrderExecuted(order.get_order_id(), exec_size, price);
-                                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng)
-                            }
-                            TraderIntersectingLimitOrder => {
-                                let reply = OrderPartiallyExecuted(order.get_order_id(), exec_size, price);
-                                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng)
-                            }
-                            _ => {}
-                        }
-                        if limit_order.from == OrderOrigin::Trader {
-                            let reply = OrderPartiallyExecuted(limit_order.order_id, exec_size, price);
-                            self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                            limit_order.size -= exec_size
-                        } else if Self::react_with_history_limit_orders::<ORDER_TYPE>() {
-                            limit_order.size -= exec_size
-                        }
-                        return;
-                    }
-                    Ordering::Equal => {
-                        // (OrderExecuted, OrderExecuted)
-                        let exec_size = order.get_order_size();
-                        if TRD_SUBSCRIPTION {
-                            self.executed_trades.push(OrderBookDiff {
-                                datetime: self.current_dt,
-                                price,
-                                size: exec_size,
-                                direction: order.get_order_direction(),
-                            })
-                        }
-                        match ORDER_TYPE {
-                            TraderMarketOrder => {
-                                let reply = OrderExecuted(order.get_order_id(), exec_size, price);
-                                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                            }
-                            TraderIntersectingLimitOrder => {
-                                let reply = OrderPartiallyExecuted(order.get_order_id(), exec_size, price);
-                                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                            }
-                            _ => {}
-                        }
-                        if limit_order.from == OrderOrigin::Trader {
-                            let reply = OrderExecuted(limit_order.order_id, exec_size, price);
-                            self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                            self.trader_pending_limit_orders.remove(&limit_order.order_id);
-                            level_cursor.remove_current();
-                            if level.queue.is_empty() {
-                                side_cursor.remove_current();
-                            }
-                        } else if Self::react_with_history_limit_orders::<ORDER_TYPE>() {
-                            level_cursor.remove_current();
-                            if level.queue.is_empty() {
-                                side_cursor.remove_current();
-                            }
-                        };
-                        return;
-                    }
-                    Ordering::Greater => {
-                        // (OrderPartiallyExecuted, OrderExecuted)
-                        let exec_size = limit_order.size;
-                        *order.mut_order_size() -= exec_size;
-                        if TRD_SUBSCRIPTION {
-                            self.executed_trades.push(OrderBookDiff {
-                                datetime: self.current_dt,
-                                price,
-                                size: exec_size,
-                                direction: order.get_order_direction(),
-                            })
-                        }
-                        if Self::is_trader_aggressive_order::<ORDER_TYPE>() {
-                            let reply = OrderPartiallyExecuted(order.get_order_id(), exec_size, price);
-                            self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                        }
-                        match limit_order.from {
-                            OrderOrigin::History => {
-                                if Self::react_with_history_limit_orders::<ORDER_TYPE>() {
-                                    level_cursor.remove_current();
-                                    match level_cursor.current() {
-                                        Some(entry) => { limit_order = entry }
-                                        None => {
-                                            if level.queue.is_empty() {
-                                                side_cursor.remove_current();
-                                            } else {
-                                                side_cursor.move_next();
-                                            }
-                                            break;
-                                        }
-                                    }
-                                } else {
-                                    level_cursor.move_next();
-                                    match level_cursor.current() {
-                                        Some(entry) => { limit_order = entry }
-                                        None => {
-                                            side_cursor.move_next();
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                            OrderOrigin::Trader => {
-                                let reply = OrderExecuted(limit_order.order_id, exec_size, price);
-                                self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
-                                self.trader_pending_limit_orders.remove(&limit_order.order_id);
-                                level_cursor.remove_current();
-                                match level_cursor.current() {
-                                    Some(entry) => { limit_order = entry }
-                                    None => {
-                                        if level.queue.is_empty() {
-                                            side_cursor.remove_current();
-                                        } else {
-                                            side_cursor.move_next();
-                                        }
-                                        break;
-                                    }
-                                }
-                            }
-                        }
+    /// Cancels the active trader order wherever it is held. Returns whether the order has been found
+    pub(crate) fn cancel_trader_order(&mut self, order_id: OrderID, reason: CancellationReason) -> bool {
+        let instrument = match self.trader_submitted_orders.get(&order_id) {
+            Some(&instrument) => { instrument }
+            None => { return false; }
+        };
+        let cancelled = if let Some((price, direction, _)) = self.books[instrument].trader_pending_limit_orders.remove(&order_id) {
+            self.remove_trader_limit_order_from_ob(instrument, order_id, price, direction);
+            true
+        } else if let Some(i) = self.books[instrument].trader_pending_stop_orders.iter().position(|order| order.get_order_id() == order_id) {
+            self.books[instrument].trader_pending_stop_orders.remove(i);
+            true
+        } else if let Some(i) = self.books[instrument].trader_suspended_limit_orders.iter().position(|order| order.get_order_id() == order_id) {
+            self.books[instrument].trader_suspended_limit_orders.remove(i);
+            true
+        } else if let Some(i) = self.books[instrument].trader_close_limit_orders.iter().position(|order| order.get_order_id() == order_id) {
+            self.books[instrument].trader_close_limit_orders.remove(i);
+            true
+        } else {
+            let book = &mut self.books[instrument];
+            [
+                &mut book.trader_pending_market_orders,
+                &mut book.trader_suspended_market_orders,
+                &mut book.trader_auction_market_orders,
+                &mut book.trader_close_market_orders,
+            ]
+                .into_iter()
+                .any(|orders| remove_market_order(orders, |order| order.get_order_id() == order_id))
+        };
+        if cancelled {
+            self.reply_order_cancelled(order_id, reason)
+        }
+        cancelled
+    }
+
+    /// Forgets the cancelled trader order in the accounts of its owner and sends `OrderCancelled`
+    fn reply_order_cancelled(&mut self, order_id: OrderID, reason: CancellationReason) {
+        if let Some(owner) = self.get_order_owner(order_id) {
+            let hosted = &mut self.traders[owner.0];
+            hosted.fee_account.forget_order(order_id);
+            hosted.risk_gate.forget_order(order_id)
+        }
+        let reply = OrderCancelled(order_id, reason);
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    /// Updates the trader position, cancels the other order of the executed OCO pairs and places the children
+    /// of the executed bracket parents
+    fn enforce_order_groups(&mut self) {
+        while !self.trader_executions.is_empty() {
+            for TraderExecution { order_id, instrument, size, price, fee, is_executed } in std::mem::take(&mut self.trader_executions) {
+                let notional = self.books[instrument].spec.get_notional(price, size);
+                let owner = self.get_order_owner(order_id).expect_with(
+                    || format!("Executed order {:?} does not belong to any trader", order_id)
+                );
+                let hosted = &mut self.traders[owner.0];
+                hosted.message_statistics.trades += 1;
+                hosted.risk_gate.record_execution(order_id, size, notional, fee);
+                if is_executed {
+                    hosted.risk_gate.forget_order(order_id)
+                }
+                if let Some(other_id) = self.trader_oco_orders.remove(&order_id) {
+                    self.trader_oco_orders.remove(&other_id);
+                    self.cancel_trader_order(other_id, CancellationReason::OneCancelsOther);
+                }
+                if is_executed {
+                    if let Some((take_profit, stop_loss)) = self.trader_bracket_orders.remove(&order_id) {
+                        self.place_oco_orders(take_profit, stop_loss)
                     }
                 }
             }
         }
+        // The groups of the cancelled orders are dissolved
+        if !self.trader_bracket_orders.is_empty() {
+            let mut dissolved: Vec<_> = self.trader_bracket_orders.keys()
+                .copied()
+                .filter(|order_id| !self.is_trader_order_active(*order_id))
+                .collect();
+            dissolved.sort();
+            for order_id in dissolved {
+                if let Some((take_profit, stop_loss)) = self.trader_bracket_orders.remove(&order_id) {
+                    for order in [take_profit, stop_loss] {
+                        let reply = OrderPlacementDiscarded(order.get_order_id(), DiscardingReason::ParentNotExecuted);
+                        self.event_queue.schedule_reply_for_trader(reply);
+                    }
+                }
+            }
+        }
+        if !self.trader_oco_orders.is_empty() {
+            let dissolved: Vec<_> = self.trader_oco_orders.keys()
+                .copied()
+                .filter(|order_id| !self.is_trader_order_active(*order_id))
+                .collect();
+            for order_id in dissolved {
+                if let Some(other_id) = self.trader_oco_orders.remove(&order_id) {
+                    self.trader_oco_orders.remove(&other_id);
+                }
+            }
+        }
+    }
+
+    fn reprice_pegged_orders(&mut self, instrument: InstrumentID) {
+        if self.exchange_closed || self.books[instrument].trader_pegged_orders.is_empty() {
+            return;
+        }
+        let book = &mut self.books[instrument];
+        let pending_limit_orders = &book.trader_pending_limit_orders;
+        book.trader_pegged_orders.retain(|order_id, _| pending_limit_orders.contains_key(order_id));
+        // Pegged orders follow the reference prices only
+        let reference_prices = (
+            self.get_peg_reference_price(instrument, Direction::Buy),
+            self.get_peg_reference_price(instrument, Direction::Sell),
+        );
+        let book = &mut self.books[instrument];
+        if std::mem::replace(&mut book.peg_reference_prices, reference_prices) == reference_prices {
+            return;
+        }
+        let mut pegged_orders: Vec<_> = book.trader_pegged_orders.iter().map(|(order_id, peg)| (*order_id, *peg)).collect();
+        pegged_orders.sort();
+        for (order_id, peg) in pegged_orders {
+            let (price, direction, time_in_force) = match self.books[instrument].trader_pending_limit_orders.get(&order_id) {
+                Some(&pending) => { pending }
+                None => { continue; }
+            };
+            let new_price = match self.get_peg_price(instrument, direction, peg) {
+                Some(new_price) if new_price != price => { new_price }
+                _ => { continue; }
+            };
+            let order = match self.find_trader_limit_order(instrument, order_id, price, direction) {
+                Some(order) => { order.to_limit_order(order.size + order.hidden_size, direction, new_price, time_in_force) }
+                None => { continue; }
+            };
+            // Repriced order loses its queue priority
+            self.books[instrument].trader_pending_limit_orders.remove(&order_id);
+            self.remove_trader_limit_order_from_ob(instrument, order_id, price, direction);
+            let reply = OrderRepriced(order_id, new_price);
+            self.event_queue.schedule_reply_for_trader(reply);
+            self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, order);
+        }
+    }
+
+    pub(crate) fn get_best_price(&self, instrument: InstrumentID, side: Direction) -> Option<Price> {
+        match side {
+            Direction::Buy => { self.books[instrument].bids.get_best_price() }
+            Direction::Sell => { self.books[instrument].asks.get_best_price() }
+        }
+    }
+
+    pub(crate) fn get_available_liquidity(&self, instrument: InstrumentID, direction: Direction, limit_price: Option<Price>) -> Size {
+        let levels = match direction {
+            Direction::Buy => { &self.books[instrument].asks }
+            Direction::Sell => { &self.books[instrument].bids }
+        };
+        // Only the first met order of the OCO pair can be executed
+        let mut oco_orders = Vec::new();
+        levels.iter()
+            .take_while(
+                |level| limit_price.map_or(true, |price| match direction {
+                    Direction::Buy => { level.price <= price }
+                    Direction::Sell => { level.price >= price }
+                })
+            )
+            .flat_map(|level| level.iter())
+            .filter(
+                |order| match self.trader_oco_orders.get(&order.order_id) {
+                    Some(other_id) if order.from == OrderOrigin::Trader => {
+                        let is_available = !oco_orders.contains(other_id) && !is_oco_pair_executed(
+                            &self.trader_oco_orders,
+                            &self.trader_executions,
+                            order.order_id,
+                        );
+                        oco_orders.push(order.order_id);
+                        is_available
+                    }
+                    _ => { true }
+                }
+            )
+            .map(|order| order.size + order.hidden_size)
+            .sum()
+    }
+
+    const fn react_with_history_limit_orders<const ORDER_TYPE: AggressiveOrderType>() -> bool {
+        match (ORDER_TYPE, TRD_UPDATES_OB) {
+            (TraderMarketOrder | TraderIntersectingLimitOrder, _) | (_, true) => { true }
+            _ => { false }
+        }
+    }
+
+    pub(crate)
+    fn insert_aggressive_order<O, const ORDER_TYPE: AggressiveOrderType>(&mut self, instrument: InstrumentID, mut order: O)
+        where O: Order
+    {
+        self.match_aggressive_order::<O, ORDER_TYPE>(instrument, &mut order, None);
+        if order.get_order_size() == Size(0) {
+            return;
+        }
         match ORDER_TYPE {
             TraderMarketOrder => {
-                self.trader_pending_market_orders.push_back(
-                    MarketOrder::new(order.get_order_id(), order.get_order_size(), order.get_order_direction())
-                )
+                match order.get_time_in_force() {
+                    TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill => {
+                        let reply = OrderCancelled(order.get_order_id(), CancellationReason::ImmediateOrCancel);
+                        self.event_queue.schedule_reply_for_trader(reply)
+                    }
+                    time_in_force if self.market_order_policy == MarketOrderPolicy::KeepPending => {
+                        let reply = OrderRemainderPending(order.get_order_id(), order.get_order_size());
+                        self.event_queue.schedule_reply_for_trader(reply);
+                        self.books[instrument].trader_pending_market_orders.push_back(
+                            MarketOrder::new(order.get_order_id(), order.get_order_size(), order.get_order_direction())
+                                .with_time_in_force(time_in_force)
+                        )
+                    }
+                    _ => {
+                        let reply = OrderCancelled(order.get_order_id(), CancellationReason::InsufficientLiquidity);
+                        self.event_queue.schedule_reply_for_trader(reply)
+                    }
+                }
             }
             HistoryMarketOrder => {
                 if DEBUG {
@@ -257,36 +580,325 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
         }
     }
 
-    pub(crate) fn insert_limit_order<O, const COME_FROM: OrderOrigin>(&mut self, mut order: O)
+    /// Charges the fee for the execution of the trader order, records the execution to enforce the order groups
+    /// and the limits, and schedules the reply
+    pub(crate) fn report_trader_execution(&mut self,
+                                          instrument: InstrumentID,
+                                          order_id: OrderID,
+                                          size: Size,
+                                          price: Price,
+                                          liquidity: Liquidity,
+                                          is_executed: bool) {
+        let mut execution = TraderExecution { order_id, instrument, size, price, fee: Fee(0), is_executed };
+        let owner = self.get_order_owner(order_id).expect_with(
+            || format!("Executed order {:?} does not belong to any trader", order_id)
+        );
+        self.traders[owner.0].fee_account.charge(&mut self.policies.fees, &self.books[instrument].spec, self.current_dt, liquidity, &mut execution);
+        let fee = execution.fee;
+        self.trader_executions.push(execution);
+        let reply = if is_executed {
+            OrderExecuted(order_id, size, price, liquidity, fee)
+        } else {
+            OrderPartiallyExecuted(order_id, size, price, liquidity, fee)
+        };
+        self.event_queue.schedule_reply_for_trader(reply)
+    }
+
+    /// Executes the order against the opposite side of the order book from the best level up to the limit price,
+    /// if any. The self-trade prevention is applied to the resting orders of the same trader as they are met
+    fn match_aggressive_order<O, const ORDER_TYPE: AggressiveOrderType>(&mut self, instrument: InstrumentID, order: &mut O, limit_price: Option<Price>)
+        where O: Order
+    {
+        let self_trade_owner = match ORDER_TYPE {
+            TraderMarketOrder | TraderIntersectingLimitOrder if self.self_trade_prevention.is_some() => {
+                self.get_order_owner(order.get_order_id())
+            }
+            _ => { None }
+        };
+        let direction = order.get_order_direction();
+        let mut prev_price = None;
+        while let Some(mut level) = self.get_opposite_side_mut(instrument, direction).take_next_level(prev_price)
+        {
+            let price = level.price;
+            let is_within_limit = limit_price.map_or(true, |limit_price| match direction {
+                Direction::Buy => { price <= limit_price }
+                Direction::Sell => { price >= limit_price }
+            });
+            if is_within_limit {
+                if M::IN_QUEUE_ORDER {
+                    self.match_in_queue_order::<O, ORDER_TYPE>(instrument, order, &mut level, self_trade_owner)
+                } else {
+                    self.match_by_allocations::<O, ORDER_TYPE>(instrument, order, &mut level, self_trade_owner)
+                }
+            }
+            self.get_opposite_side_mut(instrument, direction).restore_level(level);
+            prev_price = Some(price);
+            if !is_within_limit || order.get_order_size() == Size(0) {
+                return;
+            }
+        }
+    }
+
+    /// Applies the self-trade prevention to the resting trader order met by the incoming order of the same trader
+    fn prevent_self_trade<O: Order>(&mut self, instrument: InstrumentID, order: &mut O, level: &mut OrderBookLevel, slot: usize) {
+        let mode = self.self_trade_prevention.expect("Self-trade prevention is set");
+        let entry = level.get_mut(slot);
+        let resting_size = entry.size + entry.hidden_size;
+        let (resting_decrement, incoming_decrement) = get_self_trade_decrements(mode, resting_size, order.get_order_size());
+        if resting_decrement == resting_size {
+            let order_id = level.remove(slot).order_id;
+            self.books[instrument].trader_pending_limit_orders.remove(&order_id);
+            let reply = OrderCancelled(order_id, CancellationReason::SelfTradePrevention);
+            self.event_queue.schedule_reply_for_trader(reply);
+        } else {
+            let hidden_decrement = min(entry.hidden_size, resting_decrement);
+            entry.hidden_size -= hidden_decrement;
+            entry.size -= resting_decrement - hidden_decrement;
+        }
+        self.decrease_by_self_trade(order, incoming_decrement)
+    }
+
+    /// Decreases the incoming order by the self-trade prevention and cancels it once nothing is left
+    fn decrease_by_self_trade<O: Order>(&mut self, order: &mut O, decrement: Size) {
+        if decrement == Size(0) {
+            return;
+        }
+        *order.mut_order_size() -= decrement;
+        if order.get_order_size() == Size(0) {
+            let reply = OrderCancelled(order.get_order_id(), CancellationReason::SelfTradePrevention);
+            self.event_queue.schedule_reply_for_trader(reply);
+        }
+    }
+
+    /// Side of the order book the order with the given direction is matched against
+    fn get_opposite_side_mut(&mut self, instrument: InstrumentID, direction: Direction) -> &mut OrderBookSide {
+        match direction {
+            Direction::Buy => { &mut self.books[instrument].asks }
+            Direction::Sell => { &mut self.books[instrument].bids }
+        }
+    }
+
+    /// Executes the level orders one by one in the queue order. Refilled iceberg orders are met again
+    /// once they reach the front
+    fn match_in_queue_order<O, const ORDER_TYPE: AggressiveOrderType>(&mut self,
+                                                                      instrument: InstrumentID,
+                                                                      order: &mut O,
+                                                                      level: &mut OrderBookLevel,
+                                                                      self_trade_owner: Option<TraderID>)
+        where O: Order
+    {
+        let mut met_oco_orders = Vec::new();
+        let mut current = level.front();
+        while let Some(slot) = current {
+            if order.get_order_size() == Size(0) {
+                return;
+            }
+            let entry = level.get(slot);
+            let next = level.next(slot);
+            if !is_matchable(entry, &mut met_oco_orders, &self.trader_oco_orders, &self.trader_executions) {
+                current = next;
+                continue;
+            }
+            if self_trade_owner.is_some() && entry.from == OrderOrigin::Trader && entry.owner == self_trade_owner {
+                self.prevent_self_trade(instrument, order, level, slot);
+                current = next;
+                continue;
+            }
+            let exec_size = min(order.get_order_size(), entry.size);
+            let refilled = self.execute_level_entry::<O, ORDER_TYPE>(instrument, order, level, slot, exec_size);
+            current = next.or(refilled);
+        }
+    }
+
+    /// Splits the execution among the level orders with the matching policy. Refilled iceberg orders are
+    /// allocated the rest of the execution, if any, in the next round. The round stops at the order of the same trader,
+    /// and the rest is allocated again once the self-trade prevention has been applied
+    fn match_by_allocations<O, const ORDER_TYPE: AggressiveOrderType>(&mut self,
+                                                                      instrument: InstrumentID,
+                                                                      order: &mut O,
+                                                                      level: &mut OrderBookLevel,
+                                                                      self_trade_owner: Option<TraderID>)
+        where O: Order
+    {
+        // Orders before this index have been met by the order, except for refilled iceberg orders
+        let mut first_unmet = 0;
+        while order.get_order_size() != Size(0) && first_unmet < level.len()
+        {
+            let order_sizes = get_matchable_sizes(
+                level,
+                first_unmet,
+                &self.trader_oco_orders,
+                &self.trader_executions,
+            );
+            let level_size: Size = order_sizes.iter().copied().sum();
+            if level_size == Size(0) {
+                break;
+            }
+            let allocations = self.policies.matching.allocate(min(order.get_order_size(), level_size), &order_sizes);
+            let slots: Vec<usize> = level.entries().map(|(slot, _)| slot).collect();
+            let mut refilled = 0;
+            let mut is_self_trade_met = false;
+            for (slot, exec_size) in slots.into_iter().zip(allocations) {
+                if exec_size == Size(0) {
+                    continue;
+                }
+                let entry = level.get(slot);
+                if self_trade_owner.is_some() && entry.from == OrderOrigin::Trader && entry.owner == self_trade_owner {
+                    self.prevent_self_trade(instrument, order, level, slot);
+                    is_self_trade_met = true;
+                    break;
+                }
+                if self.execute_level_entry::<O, ORDER_TYPE>(instrument, order, level, slot, exec_size).is_some() {
+                    refilled += 1
+                }
+            }
+            if !is_self_trade_met {
+                first_unmet = level.len() - refilled;
+            }
+        }
+    }
+
+    /// Executes the given size of the level order against the incoming order.
+    /// Returns the new slot of the refilled iceberg order moved to the back of the level
+    fn execute_level_entry<O, const ORDER_TYPE: AggressiveOrderType>(&mut self,
+                                                                     instrument: InstrumentID,
+                                                                     order: &mut O,
+                                                                     level: &mut OrderBookLevel,
+                                                                     slot: usize,
+                                                                     exec_size: Size) -> Option<usize>
+        where O: Order
+    {
+        let price = level.price;
+        *order.mut_order_size() -= exec_size;
+        self.books[instrument].traded_price_range.update(price);
+        if TRD_SUBSCRIPTION {
+            self.books[instrument].executed_trades.push(OrderBookDiff {
+                datetime: self.current_dt,
+                instrument,
+                price,
+                size: exec_size,
+                direction: order.get_order_direction(),
+            })
+        }
+        match ORDER_TYPE {
+            TraderMarketOrder | TraderIntersectingLimitOrder if order.get_order_size() == Size(0) => {
+                self.report_trader_execution(instrument, order.get_order_id(), exec_size, price, Liquidity::Removed, true)
+            }
+            TraderMarketOrder | TraderIntersectingLimitOrder => {
+                self.report_trader_execution(instrument, order.get_order_id(), exec_size, price, Liquidity::Removed, false)
+            }
+            _ => {}
+        }
+        let limit_order = level.get_mut(slot);
+        match limit_order.from {
+            OrderOrigin::History => {
+                if Self::react_with_history_limit_orders::<ORDER_TYPE>() {
+                    limit_order.size -= exec_size
+                } else if ORDER_TYPE == HistoryMarketOrder {
+                    // The order book diff of the order reflects the execution later
+                    *self.books[instrument].history_fills.entry(limit_order.order_id).or_default() += exec_size
+                }
+                if let (TraderMarketOrder | TraderIntersectingLimitOrder, Some(impact)) = (ORDER_TYPE, self.market_impact) {
+                    let history_size = limit_order.size + exec_size;
+                    let direction = match order.get_order_direction() {
+                        Direction::Buy => { Direction::Sell }
+                        Direction::Sell => { Direction::Buy }
+                    };
+                    self.books[instrument].consumed_history_orders.entry(limit_order.order_id)
+                        .or_insert_with(|| ConsumedOrder::new(price, direction, history_size, self.current_dt))
+                        .consume(impact, exec_size, self.current_dt);
+                    if let MarketImpact::Resiliency { refill_interval, .. } = impact {
+                        self.event_queue.push(
+                            Event {
+                                datetime: self.current_dt + refill_interval,
+                                body: EventBody::HistoryOrderRefill(instrument, limit_order.order_id),
+                            }
+                        )
+                    }
+                }
+                if limit_order.size == Size(0) {
+                    level.remove(slot);
+                }
+            }
+            OrderOrigin::Trader if exec_size < limit_order.size => {
+                self.report_trader_execution(instrument, limit_order.order_id, exec_size, price, Liquidity::Added, false);
+                limit_order.size -= exec_size
+            }
+            OrderOrigin::Trader if limit_order.hidden_size != Size(0) => {
+                // Iceberg order goes to the back of the queue after each refill
+                self.report_trader_execution(instrument, limit_order.order_id, exec_size, price, Liquidity::Added, false);
+                limit_order.refill();
+                return Some(level.move_to_back(slot));
+            }
+            OrderOrigin::Trader => {
+                self.report_trader_execution(instrument, limit_order.order_id, exec_size, price, Liquidity::Added, true);
+                self.books[instrument].trader_pending_limit_orders.remove(&limit_order.order_id);
+                level.remove(slot);
+            }
+        }
+        None
+    }
+
+    /// Executes the incoming limit order against the pending trader market orders of the opposite direction
+    /// taken out of the order book
+    fn match_pending_market_orders<O, const COME_FROM: OrderOrigin>(&mut self,
+                                                                    instrument: InstrumentID,
+                                                                    order: &mut O,
+                                                                    pending_orders: &mut LinkedList<MarketOrder>)
         where O: PricedOrder
     {
         let price = order.get_price();
-
-        // Check that the Exchange have pending market orders
-        let mut cursor = self.trader_pending_market_orders.cursor_front_mut();
+        let self_trade_owner = match self.self_trade_prevention {
+            Some(_) if COME_FROM == OrderOrigin::Trader => { self.get_order_owner(order.get_order_id()) }
+            _ => { None }
+        };
+        let mut cursor = pending_orders.cursor_front_mut();
         while let Some(pending) = cursor.current()
         {
+            if order.get_order_size() == Size(0) {
+                return;
+            }
             if pending.get_order_direction() == order.get_order_direction() {
                 cursor.move_next();
                 continue;
             }
+            if let (Some(mode), Some(_)) = (self.self_trade_prevention, self_trade_owner) {
+                if self.get_order_owner(pending.get_order_id()) == self_trade_owner {
+                    let (resting_decrement, incoming_decrement) = get_self_trade_decrements(
+                        mode,
+                        pending.get_order_size(),
+                        order.get_order_size(),
+                    );
+                    if resting_decrement == pending.get_order_size() {
+                        let reply = OrderCancelled(pending.get_order_id(), CancellationReason::SelfTradePrevention);
+                        self.event_queue.schedule_reply_for_trader(reply);
+                        cursor.remove_current();
+                    } else {
+                        *pending.mut_order_size() -= resting_decrement;
+                        cursor.move_next();
+                    }
+                    self.decrease_by_self_trade(order, incoming_decrement);
+                    continue;
+                }
+            }
             match order.get_order_size().cmp(&pending.get_order_size()) {
                 Ordering::Less => {
                     // (OrderExecuted, OrderPartiallyExecuted)
                     let exec_size = order.get_order_size();
+                    *order.mut_order_size() -= exec_size;
                     *pending.mut_order_size() -= exec_size;
-                    let reply = OrderPartiallyExecuted(pending.get_order_id(), exec_size, price);
-                    self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                    self.report_trader_execution(instrument, pending.get_order_id(), exec_size, price, Liquidity::Added, false);
                     if COME_FROM == OrderOrigin::Trader {
-                        let reply = OrderExecuted(order.get_order_id(), exec_size, price);
-                        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                        self.report_trader_execution(instrument, order.get_order_id(), exec_size, price, Liquidity::Removed, true);
                     }
+                    self.books[instrument].traded_price_range.update(price);
                     if TRD_SUBSCRIPTION {
-                        self.executed_trades.push(OrderBookDiff {
+                        self.books[instrument].executed_trades.push(OrderBookDiff {
                             datetime: self.current_dt,
+                            instrument,
                             price,
                             size: exec_size,
-                            direction: pending.get_order_direction(),
+                            direction: order.get_order_direction(),
                         })
                     }
                     return;
@@ -294,18 +906,19 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                 Ordering::Equal => {
                     // (OrderExecuted, OrderExecuted)
                     let exec_size = order.get_order_size();
-                    let reply = OrderExecuted(pending.get_order_id(), exec_size, price);
-                    self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                    *order.mut_order_size() -= exec_size;
+                    self.report_trader_execution(instrument, pending.get_order_id(), exec_size, price, Liquidity::Added, true);
                     if COME_FROM == OrderOrigin::Trader {
-                        let reply = OrderExecuted(order.get_order_id(), exec_size, price);
-                        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                        self.report_trader_execution(instrument, order.get_order_id(), exec_size, price, Liquidity::Removed, true);
                     }
+                    self.books[instrument].traded_price_range.update(price);
                     if TRD_SUBSCRIPTION {
-                        self.executed_trades.push(OrderBookDiff {
+                        self.books[instrument].executed_trades.push(OrderBookDiff {
                             datetime: self.current_dt,
+                            instrument,
                             price,
                             size: exec_size,
-                            direction: pending.get_order_direction(),
+                            direction: order.get_order_direction(),
                         })
                     }
                     cursor.remove_current();
@@ -315,112 +928,103 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                     // (OrderPartiallyExecuted, OrderExecuted)
                     let exec_size = pending.get_order_size();
                     *order.mut_order_size() -= exec_size;
-                    let reply = OrderExecuted(pending.get_order_id(), exec_size, price);
-                    self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                    self.report_trader_execution(instrument, pending.get_order_id(), exec_size, price, Liquidity::Added, true);
                     if COME_FROM == OrderOrigin::Trader {
-                        let reply = OrderPartiallyExecuted(order.get_order_id(), exec_size, price);
-                        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+                        self.report_trader_execution(instrument, order.get_order_id(), exec_size, price, Liquidity::Removed, false);
                     }
+                    self.books[instrument].traded_price_range.update(price);
                     if TRD_SUBSCRIPTION {
-                        self.executed_trades.push(OrderBookDiff {
+                        self.books[instrument].executed_trades.push(OrderBookDiff {
                             datetime: self.current_dt,
+                            instrument,
                             price,
                             size: exec_size,
-                            direction: pending.get_order_direction(),
+                            direction: order.get_order_direction(),
                         })
                     }
                     cursor.remove_current();
                 }
             }
         }
+    }
 
-        // Check whether LimitOrder intersects the opposite side of the Order Book
-        let intersection_size = match order.get_order_direction() {
-            Direction::Buy => {
-                self.asks.iter()
-                    .take_while(|level| level.price <= price)
-                    .map(OrderBookLevel::get_ob_level_size)
-                    .sum()
-            }
-            Direction::Sell => {
-                self.bids.iter()
-                    .take_while(|level| level.price >= price)
-                    .map(OrderBookLevel::get_ob_level_size)
-                    .sum()
-            }
-        };
-        if intersection_size < order.get_order_size() {
-            if intersection_size != Size(0) {
-                *order.mut_order_size() -= intersection_size;
-                let order = MarketOrder::new(order.get_order_id(), intersection_size, order.get_order_direction());
-                match COME_FROM {
-                    OrderOrigin::History => {
-                        self.insert_aggressive_order::<MarketOrder, { HistoryIntersectingLimitOrder }>(order)
-                    }
-                    OrderOrigin::Trader => {
-                        self.insert_aggressive_order::<MarketOrder, { TraderIntersectingLimitOrder }>(order)
-                    }
-                }
+    pub(crate) fn insert_limit_order<O, const COME_FROM: OrderOrigin>(&mut self, instrument: InstrumentID, mut order: O)
+        where O: PricedOrder
+    {
+        // Orders accumulate without matching during the auction call phase and the halts
+        if self.is_matching_stopped(instrument) {
+            self.rest_limit_order::<O, COME_FROM>(instrument, order);
+            return;
+        }
+        let price = order.get_price();
+
+        // Check that the Exchange have pending market orders
+        let mut pending_orders = std::mem::take(&mut self.books[instrument].trader_pending_market_orders);
+        self.match_pending_market_orders::<O, COME_FROM>(instrument, &mut order, &mut pending_orders);
+        self.books[instrument].trader_pending_market_orders = pending_orders;
+        if order.get_order_size() == Size(0) {
+            return;
+        }
+
+        // The order is executed against the opposite side of the Order Book up to its price, and the rest rests
+        match COME_FROM {
+            OrderOrigin::History => {
+                self.match_aggressive_order::<O, { HistoryIntersectingLimitOrder }>(instrument, &mut order, Some(price))
             }
-        } else {
-            let order = MarketOrder::new(order.get_order_id(), order.get_order_size(), order.get_order_direction());
-            match COME_FROM {
-                OrderOrigin::History => {
-                    self.insert_aggressive_order::<MarketOrder, { AggressiveOrderType::HistoryMarketOrder }>(order)
-                }
-                OrderOrigin::Trader => {
-                    self.insert_aggressive_order::<MarketOrder, { AggressiveOrderType::TraderMarketOrder }>(order)
-                }
+            OrderOrigin::Trader => {
+                self.match_aggressive_order::<O, { TraderIntersectingLimitOrder }>(instrument, &mut order, Some(price))
             }
+        }
+        if order.get_order_size() == Size(0) {
             return;
         }
+        self.rest_limit_order::<O, COME_FROM>(instrument, order)
+    }
 
-        // Insert Order in the Order Book
-        let mut insert_new_level = true;
-        let mut cursor = match order.get_order_direction() {
-            Direction::Buy => {
-                let mut cursor = self.bids.cursor_front_mut();
-                while let Some(ob_level) = cursor.current() {
-                    match ob_level.price.cmp(&price) {
-                        Ordering::Less => { break; }
-                        Ordering::Equal => {
-                            insert_new_level = false;
-                            break;
-                        }
-                        Ordering::Greater => { cursor.move_next() }
-                    }
-                }
-                cursor
+    fn rest_limit_order<O, const COME_FROM: OrderOrigin>(&mut self, instrument: InstrumentID, order: O)
+        where O: PricedOrder
+    {
+        if COME_FROM == OrderOrigin::Trader {
+            if let TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill = order.get_time_in_force() {
+                let reply = OrderCancelled(order.get_order_id(), CancellationReason::ImmediateOrCancel);
+                self.event_queue.schedule_reply_for_trader(reply);
+                return;
             }
-            Direction::Sell => {
-                let mut cursor = self.asks.cursor_front_mut();
-                while let Some(ob_level) = cursor.current() {
-                    match ob_level.price.cmp(&price) {
-                        Ordering::Greater => { break; }
-                        Ordering::Equal => {
-                            insert_new_level = false;
-                            break;
-                        }
-                        Ordering::Less => { cursor.move_next() }
-                    }
-                }
-                cursor
+        }
+
+        // Insert Order in the Order Book
+        let price = order.get_price();
+        let (size, hidden_size) = match order.get_peak_size() {
+            Some(peak_size) if COME_FROM == OrderOrigin::Trader && peak_size < order.get_order_size() => {
+                (peak_size, order.get_order_size() - peak_size)
             }
+            _ => { (order.get_order_size(), Size(0)) }
         };
-        if insert_new_level {
-            let iter = [OrderBookEntry { order_id: order.get_order_id(), size: order.get_order_size(), from: COME_FROM }];
-            cursor.insert_before(OrderBookLevel { price, queue: FromIterator::from_iter(iter) })
-        } else {
-            cursor.current().unwrap().queue.push_back(
-                OrderBookEntry {
-                    order_id: order.get_order_id(),
-                    size: order.get_order_size(),
-                    from: COME_FROM,
-                }
-            )
+        let entry = OrderBookEntry {
+            order_id: order.get_order_id(),
+            size,
+            from: COME_FROM,
+            owner: match COME_FROM {
+                OrderOrigin::Trader => { self.get_order_owner(order.get_order_id()) }
+                OrderOrigin::History => { None }
+            },
+            peak_size: order.get_peak_size(),
+            hidden_size,
+            post_only: order.get_post_only(),
+            peg: order.get_peg(),
+        };
+        match order.get_order_direction() {
+            Direction::Buy => { self.books[instrument].bids.push_back(price, entry) }
+            Direction::Sell => { self.books[instrument].asks.push_back(price, entry) }
         }
         if let OrderOrigin::Trader = COME_FROM {
-            self.trader_pending_limit_orders.insert(order.get_order_id(), (price, order.get_order_direction()));
+            self.books[instrument].trader_pending_limit_orders.insert(
+                order.get_order_id(),
+                (price, order.get_order_direction(), order.get_time_in_force()),
+            );
+            if let Some(peg) = order.get_peg() {
+                self.books[instrument].trader_pegged_orders.insert(order.get_order_id(), peg);
+            }
         }
     }
 
@@ -451,14 +1055,19 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
             }
         }
         if WAKEUP_SUBSCRIPTION {
-            if let Some(lag) = self.wakeup.gen_ns(&mut self.rng, self.current_dt) {
-                let next_time = self.current_dt + Duration::nanoseconds(lag.get() as i64);
-                self.event_queue.push(
-                    Event {
-                        datetime: next_time,
-                        body: EventBody::TraderWakeUp,
-                    }
-                )
+            for trader in self.get_trader_ids() {
+                if !self.traders[trader.0].subscriptions.wakeup {
+                    continue;
+                }
+                if let Some(lag) = self.wakeup.gen_ns(&mut self.rng, self.current_dt) {
+                    let next_time = self.current_dt + Duration::nanoseconds(lag.get() as i64);
+                    self.event_queue.push(
+                        Event {
+                            datetime: next_time,
+                            body: EventBody::TraderWakeUp(trader),
+                        }
+                    )
+                }
             }
         }
     }
@@ -470,29 +1079,30 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
         match subscription_type {
             SubscriptionSchedule::OrderBook => {
                 if OB_SUBSCRIPTION {
-                    let (depth, ns_gen) = &mut self.ob_depth_and_interval_ns;
-                    let get_snapshot = |ob_side: &LinkedList<OrderBookLevel>| {
+                    let depth = self.ob_depth_and_interval_ns.0;
+                    let get_snapshot = |ob_side: &OrderBookSide, depth: usize| {
                         ob_side.iter()
-                            .enumerate()
-                            .take_while(|(i, _)| i != depth)
-                            .map(|(_, level)| (level.price, level.get_ob_level_size()))
+                            .take(depth)
+                            .map(|level| (level.price, level.get_ob_level_size()))
                             .collect::<Vec<_>>()
                     };
-                    self.event_queue.push(
-                        Event {
-                            datetime: self.current_dt + Duration::nanoseconds(T::exchange_to_trader_latency(&mut self.rng, self.current_dt) as i64),
-                            body: EventBody::SubscriptionUpdate(
-                                SubscriptionUpdate::OrderBook(
-                                    OrderBookSnapshot {
-                                        bids: get_snapshot(&self.bids),
-                                        asks: get_snapshot(&self.asks),
-                                    }
-                                ),
-                                self.current_dt,
-                            ),
-                        }
-                    );
-                    if let Some(lag) = ns_gen.gen_ns(&mut self.rng, self.current_dt) {
+                    let snapshots: Vec<_> = self.books.iter()
+                        .filter(|book| book.subscriptions.order_book)
+                        .map(
+                            |book| {
+                                let depth = book.subscriptions.ob_depth.unwrap_or(depth);
+                                OrderBookSnapshot {
+                                    instrument: book.id,
+                                    bids: get_snapshot(&book.bids, depth),
+                                    asks: get_snapshot(&book.asks, depth),
+                                }
+                            }
+                        )
+                        .collect();
+                    for snapshot in snapshots {
+                        self.schedule_subscription_update(SubscriptionUpdate::OrderBook(snapshot))
+                    }
+                    if let Some(lag) = self.ob_depth_and_interval_ns.1.gen_ns(&mut self.rng, self.current_dt) {
                         self.event_queue.push(
                             Event {
                                 datetime: self.current_dt + Duration::nanoseconds(lag.get() as i64),
@@ -506,17 +1116,15 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
             }
             SubscriptionSchedule::TradeInfo => {
                 if TRD_SUBSCRIPTION {
-                    self.event_queue.push(
-                        Event {
-                            datetime: self.current_dt + Duration::nanoseconds(T::exchange_to_trader_latency(&mut self.rng, self.current_dt) as i64),
-                            body: EventBody::SubscriptionUpdate(
-                                SubscriptionUpdate::TradeInfo(
-                                    self.executed_trades.yield_trade_info()
-                                ),
-                                self.current_dt,
-                            ),
+                    for i in 0..self.books.count() {
+                        let instrument = self.books.get_id(i);
+                        let book = &mut self.books[instrument];
+                        let trade_info = book.executed_trades.yield_trade_info();
+                        if !book.subscriptions.trade_info {
+                            continue;
                         }
-                    );
+                        self.schedule_subscription_update(SubscriptionUpdate::TradeInfo(trade_info))
+                    }
                     if let Some(latency) = self.trade_info_interval_ns.gen_ns(&mut self.rng, self.current_dt) {
                         self.event_queue.push(
                             Event {
@@ -529,73 +1137,101 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                     unreachable!()
                 }
             }
+            SubscriptionSchedule::AuctionInfo(instrument, phase) => {
+                if self.books[instrument].auction_phase != Some(phase) {
+                    return;
+                }
+                let auction_info = Uncrossing::to_auction_info(self.get_auction_uncrossing(instrument), instrument, phase);
+                self.schedule_subscription_update(SubscriptionUpdate::AuctionInfo(auction_info));
+                if let Some(interval) = self.get_indicative_interval(instrument, phase) {
+                    self.event_queue.push(
+                        Event {
+                            datetime: self.current_dt + interval,
+                            body: EventBody::SubscriptionSchedule(SubscriptionSchedule::AuctionInfo(instrument, phase)),
+                        }
+                    )
+                }
+            }
         }
     }
 
-    fn handle_exchange_reply(&mut self, reply: ExchangeReply, exchange_dt: DateTime) {
+    fn handle_exchange_reply(&mut self, trader: TraderID, reply: ExchangeReply, exchange_dt: DateTime) {
         let delivery_dt = self.current_dt;
-        let trader_reactions = self.trader.handle_exchange_reply(exchange_dt, delivery_dt, reply);
-        let rng = &mut self.rng;
-        self.event_queue.extend(
-            trader_reactions.into_iter()
-                .map(
-                    |request| Event {
-                        datetime: delivery_dt + Duration::nanoseconds(T::trader_to_exchange_latency(rng, delivery_dt) as i64),
-                        body: EventBody::TraderRequest(request),
-                    }
-                )
-        )
+        let trader_reactions = self.traders[trader.0].trader.handle_exchange_reply(exchange_dt, delivery_dt, reply);
+        self.schedule_trader_requests(trader, trader_reactions, delivery_dt)
+    }
+
+    pub(crate) fn handle_trader_request(&mut self, trader: TraderID, request: TraderRequest) {
+        if !self.check_instrument(&request) {
+            return;
+        }
+        if let Some(reply) = self.check_risk_limits(trader, &request) {
+            self.event_queue.schedule_reply_for_trader(reply);
+            return;
+        }
+        match request {
+            PlaceLimitOrder(_) | PlaceMarketOrder(_) | PlaceStopOrder(_) => { self.submit_order(request) }
+            PlaceOcoOrders(first, second) => { self.place_oco_orders(first, second) }
+            PlaceBracketOrder(parent, take_profit, stop_loss) => {
+                self.place_bracket_order(parent, take_profit, stop_loss)
+            }
+            CancelAll => { self.cancel_trader_orders(trader, None, None) }
+            CancelAllOnSide(direction) => { self.cancel_trader_orders(trader, Some(direction), None) }
+            CancelInPriceRange(min_price, max_price) => {
+                self.cancel_trader_orders(trader, None, Some((min_price, max_price)))
+            }
+            CancelLimitOrder(order_id) => { self.cancel_limit_order(order_id) }
+            CancelMarketOrder(order_id) => { self.cancel_market_order(order_id) }
+            CancelStopOrder(order_id) => { self.cancel_stop_order(order_id) }
+            ModifyLimitOrder(order_id, price, size) => {
+                if size != Size(0) {
+                    self.modify_limit_order(order_id, price, size)
+                } else {
+                    self.event_queue.schedule_reply_for_trader(CannotModifyOrder(order_id, InabilityToModifyReason::ZeroSize))
+                }
+            }
+        }
     }
 
-    fn handle_trader_request(&mut self, request: TraderRequest) {
+    /// Submits the order of the placing request that has passed the routing and the risk checks
+    pub(crate) fn submit_order(&mut self, request: TraderRequest) {
         match request {
             PlaceLimitOrder(order) => {
-                if order.get_order_size() != Size(0) {
+                if order.get_order_size() != Size(0) && order.get_peak_size() != Some(Size(0)) {
                     self.submit_limit_order(order)
                 } else {
-                    self.event_queue.schedule_reply_for_trader::<T>(
-                        OrderPlacementDiscarded(order.get_order_id(), ZeroSize),
-                        self.current_dt,
-                        &mut self.rng,
-                    )
+                    self.event_queue.schedule_reply_for_trader(OrderPlacementDiscarded(order.get_order_id(), ZeroSize))
                 }
             }
             PlaceMarketOrder(order) => {
                 if order.get_order_size() != Size(0) {
                     self.submit_market_order(order)
                 } else {
-                    self.event_queue.schedule_reply_for_trader::<T>(
-                        OrderPlacementDiscarded(order.get_order_id(), ZeroSize),
-                        self.current_dt,
-                        &mut self.rng,
-                    )
+                    self.event_queue.schedule_reply_for_trader(OrderPlacementDiscarded(order.get_order_id(), ZeroSize))
                 }
             }
-            CancelLimitOrder(order_id) => { self.cancel_limit_order(order_id) }
-            CancelMarketOrder(order_id) => { self.cancel_market_order(order_id) }
+            PlaceStopOrder(order) => {
+                if order.get_order_size() != Size(0) {
+                    self.submit_stop_order(order)
+                } else {
+                    self.event_queue.schedule_reply_for_trader(OrderPlacementDiscarded(order.get_order_id(), ZeroSize))
+                }
+            }
+            _ => { unreachable!() }
         }
     }
 
-    fn handle_trader_wakeup(&mut self) {
+    fn handle_trader_wakeup(&mut self, trader: TraderID) {
         if WAKEUP_SUBSCRIPTION {
             let current_time = self.current_dt;
-            let trader_reactions = self.trader.handle_wakeup(current_time);
-            let rng = &mut self.rng;
-            self.event_queue.extend(
-                trader_reactions.into_iter()
-                    .map(
-                        |request| Event {
-                            datetime: current_time + Duration::nanoseconds(T::trader_to_exchange_latency(rng, current_time) as i64),
-                            body: EventBody::TraderRequest(request),
-                        }
-                    )
-            );
+            let trader_reactions = self.traders[trader.0].trader.handle_wakeup(current_time);
+            self.schedule_trader_requests(trader, trader_reactions, current_time);
             if self.has_history_events_in_queue {
-                if let Some(lag) = self.wakeup.gen_ns(rng, self.current_dt) {
+                if let Some(lag) = self.wakeup.gen_ns(&mut self.rng, self.current_dt) {
                     self.event_queue.push(
                         Event {
                             datetime: current_time + Duration::nanoseconds(lag.get() as i64),
-                            body: EventBody::TraderWakeUp,
+                            body: EventBody::TraderWakeUp(trader),
                         }
                     )
                 }
@@ -605,6 +1241,18 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
         }
     }
 
+    pub(crate) fn schedule_first_open_tryout(&mut self) {
+        if let Some(first_event) = self.event_queue.peek() {
+            let first_event_dt = first_event.datetime;
+            self.event_queue.push(
+                Event {
+                    datetime: (self.get_next_open_dt)(first_event_dt),
+                    body: EventBody::ExchangeOpenTryout,
+                }
+            )
+        }
+    }
+
     pub(crate)
     fn process_next_event(&mut self, event: Event) -> Result<(), ()> {
         self.current_dt = event.datetime;
@@ -612,44 +1260,105 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
             eprintln!("{} :: process_next_event :: EVENT :: {:?}", event.datetime, event.body)
         }
         match event.body {
-            EventBody::HistoryEvent(event) => {
-                self.handle_history_event(event)
+            EventBody::HistoryEvent(instrument, event) => {
+                self.handle_history_event(instrument, event)
             }
-            EventBody::TraderRequest(request) => {
-                self.handle_trader_request(request)
+            EventBody::TraderRequest(trader, request) => {
+                self.handle_arrived_trader_request(trader, request)
             }
-            EventBody::ExchangeReply(reply, exchange_ts) => {
-                self.handle_exchange_reply(reply, exchange_ts)
+            EventBody::ExchangeReply(trader, reply, exchange_ts) => {
+                self.handle_exchange_reply(trader, reply, exchange_ts)
             }
-            EventBody::SubscriptionUpdate(update, exchange_ts) => {
-                self.handle_subscription_update(update, exchange_ts)
+            EventBody::SubscriptionUpdate(trader, update, exchange_ts) => {
+                self.handle_subscription_update(trader, update, exchange_ts)
             }
             EventBody::SubscriptionSchedule(subscription_type) => {
                 self.handle_subscription_schedule(subscription_type)
             }
-            EventBody::TraderWakeUp => {
-                self.handle_trader_wakeup()
+            EventBody::TraderWakeUp(trader) => {
+                self.handle_trader_wakeup(trader)
+            }
+            EventBody::TraderOrderExpiry(order_id) => {
+                self.expire_order(order_id)
+            }
+            EventBody::HistoryOrderRefill(instrument, order_id) => {
+                self.refill_consumed_order(instrument, order_id)
+            }
+            EventBody::DelayedRequestsRelease(trader) => {
+                self.release_delayed_requests(trader)
+            }
+            EventBody::ClosingAuctionStart => {
+                if !self.exchange_closed {
+                    for i in 0..self.books.count() {
+                        let instrument = self.books.get_id(i);
+                        if self.books[instrument].auction_phase.is_some() {
+                            self.finish_auction(instrument)
+                        }
+                        self.start_auction(instrument, AuctionPhase::Closing)
+                    }
+                }
+            }
+            EventBody::AuctionUncross => {
+                for i in 0..self.books.count() {
+                    let instrument = self.books.get_id(i);
+                    if self.books[instrument].auction_phase == Some(AuctionPhase::Opening) {
+                        self.finish_auction(instrument)
+                    }
+                }
+            }
+            EventBody::TradingResumption(instrument) => {
+                if self.books[instrument].auction_phase == Some(AuctionPhase::Reopening) {
+                    self.finish_auction(instrument)
+                } else {
+                    self.finish_halt(instrument);
+                    self.trigger_stop_orders(instrument)
+                }
             }
             EventBody::ExchangeOpenTryout => {
                 if self.has_history_events_in_queue {
-                    for event in self.event_queue.0.iter() {
-                        if let EventBody::HistoryEvent(_) = event.0.body {
+                    for event in self.event_queue.events.iter() {
+                        if let EventBody::HistoryEvent(..) = event.0.body {
                             let event_dt = event.0.datetime;
                             let possible_next_close_dt = (self.get_next_close_dt)(self.current_dt);
                             if event_dt < possible_next_close_dt {
                                 self.exchange_closed = false;
-                                self.cleanup::<false>();
+                                for i in 0..self.books.count() {
+                                    let instrument = self.books.get_id(i);
+                                    let book = &mut self.books[instrument];
+                                    book.price_monitor.start_session(book.traded_price_range.get_last_price());
+                                    if self.opening_auction.is_some() {
+                                        self.start_auction(instrument, AuctionPhase::Opening)
+                                    }
+                                    self.cleanup::<false>(instrument)
+                                }
+                                for trader in self.get_trader_ids() {
+                                    let position_value = self.get_position_value(trader);
+                                    self.traders[trader.0].risk_gate.start_session(self.loss_limits, position_value)
+                                }
+                                if let Some(auction) = self.opening_auction {
+                                    self.event_queue.push(
+                                        Event {
+                                            datetime: min(self.current_dt + auction.duration, possible_next_close_dt),
+                                            body: EventBody::AuctionUncross,
+                                        }
+                                    )
+                                }
+                                if let Some(auction) = self.closing_auction {
+                                    self.event_queue.push(
+                                        Event {
+                                            datetime: max(possible_next_close_dt - auction.duration, self.current_dt),
+                                            body: EventBody::ClosingAuctionStart,
+                                        }
+                                    )
+                                }
                                 self.schedule_subscriptions_when_exchange_open();
-                                self.event_queue.extend([
-                                    Event {
-                                        datetime: self.current_dt + Duration::nanoseconds(T::exchange_to_trader_latency(&mut self.rng, self.current_dt) as i64),
-                                        body: EventBody::SubscriptionUpdate(SubscriptionUpdate::ExchangeOpen, self.current_dt),
-                                    },
+                                self.schedule_subscription_update(SubscriptionUpdate::ExchangeOpen);
+                                self.event_queue.push(
                                     Event {
                                         datetime: possible_next_close_dt,
                                         body: EventBody::ExchangeClosed,
                                     }
-                                ]);
+                                );
                             } else {
                                 self.event_queue.push(
                                     Event {
@@ -666,23 +1375,98 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                 }
             }
             EventBody::ExchangeClosed => {
+                for i in 0..self.books.count() {
+                    let instrument = self.books.get_id(i);
+                    // At-the-close orders are executed even if the closing auction has no call phase
+                    if self.books[instrument].auction_phase.is_none()
+                        && !(self.books[instrument].trader_close_limit_orders.is_empty() && self.books[instrument].trader_close_market_orders.is_empty())
+                    {
+                        self.start_auction(instrument, AuctionPhase::Closing)
+                    }
+                    if self.books[instrument].auction_phase.is_some() {
+                        self.finish_auction(instrument)
+                    }
+                    self.finish_halt(instrument)
+                }
                 self.exchange_closed = true;
                 if DEBUG {
                     eprintln!("{} :: process_next_event :: CLEANUP", event.datetime)
                 }
-                self.cleanup::<true>();
-                self.event_queue.extend([
-                    Event {
-                        datetime: self.current_dt + Duration::nanoseconds(T::exchange_to_trader_latency(&mut self.rng, self.current_dt) as i64),
-                        body: EventBody::SubscriptionUpdate(SubscriptionUpdate::ExchangeClosed, self.current_dt),
-                    },
+                for i in 0..self.books.count() {
+                    self.cleanup::<true>(self.books.get_id(i))
+                }
+                self.finish_message_accounting();
+                self.schedule_subscription_update(SubscriptionUpdate::ExchangeClosed);
+                self.event_queue.push(
                     Event {
                         datetime: (self.get_next_open_dt)((self.current_dt.date() + Duration::days(1)).and_hms(0, 0, 0)),
                         body: EventBody::ExchangeOpenTryout,
                     }
-                ])
+                )
             }
         };
+        for i in 0..self.books.count() {
+            let instrument = self.books.get_id(i);
+            self.check_trading_halts(instrument);
+            self.trigger_stop_orders(instrument);
+            self.reprice_pegged_orders(instrument)
+        }
+        self.enforce_order_groups();
+        self.check_loss_limits();
+        self.deliver_trader_replies();
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Sizes of the level orders that can be matched with the incoming order
+fn get_matchable_sizes(level: &OrderBookLevel,
+                       first_unmet: usize,
+                       oco_orders: &HashMap<OrderID, OrderID>,
+                       executions: &[TraderExecution]) -> Vec<Size> {
+    let mut met_oco_orders = Vec::new();
+    level.iter()
+        .enumerate()
+        .map(
+            |(index, order)| {
+                let is_matchable = index >= first_unmet && is_matchable(order, &mut met_oco_orders, oco_orders, executions);
+                if is_matchable { order.size } else { Size(0) }
+            }
+        )
+        .collect()
+}
+
+/// Only the first met order of the OCO pair can be executed, and not before the executed one
+fn is_matchable(order: &OrderBookEntry,
+                met_oco_orders: &mut Vec<OrderID>,
+                oco_orders: &HashMap<OrderID, OrderID>,
+                executions: &[TraderExecution]) -> bool {
+    match oco_orders.get(&order.order_id) {
+        Some(other_id) if order.from == OrderOrigin::Trader => {
+            let is_available = !met_oco_orders.contains(other_id)
+                && !is_oco_pair_executed(oco_orders, executions, order.order_id);
+            met_oco_orders.push(order.order_id);
+            is_available
+        }
+        _ => { true }
+    }
+}
+
+fn is_oco_pair_executed(oco_orders: &HashMap<OrderID, OrderID>, executions: &[TraderExecution], order_id: OrderID) -> bool {
+    match oco_orders.get(&order_id) {
+        Some(other_id) => { executions.iter().any(|execution| execution.order_id == *other_id) }
+        None => { false }
+    }
+}
+
+/// Returns the sizes by which the resting and the incoming orders of the same trader are decreased
+fn get_self_trade_decrements(mode: SelfTradePrevention, resting_size: Size, incoming_size: Size) -> (Size, Size) {
+    match mode {
+        SelfTradePrevention::CancelNewest => { (Size(0), incoming_size) }
+        SelfTradePrevention::CancelOldest => { (resting_size, Size(0)) }
+        SelfTradePrevention::CancelBoth => { (resting_size, incoming_size) }
+        SelfTradePrevention::DecrementAndCancel => {
+            let decrement = min(resting_size, incoming_size);
+            (decrement, decrement)
+        }
+    }
+}
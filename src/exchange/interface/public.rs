@@ -1,10 +1,22 @@
+use std::marker::PhantomData;
 use std::num::NonZeroU64;
 
-use crate::exchange::{Exchange, types::{Event, EventBody}};
+use crate::auction::CallAuction;
+use crate::exchange::{Exchange, types::{ExchangePolicies, HostedTrader, InstrumentBook, InstrumentBooks}};
+use crate::fees::{interface::FeeSchedule, schedules::NoFees};
+use crate::halt::{PriceBand, TradingHalt};
 use crate::history::parser::EventProcessor;
+use crate::impact::MarketImpact;
+use crate::instrument::InstrumentSpec;
 use crate::lags::interface::NanoSecondGenerator;
-use crate::trader::Trader;
-use crate::types::{DateTime, SeedableRng, StdRng};
+use crate::matching::{fifo::Fifo, interface::MatchingPolicy};
+use crate::order::{MarketOrderPolicy, SelfTradePrevention};
+use crate::queue_position::{interface::QueuePositionModel, models::Exact};
+use crate::risk::{LossLimits, RiskLimits};
+use crate::throttle::{RateLimit, ThrottlePolicy};
+use crate::trader::{subscriptions::{InstrumentSubscriptions, TraderSubscriptions}, Trader};
+use crate::types::{DateTime, Duration, InstrumentID, SeedableRng, Size, StdRng, TraderID};
+use crate::utils::ExpectWith;
 
 pub struct VoidNanoSecGen;
 
@@ -12,12 +24,12 @@ impl NanoSecondGenerator for VoidNanoSecGen {
     fn gen_ns(&mut self, _: &mut StdRng, _: DateTime) -> Option<NonZeroU64> { unreachable!() }
 }
 
-pub struct ExchangeBuilder<T, E> {
-    _dummy_a: T,
-    _dummy_b: E,
+pub struct ExchangeBuilder<T: ?Sized, E> {
+    market_order_policy: MarketOrderPolicy,
+    _dummy: PhantomData<(E, Box<T>)>,
 }
 
-impl<'a, T: Trader, E: EventProcessor> ExchangeBuilder<T, E>
+impl<'a, T: Trader + ?Sized, E: EventProcessor> ExchangeBuilder<T, E>
 {
     pub
     fn new<const TRD_UPDATES_OB: bool>(
@@ -25,8 +37,8 @@ impl<'a, T: Trader, E: EventProcessor> ExchangeBuilder<T, E>
         trader: &'a mut T,
         get_next_open_dt: fn(DateTime) -> DateTime,
         get_next_close_dt: fn(DateTime) -> DateTime,
-    ) -> Exchange<'a, T, E, VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, false, TRD_UPDATES_OB, false, false, false> {
-        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt)
+    ) -> Exchange<'a, T, E, VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees, false, TRD_UPDATES_OB, false, false, false> {
+        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt, MarketOrderPolicy::KeepPending)
     }
 
     pub
@@ -37,15 +49,48 @@ impl<'a, T: Trader, E: EventProcessor> ExchangeBuilder<T, E>
         get_next_close_dt: fn(DateTime) -> DateTime,
     ) -> Exchange<
         'a, T, E,
-        VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen,
+        VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees,
         true, TRD_UPDATES_OB, false, false, false
     > {
-        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt)
+        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt, MarketOrderPolicy::KeepPending)
+    }
+
+    /// Builder of the exchange handling the remainders of the trader market orders by the given policy.
+    /// `new` and `new_debug` keep them pending
+    pub
+    fn with_market_order_policy(policy: MarketOrderPolicy) -> Self {
+        ExchangeBuilder { market_order_policy: policy, _dummy: PhantomData }
+    }
+
+    pub
+    fn build<const TRD_UPDATES_OB: bool>(
+        self,
+        event_processor: E,
+        trader: &'a mut T,
+        get_next_open_dt: fn(DateTime) -> DateTime,
+        get_next_close_dt: fn(DateTime) -> DateTime,
+    ) -> Exchange<'a, T, E, VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees, false, TRD_UPDATES_OB, false, false, false> {
+        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt, self.market_order_policy)
+    }
+
+    pub
+    fn build_debug<const TRD_UPDATES_OB: bool>(
+        self,
+        event_processor: E,
+        trader: &'a mut T,
+        get_next_open_dt: fn(DateTime) -> DateTime,
+        get_next_close_dt: fn(DateTime) -> DateTime,
+    ) -> Exchange<
+        'a, T, E,
+        VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees,
+        true, TRD_UPDATES_OB, false, false, false
+    > {
+        Exchange::build(event_processor, trader, get_next_open_dt, get_next_close_dt, self.market_order_policy)
     }
 }
 
 impl<'a,
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
@@ -55,16 +100,17 @@ impl<'a,
 >
 Exchange<
     'a, T, E,
-    VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen,
+    VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees,
     DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
 >
 {
     fn build(mut event_processor: E,
              trader: &'a mut T,
              get_next_open_dt: fn(DateTime) -> DateTime,
-             get_next_close_dt: fn(DateTime) -> DateTime, ) -> Exchange<
+             get_next_close_dt: fn(DateTime) -> DateTime,
+             market_order_policy: MarketOrderPolicy, ) -> Exchange<
         'a, T, E,
-        VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen,
+        VoidNanoSecGen, VoidNanoSecGen, VoidNanoSecGen, Fifo, Exact, NoFees,
         DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
     > {
         let first_event = match event_processor.yield_next_event() {
@@ -76,14 +122,25 @@ Exchange<
             event_queue: Default::default(),
             event_processor,
             has_history_events_in_queue: true,
-            history_order_ids: Default::default(),
-            bids: Default::default(),
-            asks: Default::default(),
-            trader,
-            trader_pending_market_orders: Default::default(),
-            trader_pending_limit_orders: Default::default(),
+            books: InstrumentBooks::new(InstrumentBook::new(InstrumentID(0), Default::default())),
+            traders: vec![HostedTrader::new(trader)],
+            order_owners: Default::default(),
+            trader_oco_orders: Default::default(),
+            trader_bracket_orders: Default::default(),
             trader_submitted_orders: Default::default(),
-            executed_trades: Default::default(),
+            trader_executions: Default::default(),
+            market_order_policy,
+            self_trade_prevention: None,
+            market_impact: None,
+            opening_auction: None,
+            closing_auction: None,
+            price_band: None,
+            trading_halts: Vec::new(),
+            rate_limits: Vec::new(),
+            throttle_policy: ThrottlePolicy::Discard,
+            risk_limits: Default::default(),
+            loss_limits: None,
+            policies: ExchangePolicies { matching: Fifo, queue_position: Exact, fees: NoFees },
             current_dt: first_event.datetime,
             exchange_closed: true,
             get_next_open_dt,
@@ -102,11 +159,14 @@ Exchange<
 }
 
 impl<'a,
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     ObLagGen: NanoSecondGenerator,
     TrdLagGen: NanoSecondGenerator,
     WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
     const OB_SUBSCRIPTION: bool,
@@ -115,21 +175,13 @@ impl<'a,
 >
 Exchange<
     'a, T, E,
-    ObLagGen, TrdLagGen, WkpLagGen,
+    ObLagGen, TrdLagGen, WkpLagGen, M, Q, F,
     DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
 >
 {
     pub
     fn run_trades(&mut self) {
-        if let Some(first_event) = self.event_queue.peek() {
-            let first_event_dt = first_event.datetime;
-            self.event_queue.push(
-                Event {
-                    datetime: (self.get_next_open_dt)(first_event_dt),
-                    body: EventBody::ExchangeOpenTryout,
-                }
-            )
-        }
+        self.schedule_first_open_tryout();
         while let Some(event) = self.event_queue.pop() {
             if let Err(_) = self.process_next_event(event) {
                 return;
@@ -140,21 +192,32 @@ Exchange<
     pub
     fn ob_level_subscription_depth<G: NanoSecondGenerator>(self, ns_gen: G, depth: usize) -> Exchange<
         'a, T, E,
-        G, TrdLagGen, WkpLagGen,
+        G, TrdLagGen, WkpLagGen, M, Q, F,
         DEBUG, TRD_UPDATES_OB, true, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
     > {
         let Exchange {
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -168,14 +231,25 @@ Exchange<
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -190,21 +264,32 @@ Exchange<
     pub
     fn ob_level_subscription_full<G: NanoSecondGenerator>(self, ns_gen: G) -> Exchange<
         'a, T, E,
-        G, TrdLagGen, WkpLagGen,
+        G, TrdLagGen, WkpLagGen, M, Q, F,
         DEBUG, TRD_UPDATES_OB, true, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
     > {
         let Exchange {
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -218,14 +303,25 @@ Exchange<
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -240,21 +336,32 @@ Exchange<
     pub
     fn trade_info_subscription<G: NanoSecondGenerator>(self, ns_gen: G) -> Exchange<
         'a, T, E,
-        ObLagGen, G, WkpLagGen,
+        ObLagGen, G, WkpLagGen, M, Q, F,
         DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, true, WAKEUP_SUBSCRIPTION
     > {
         let Exchange {
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -268,14 +375,25 @@ Exchange<
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -290,21 +408,32 @@ Exchange<
     pub
     fn with_periodic_wakeup<G: NanoSecondGenerator>(self, ns_gen: G) -> Exchange<
         'a, T, E,
-        ObLagGen, TrdLagGen, G,
+        ObLagGen, TrdLagGen, G, M, Q, F,
         DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, true
     > {
         let Exchange {
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -318,14 +447,25 @@ Exchange<
             event_queue,
             event_processor,
             has_history_events_in_queue,
-            history_order_ids,
-            bids,
-            asks,
-            trader,
-            trader_pending_market_orders,
-            trader_pending_limit_orders,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
             trader_submitted_orders,
-            executed_trades,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
             current_dt,
             exchange_closed,
             get_next_open_dt,
@@ -337,5 +477,230 @@ Exchange<
         }
     }
 
+    pub
+    fn with_matching_policy<P: MatchingPolicy>(self, policy: P) -> Exchange<
+        'a, T, E,
+        ObLagGen, TrdLagGen, WkpLagGen, P, Q, F,
+        DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
+    > {
+        self.map_policies(
+            |ExchangePolicies { queue_position, fees, .. }| ExchangePolicies { matching: policy, queue_position, fees }
+        )
+    }
+
+    pub
+    fn with_queue_position_model<P: QueuePositionModel>(self, model: P) -> Exchange<
+        'a, T, E,
+        ObLagGen, TrdLagGen, WkpLagGen, M, P, F,
+        DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
+    > {
+        self.map_policies(
+            |ExchangePolicies { matching, fees, .. }| ExchangePolicies { matching, queue_position: model, fees }
+        )
+    }
+
+    /// Fee schedule evaluated at every trader execution. Executions are free by default
+    pub
+    fn with_fee_schedule<S: FeeSchedule>(self, schedule: S) -> Exchange<
+        'a, T, E,
+        ObLagGen, TrdLagGen, WkpLagGen, M, Q, S,
+        DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
+    > {
+        self.map_policies(
+            |ExchangePolicies { matching, queue_position, .. }| ExchangePolicies { matching, queue_position, fees: schedule }
+        )
+    }
+
+    fn map_policies<NewM: MatchingPolicy, NewQ: QueuePositionModel, NewF: FeeSchedule>(
+        self,
+        f: impl FnOnce(ExchangePolicies<M, Q, F>) -> ExchangePolicies<NewM, NewQ, NewF>,
+    ) -> Exchange<
+        'a, T, E,
+        ObLagGen, TrdLagGen, WkpLagGen, NewM, NewQ, NewF,
+        DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION
+    > {
+        let Exchange {
+            event_queue,
+            event_processor,
+            has_history_events_in_queue,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
+            trader_submitted_orders,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies,
+            current_dt,
+            exchange_closed,
+            get_next_open_dt,
+            get_next_close_dt,
+            rng,
+            ob_depth_and_interval_ns,
+            trade_info_interval_ns,
+            wakeup,
+        } = self;
+        Exchange {
+            event_queue,
+            event_processor,
+            has_history_events_in_queue,
+            books,
+            traders,
+            order_owners,
+            trader_oco_orders,
+            trader_bracket_orders,
+            trader_submitted_orders,
+            trader_executions,
+            market_order_policy,
+            self_trade_prevention,
+            market_impact,
+            opening_auction,
+            closing_auction,
+            price_band,
+            trading_halts,
+            rate_limits,
+            throttle_policy,
+            risk_limits,
+            loss_limits,
+            policies: f(policies),
+            current_dt,
+            exchange_closed,
+            get_next_open_dt,
+            get_next_close_dt,
+            rng,
+            ob_depth_and_interval_ns,
+            trade_info_interval_ns,
+            wakeup,
+        }
+    }
+
+    pub
+    fn with_self_trade_prevention(mut self, mode: SelfTradePrevention) -> Self {
+        self.self_trade_prevention = Some(mode);
+        self
+    }
+
+    pub
+    fn with_market_impact(mut self, impact: MarketImpact) -> Self {
+        if matches!(
+            impact,
+            MarketImpact::Resiliency { refill_size, refill_interval } if refill_size <= Size(0) || refill_interval <= Duration::zero()
+        ) {
+            panic!("Refill size and interval should be positive: {:?}", impact)
+        }
+        self.market_impact = Some(impact);
+        self
+    }
+
+    pub
+    fn with_opening_auction(mut self, auction: CallAuction) -> Self {
+        self.opening_auction = Some(auction);
+        self
+    }
+
+    /// At-the-close orders are executed at the session close even if there is no closing auction call phase
+    pub
+    fn with_closing_auction(mut self, auction: CallAuction) -> Self {
+        self.closing_auction = Some(auction);
+        self
+    }
+
+    pub
+    fn with_price_band(mut self, band: PriceBand) -> Self {
+        self.price_band = Some(band);
+        self
+    }
+
+    /// Several halts can be configured. The first one triggered by the trades of the event applies
+    pub
+    fn with_trading_halt(mut self, halt: TradingHalt) -> Self {
+        self.trading_halts.push(halt);
+        self
+    }
+
+    /// Specification of the default instrument, `InstrumentID(0)`
+    pub
+    fn with_instrument_spec(mut self, spec: InstrumentSpec) -> Self {
+        self.books[InstrumentID(0)].spec = spec;
+        self
+    }
+
+    /// Adds the instrument with its own order book, or replaces the specification of the added one.
+    /// The history events and the trader orders of the instruments not added are not handled
+    pub
+    fn with_instrument(mut self, instrument: InstrumentID, spec: InstrumentSpec) -> Self {
+        match self.books.get_mut(instrument) {
+            Some(book) => { book.spec = spec }
+            None => { self.books.insert(InstrumentBook::new(instrument, spec)) }
+        }
+        self
+    }
+
+    /// Hosts another trader, which gets the next `TraderID` and its own order ID namespace. Risk and rate limits,
+    /// fees and message statistics apply to each trader on its own
+    pub
+    fn with_trader(mut self, trader: &'a mut T) -> Self {
+        self.traders.push(HostedTrader::new(trader));
+        self
+    }
+
+    pub
+    fn with_trader_subscriptions(mut self, trader: TraderID, subscriptions: TraderSubscriptions) -> Self {
+        self.traders.get_mut(trader.0)
+            .expect_with(|| format!("Trader {:?} is not hosted by the exchange", trader))
+            .subscriptions = subscriptions;
+        self
+    }
+
+    pub
+    fn with_instrument_subscriptions(mut self, instrument: InstrumentID, subscriptions: InstrumentSubscriptions) -> Self {
+        self.books.get_mut(instrument)
+            .expect_with(|| format!("Instrument {:?} has not been added to the exchange", instrument))
+            .subscriptions = subscriptions;
+        self
+    }
+
+    /// Several rate limits with different windows can be configured. Mass-cancel requests count towards them,
+    /// but are never throttled
+    pub
+    fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        if limit.max_messages == 0 {
+            panic!("Rate limit should allow at least one message: {:?}", limit)
+        }
+        self.rate_limits.push(limit);
+        self
+    }
+
+    pub
+    fn with_throttle_policy(mut self, policy: ThrottlePolicy) -> Self {
+        self.throttle_policy = policy;
+        self
+    }
+
+    /// Requests breaching the limits are rejected before the exchange handles them
+    pub
+    fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk_limits = limits;
+        self
+    }
+
+    /// Trader losses are checked after every event
+    pub
+    fn with_loss_limits(mut self, limits: LossLimits) -> Self {
+        self.loss_limits = Some(limits);
+        self
+    }
+
     pub fn seed_rng(&mut self, seed: u64) { self.rng = StdRng::seed_from_u64(seed) }
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+use crate::exchange::Exchange;
+use crate::fees::interface::FeeSchedule;
+use crate::halt::PriceBand;
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::{CancellationReason, DiscardingReason, ExchangeReply, InabilityToModifyReason, TraderRequest};
+use crate::order::{Order, PricedOrder};
+use crate::queue_position::interface::QueuePositionModel;
+use crate::risk::RiskLimitBreach;
+use crate::trader::Trader;
+use crate::types::{Direction, InstrumentID, Price, Size, TraderID};
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    /// Returns the rejection of the request breaching the pre-trade risk limits, if any.
+    /// Order groups are checked order by order as they are placed
+    pub(crate) fn check_risk_limits(&self, trader: TraderID, request: &TraderRequest) -> Option<ExchangeReply> {
+        if self.traders[trader.0].risk_gate.is_kill_switch_engaged() {
+            return match request {
+                TraderRequest::PlaceLimitOrder(order) => {
+                    Some(ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), DiscardingReason::KillSwitch))
+                }
+                TraderRequest::PlaceMarketOrder(order) => {
+                    Some(ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), DiscardingReason::KillSwitch))
+                }
+                TraderRequest::PlaceStopOrder(order) => {
+                    Some(ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), DiscardingReason::KillSwitch))
+                }
+                TraderRequest::ModifyLimitOrder(order_id, ..) => {
+                    Some(ExchangeReply::CannotModifyOrder(*order_id, InabilityToModifyReason::KillSwitch))
+                }
+                _ => { None }
+            };
+        }
+        match request {
+            TraderRequest::PlaceLimitOrder(order) => {
+                // Pegged order price is not known until the order is submitted
+                let limit_price = Some(order.get_price()).filter(|_| order.get_peg().is_none());
+                self.check_order_risk::<true>(order.get_instrument(), trader, order.get_order_direction(), order.get_order_size(), Some(order.get_price()), limit_price)
+                    .map(|breach| ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), breach.into()))
+            }
+            TraderRequest::PlaceMarketOrder(order) => {
+                self.check_order_risk::<true>(order.get_instrument(), trader, order.get_order_direction(), order.get_order_size(), None, None)
+                    .map(|breach| ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), breach.into()))
+            }
+            TraderRequest::PlaceStopOrder(order) => {
+                let price = order.get_limit_price().unwrap_or(order.get_stop_price());
+                self.check_order_risk::<true>(order.get_instrument(), trader, order.get_order_direction(), order.get_order_size(), Some(price), None)
+                    .map(|breach| ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), breach.into()))
+            }
+            TraderRequest::ModifyLimitOrder(order_id, price, size) => {
+                let &instrument = self.trader_submitted_orders.get(order_id)?;
+                let &(_, direction, _) = self.books[instrument].trader_pending_limit_orders.get(order_id)?;
+                let limit_price = Some(*price).filter(|_| !self.books[instrument].trader_pegged_orders.contains_key(order_id));
+                let reason = match self.check_order_risk::<false>(instrument, trader, direction, *size, Some(*price), limit_price)? {
+                    RiskLimitBreach::MaxOrderSizeExceeded => { InabilityToModifyReason::MaxOrderSizeExceeded }
+                    RiskLimitBreach::MaxNotionalExceeded => { InabilityToModifyReason::MaxNotionalExceeded }
+                    RiskLimitBreach::MaxPositionExceeded => { InabilityToModifyReason::MaxPositionExceeded }
+                    RiskLimitBreach::FatFingerPrice => { InabilityToModifyReason::FatFingerPrice }
+                    RiskLimitBreach::MaxOpenOrdersExceeded => { unreachable!("Modification does not open an order") }
+                };
+                Some(ExchangeReply::CannotModifyOrder(*order_id, reason))
+            }
+            _ => { None }
+        }
+    }
+
+    /// Returns the limit breached by the order, if any. The order is valued at `price`, or at the touch if there is
+    /// none, and its `limit_price`, if any, is checked against the touch
+    fn check_order_risk<const IS_NEW: bool>(&self,
+                                            instrument: InstrumentID,
+                                            trader: TraderID,
+                                            direction: Direction,
+                                            size: Size,
+                                            price: Option<Price>,
+                                            limit_price: Option<Price>) -> Option<RiskLimitBreach> {
+        let limits = &self.risk_limits;
+        let touch = self.get_best_price(instrument, match direction {
+            Direction::Buy => { Direction::Sell }
+            Direction::Sell => { Direction::Buy }
+        });
+        if limits.max_order_size.map_or(false, |max_order_size| size > max_order_size) {
+            Some(RiskLimitBreach::MaxOrderSizeExceeded)
+        } else if matches!(
+            (limits.max_notional, price.or(touch)),
+            (Some(max_notional), Some(price)) if self.books[instrument].spec.get_notional(price, size) > max_notional
+        ) {
+            Some(RiskLimitBreach::MaxNotionalExceeded)
+        } else if IS_NEW && limits.max_open_orders.map_or(false, |max_open_orders| self.get_open_trader_order_count(trader) >= max_open_orders) {
+            Some(RiskLimitBreach::MaxOpenOrdersExceeded)
+        } else if self.traders[trader.0].risk_gate.exceeds_position(limits, instrument, direction, size) {
+            Some(RiskLimitBreach::MaxPositionExceeded)
+        } else if matches!(
+            (limits.max_price_distance_bps, touch, limit_price),
+            (Some(width_bps), Some(touch), Some(price)) if !PriceBand { width_bps }.contains(touch, price)
+        ) {
+            Some(RiskLimitBreach::FatFingerPrice)
+        } else {
+            None
+        }
+    }
+
+    /// Engages the kill switch of each trader whose losses breach the limits, cancelling its active orders
+    pub(crate) fn check_loss_limits(&mut self) {
+        if self.loss_limits.is_none() {
+            return;
+        }
+        for trader in self.get_trader_ids() {
+            let position_value = self.get_position_value(trader);
+            if let Some(reason) = self.traders[trader.0].risk_gate.check_losses(self.loss_limits, position_value) {
+                for order_id in self.get_active_trader_order_ids(None, None) {
+                    if self.get_order_owner(order_id) == Some(trader) {
+                        self.cancel_trader_order(order_id, CancellationReason::KillSwitch);
+                    }
+                }
+                let reply = ExchangeReply::KillSwitchEngaged(reason);
+                self.event_queue.schedule_reply_to_trader(trader, reply);
+            }
+        }
+    }
+
+    /// Value of the trader positions in all the instruments at their last traded prices
+    pub(crate) fn get_position_value(&self, trader: TraderID) -> f64 {
+        let risk_gate = &self.traders[trader.0].risk_gate;
+        self.books.iter()
+            .filter_map(
+                |book| book.traded_price_range.get_last_price()
+                    .map(|price| book.spec.get_notional(price, risk_gate.get_position(book.id)))
+            )
+            .sum()
+    }
+}
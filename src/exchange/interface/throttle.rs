@@ -0,0 +1,148 @@
+use crate::exchange::{Exchange, types::{Event, EventBody}};
+use crate::fees::interface::FeeSchedule;
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::{
+    DiscardingReason,
+    ExchangeReply,
+    InabilityToCancelReason,
+    InabilityToModifyReason,
+    SubscriptionUpdate,
+    TraderRequest,
+};
+use crate::order::Order;
+use crate::queue_position::interface::QueuePositionModel;
+use crate::throttle::ThrottlePolicy;
+use crate::trader::Trader;
+use crate::types::TraderID;
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    /// Handles the request arrived from the trader unless it exceeds the rate limits
+    pub(crate) fn handle_arrived_trader_request(&mut self, trader: TraderID, request: TraderRequest) {
+        let request = match self.translate_trader_request(trader, request) {
+            Some(request) => { request }
+            None => { return; }
+        };
+        // Mass-cancel requests count towards the rate limits but are never throttled
+        if let TraderRequest::CancelAll | TraderRequest::CancelAllOnSide(_) | TraderRequest::CancelInPriceRange(..) = request {
+            self.handle_counted_trader_request(trader, request);
+            return;
+        }
+        let hosted = &mut self.traders[trader.0];
+        // Delayed requests keep their order of arrival
+        if !hosted.throttle.delayed.is_empty() {
+            hosted.message_statistics.rate_limited += 1;
+            hosted.throttle.delayed.push_back(request);
+            return;
+        }
+        match hosted.throttle.get_next_slot(&self.rate_limits, self.current_dt) {
+            None => { self.handle_counted_trader_request(trader, request) }
+            Some(next_slot) => {
+                hosted.message_statistics.rate_limited += 1;
+                match self.throttle_policy {
+                    ThrottlePolicy::Discard => { self.discard_rate_limited_request(request) }
+                    ThrottlePolicy::Delay => {
+                        hosted.throttle.delayed.push_back(request);
+                        self.event_queue.push(
+                            Event {
+                                datetime: next_slot,
+                                body: EventBody::DelayedRequestsRelease(trader),
+                            }
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_counted_trader_request(&mut self, trader: TraderID, request: TraderRequest) {
+        let hosted = &mut self.traders[trader.0];
+        hosted.throttle.record(&self.rate_limits, self.current_dt);
+        hosted.message_statistics.messages += 1;
+        self.handle_trader_request(trader, request)
+    }
+
+    pub(crate) fn release_delayed_requests(&mut self, trader: TraderID) {
+        loop {
+            let hosted = &mut self.traders[trader.0];
+            if hosted.throttle.delayed.is_empty() {
+                return;
+            }
+            match hosted.throttle.get_next_slot(&self.rate_limits, self.current_dt) {
+                None => {
+                    let request = hosted.throttle.delayed.pop_front().unwrap();
+                    self.handle_counted_trader_request(trader, request)
+                }
+                Some(next_slot) => {
+                    self.event_queue.push(
+                        Event {
+                            datetime: next_slot,
+                            body: EventBody::DelayedRequestsRelease(trader),
+                        }
+                    );
+                    return;
+                }
+            }
+        }
+    }
+
+    fn discard_rate_limited_request(&mut self, request: TraderRequest) {
+        let order_ids = match request {
+            TraderRequest::PlaceLimitOrder(order) => { vec![order.get_order_id()] }
+            TraderRequest::PlaceMarketOrder(order) => { vec![order.get_order_id()] }
+            TraderRequest::PlaceStopOrder(order) => { vec![order.get_order_id()] }
+            TraderRequest::PlaceOcoOrders(first, second) => { vec![first.get_order_id(), second.get_order_id()] }
+            TraderRequest::PlaceBracketOrder(parent, take_profit, stop_loss) => {
+                vec![parent.get_order_id(), take_profit.get_order_id(), stop_loss.get_order_id()]
+            }
+            TraderRequest::CancelLimitOrder(order_id)
+            | TraderRequest::CancelMarketOrder(order_id)
+            | TraderRequest::CancelStopOrder(order_id) => {
+                let reply = ExchangeReply::CannotCancelOrder(order_id, InabilityToCancelReason::RateLimited);
+                self.event_queue.schedule_reply_for_trader(reply);
+                return;
+            }
+            TraderRequest::ModifyLimitOrder(order_id, _, _) => {
+                let reply = ExchangeReply::CannotModifyOrder(order_id, InabilityToModifyReason::RateLimited);
+                self.event_queue.schedule_reply_for_trader(reply);
+                return;
+            }
+            TraderRequest::CancelAll | TraderRequest::CancelAllOnSide(_) | TraderRequest::CancelInPriceRange(..) => {
+                unreachable!()
+            }
+        };
+        for order_id in order_ids {
+            let reply = ExchangeReply::OrderPlacementDiscarded(order_id, DiscardingReason::RateLimited);
+            self.event_queue.schedule_reply_for_trader(reply);
+        }
+    }
+
+    /// Reports the message counts of the session to each trader and handles the requests still delayed,
+    /// which are rejected as the exchange is closed
+    pub(crate) fn finish_message_accounting(&mut self) {
+        for trader in self.get_trader_ids() {
+            for request in std::mem::take(&mut self.traders[trader.0].throttle.delayed) {
+                self.handle_trader_request(trader, request)
+            }
+            let statistics = std::mem::take(&mut self.traders[trader.0].message_statistics);
+            self.schedule_subscription_update_to_trader(trader, &SubscriptionUpdate::MessageStatistics(statistics))
+        }
+    }
+}
@@ -1,96 +1,270 @@
-use std::collections::hash_map::Entry;
+use std::cmp::{max, min};
+use std::collections::LinkedList;
 
-use crate::exchange::{Exchange, interface::private::AggressiveOrderType, types::{Event, EventBody}};
+use crate::auction::AuctionPhase;
+use crate::exchange::{Exchange, types::{Event, EventBody}};
+use crate::fees::interface::FeeSchedule;
 use crate::history::{parser::EventProcessor, types::OrderOrigin};
 use crate::lags::interface::NanoSecondGenerator;
-use crate::message::{CancellationReason, DiscardingReason, ExchangeReply, InabilityToCancelReason, SubscriptionUpdate};
-use crate::order::{LimitOrder, MarketOrder, Order};
+use crate::matching::interface::MatchingPolicy;
+use crate::message::{
+    CancellationReason,
+    DiscardingReason,
+    ExchangeReply,
+    InabilityToCancelReason,
+    InabilityToModifyReason,
+    SubscriptionUpdate,
+};
+use crate::order::{GroupOrder, LimitOrder, MarketOrder, Order, PostOnlyMode, PricedOrder, StopOrder, TimeInForce};
+use crate::queue_position::interface::QueuePositionModel;
 use crate::trader::Trader;
-use crate::types::{DateTime, Direction, Duration, OrderID};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size, TraderID};
+use crate::utils::ExpectWith;
 
 impl<
-    T: Trader,
+    T: Trader + ?Sized,
     E: EventProcessor,
     ObLagGen: NanoSecondGenerator,
     TrdLagGen: NanoSecondGenerator,
     WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
     const DEBUG: bool,
     const TRD_UPDATES_OB: bool,
     const OB_SUBSCRIPTION: bool,
     const TRD_SUBSCRIPTION: bool,
     const WAKEUP_SUBSCRIPTION: bool
 >
-Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
 {
-    pub(crate) fn handle_subscription_update(&mut self, update: SubscriptionUpdate, exchange_ts: DateTime) {
+    pub(crate) fn handle_subscription_update(&mut self, trader: TraderID, update: SubscriptionUpdate, exchange_ts: DateTime) {
         let delivery_dt = self.current_dt;
+        let hosted = &mut self.traders[trader.0];
         let trader_reactions = match update {
             SubscriptionUpdate::OrderBook(ob_snapshot) => {
-                self.trader.handle_order_book_snapshot(exchange_ts, delivery_dt, ob_snapshot)
+                hosted.trader.handle_order_book_snapshot(exchange_ts, delivery_dt, ob_snapshot)
             }
             SubscriptionUpdate::TradeInfo(trade_info) => {
-                self.trader.handle_trade_info_update(exchange_ts, delivery_dt, trade_info)
+                hosted.trader.handle_trade_info_update(exchange_ts, delivery_dt, trade_info)
+            }
+            SubscriptionUpdate::AuctionInfo(auction_info) => {
+                hosted.trader.handle_auction_info(exchange_ts, delivery_dt, auction_info)
+            }
+            SubscriptionUpdate::MessageStatistics(statistics) => {
+                hosted.trader.handle_message_statistics(exchange_ts, delivery_dt, statistics);
+                return;
             }
             SubscriptionUpdate::ExchangeOpen => {
-                self.trader.exchange_open(exchange_ts, delivery_dt);
+                hosted.trader.exchange_open(exchange_ts, delivery_dt);
                 return;
             }
             SubscriptionUpdate::ExchangeClosed => {
-                self.trader.exchange_closed(exchange_ts, delivery_dt);
+                hosted.trader.exchange_closed(exchange_ts, delivery_dt);
                 return;
             }
         };
-        let rng = &mut self.rng;
-        self.event_queue.extend(
-            trader_reactions
-                .into_iter()
-                .map(
-                    |request| Event {
-                        datetime: delivery_dt + Duration::nanoseconds(T::trader_to_exchange_latency(rng, delivery_dt) as i64),
-                        body: EventBody::TraderRequest(request),
+        self.schedule_trader_requests(trader, trader_reactions, delivery_dt)
+    }
+
+    pub(crate) fn get_limit_order_available_liquidity(&self, instrument: InstrumentID, order: &LimitOrder) -> Size {
+        let pending_market_orders_size: Size = self.books[instrument].trader_pending_market_orders.iter()
+            .filter(|pending| pending.get_order_direction() != order.get_order_direction())
+            .map(|pending| pending.get_order_size())
+            .sum();
+        pending_market_orders_size + self.get_available_liquidity(instrument, order.get_order_direction(), Some(order.get_price()))
+    }
+
+    pub(crate) fn get_market_order_available_liquidity(&self, instrument: InstrumentID, order: &MarketOrder) -> Size {
+        let direction = order.get_order_direction();
+        self.get_available_liquidity(instrument, direction, self.get_market_order_protection_price(instrument, direction))
+    }
+
+    fn schedule_order_expiry(&mut self, order_id: OrderID, time_in_force: TimeInForce) {
+        if let TimeInForce::GoodTillTime(expiry_dt) = time_in_force {
+            self.event_queue.push(
+                Event {
+                    datetime: expiry_dt,
+                    body: EventBody::TraderOrderExpiry(order_id),
+                }
+            )
+        }
+    }
+
+    pub(crate) fn submit_limit_order(&mut self, mut order: LimitOrder) {
+        let instrument = order.get_instrument();
+        let order_id = order.get_order_id();
+        let direction = order.get_order_direction();
+        let requested_price = order.get_price();
+        if let Some(price) = order.get_peg().and_then(|peg| self.get_peg_price(instrument, order.get_order_direction(), peg)) {
+            order.set_price(price)
+        }
+        let reply = if !self.is_now_trading_time() {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::ExchangeClosed,
+            )
+        } else if self.trader_submitted_orders.contains_key(&order_id) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::OrderWithSuchIDAlreadySubmitted,
+            )
+        } else if self.are_placements_halted(instrument) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::TradingHalted,
+            )
+        } else if let Some(reason) = self.books[instrument].spec.check_order(Some(order.get_price()), order.get_order_size()) {
+            ExchangeReply::OrderPlacementDiscarded(order_id, reason.into())
+        } else if !self.is_within_price_band(instrument, order.get_price()) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::OutsidePriceBand,
+            )
+        } else if matches!(order.get_time_in_force(), TimeInForce::GoodTillTime(dt) if dt <= self.current_dt) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::AlreadyExpired,
+            )
+        } else if order.get_time_in_force() == TimeInForce::FillOrKill
+            && self.get_limit_order_available_liquidity(instrument, &order) < order.get_order_size()
+        {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::InsufficientLiquidity,
+            )
+        } else if let Some(price) = self.get_post_only_price(instrument, &order) {
+            if price != requested_price {
+                self.event_queue.schedule_reply_for_trader(ExchangeReply::OrderRepriced(order_id, price));
+                order.set_price(price)
+            }
+            self.schedule_order_expiry(order_id, order.get_time_in_force());
+            if order.get_time_in_force() == TimeInForce::AtTheClose && self.books[instrument].auction_phase != Some(AuctionPhase::Closing) {
+                self.books[instrument].trader_close_limit_orders.push(order)
+            } else {
+                self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, order);
+            }
+            self.record_submitted_order(instrument, order_id, direction);
+            ExchangeReply::OrderAccepted(order_id)
+        } else {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::WouldTakeLiquidity,
+            )
+        };
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    /// Returns the price at which a post-only order can rest without taking liquidity,
+    /// or None if the order has to be rejected. Repriced orders rest one tick behind the opposite best price
+    fn get_post_only_price(&self, instrument: InstrumentID, order: &LimitOrder) -> Option<Price> {
+        let price = order.get_price();
+        match (order.get_post_only(), order.get_order_direction()) {
+            (None, _) => { Some(price) }
+            (Some(mode), Direction::Buy) => {
+                match self.get_best_price(instrument, Direction::Sell) {
+                    Some(best_ask) if best_ask <= price => {
+                        match mode {
+                            PostOnlyMode::Reject => { None }
+                            PostOnlyMode::Reprice => { Some(self.books[instrument].spec.round_down(Price(best_ask.0 - 1))) }
+                        }
                     }
-                )
-        )
+                    _ => { Some(price) }
+                }
+            }
+            (Some(mode), Direction::Sell) => {
+                match self.get_best_price(instrument, Direction::Buy) {
+                    Some(best_bid) if best_bid >= price => {
+                        match mode {
+                            PostOnlyMode::Reject => { None }
+                            PostOnlyMode::Reprice => { Some(self.books[instrument].spec.round_up(Price(best_bid.0 + 1))) }
+                        }
+                    }
+                    _ => { Some(price) }
+                }
+            }
+        }
     }
 
-    pub(crate) fn submit_limit_order(&mut self, order: LimitOrder) {
+    pub(crate) fn submit_market_order(&mut self, order: MarketOrder) {
+        let instrument = order.get_instrument();
         let order_id = order.get_order_id();
+        let direction = order.get_order_direction();
         let reply = if !self.is_now_trading_time() {
             ExchangeReply::OrderPlacementDiscarded(
                 order_id,
                 DiscardingReason::ExchangeClosed,
             )
-        } else if self.trader_submitted_orders.contains(&order_id) {
+        } else if self.trader_submitted_orders.contains_key(&order_id) {
             ExchangeReply::OrderPlacementDiscarded(
                 order_id,
                 DiscardingReason::OrderWithSuchIDAlreadySubmitted,
             )
+        } else if self.are_placements_halted(instrument) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::TradingHalted,
+            )
+        } else if let Some(reason) = self.books[instrument].spec.check_order(None, order.get_order_size()) {
+            ExchangeReply::OrderPlacementDiscarded(order_id, reason.into())
+        } else if matches!(order.get_time_in_force(), TimeInForce::GoodTillTime(dt) if dt <= self.current_dt) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::AlreadyExpired,
+            )
+        } else if order.get_time_in_force() == TimeInForce::FillOrKill
+            && self.get_market_order_available_liquidity(instrument, &order) < order.get_order_size()
+        {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::InsufficientLiquidity,
+            )
         } else {
-            self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(order);
-            self.trader_submitted_orders.insert(order_id);
+            self.schedule_order_expiry(order_id, order.get_time_in_force());
+            if order.get_time_in_force() == TimeInForce::AtTheClose && self.books[instrument].auction_phase != Some(AuctionPhase::Closing) {
+                self.books[instrument].trader_close_market_orders.push_back(order)
+            } else {
+                self.insert_trader_market_order(instrument, order);
+            }
+            self.record_submitted_order(instrument, order_id, direction);
             ExchangeReply::OrderAccepted(order_id)
         };
-        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+        self.event_queue.schedule_reply_for_trader(reply);
     }
 
-    pub(crate) fn submit_market_order(&mut self, order: MarketOrder) {
+    pub(crate) fn submit_stop_order(&mut self, order: StopOrder) {
+        let instrument = order.get_instrument();
         let order_id = order.get_order_id();
+        let direction = order.get_order_direction();
         let reply = if !self.is_now_trading_time() {
             ExchangeReply::OrderPlacementDiscarded(
                 order_id,
                 DiscardingReason::ExchangeClosed,
             )
-        } else if self.trader_submitted_orders.contains(&order_id) {
+        } else if self.trader_submitted_orders.contains_key(&order_id) {
             ExchangeReply::OrderPlacementDiscarded(
                 order_id,
                 DiscardingReason::OrderWithSuchIDAlreadySubmitted,
             )
+        } else if self.are_placements_halted(instrument) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::TradingHalted,
+            )
+        } else if let Some(reason) = self.books[instrument].spec.check_order(order.get_limit_price(), order.get_order_size()) {
+            ExchangeReply::OrderPlacementDiscarded(order_id, reason.into())
+        } else if matches!(order.get_time_in_force(), TimeInForce::GoodTillTime(dt) if dt <= self.current_dt) {
+            ExchangeReply::OrderPlacementDiscarded(
+                order_id,
+                DiscardingReason::AlreadyExpired,
+            )
         } else {
-            self.insert_aggressive_order::<MarketOrder, { AggressiveOrderType::TraderMarketOrder }>(order);
-            self.trader_submitted_orders.insert(order_id);
+            self.schedule_order_expiry(order_id, order.get_time_in_force());
+            self.books[instrument].trader_pending_stop_orders.push(order);
+            self.record_submitted_order(instrument, order_id, direction);
             ExchangeReply::OrderAccepted(order_id)
         };
-        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+        self.event_queue.schedule_reply_for_trader(reply);
     }
 
     pub(crate) fn cancel_limit_order(&mut self, order_id: OrderID) {
@@ -99,49 +273,126 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                 order_id,
                 InabilityToCancelReason::ExchangeClosed,
             )
-        } else if !self.trader_submitted_orders.contains(&order_id) {
+        } else if !self.trader_submitted_orders.contains_key(&order_id) {
             ExchangeReply::CannotCancelOrder(
                 order_id,
                 InabilityToCancelReason::OrderHasNotBeenSubmitted,
             )
         } else {
-            match self.trader_pending_limit_orders.entry(order_id) {
-                Entry::Occupied(value) => {
-                    let (price, direction) = value.get();
-                    let mut side_cursor = match direction {
-                        Direction::Buy => { self.bids.cursor_front_mut() }
-                        Direction::Sell => { self.asks.cursor_front_mut() }
+            let instrument = self.trader_submitted_orders[&order_id];
+            match self.books[instrument].trader_pending_limit_orders.remove(&order_id) {
+                Some((price, direction, _)) => {
+                    self.remove_trader_limit_order_from_ob(instrument, order_id, price, direction);
+                    ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested)
+                }
+                None => {
+                    match self.books[instrument].trader_close_limit_orders.iter().position(|order| order.get_order_id() == order_id) {
+                        Some(i) => {
+                            self.books[instrument].trader_close_limit_orders.remove(i);
+                            ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested)
+                        }
+                        None => {
+                            ExchangeReply::CannotCancelOrder(
+                                order_id,
+                                InabilityToCancelReason::OrderAlreadyExecuted,
+                            )
+                        }
+                    }
+                }
+            }
+        };
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    pub(crate) fn modify_limit_order(&mut self, order_id: OrderID, new_price: Price, new_size: Size) {
+        let reply = if !self.is_now_trading_time() {
+            ExchangeReply::CannotModifyOrder(
+                order_id,
+                InabilityToModifyReason::ExchangeClosed,
+            )
+        } else if let Some(&instrument) = self.trader_submitted_orders.get(&order_id) {
+            self.modify_submitted_limit_order(instrument, order_id, new_price, new_size)
+        } else {
+            ExchangeReply::CannotModifyOrder(
+                order_id,
+                InabilityToModifyReason::OrderHasNotBeenSubmitted,
+            )
+        };
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    fn modify_submitted_limit_order(&mut self,
+                                    instrument: InstrumentID,
+                                    order_id: OrderID,
+                                    new_price: Price,
+                                    new_size: Size) -> ExchangeReply {
+        if self.are_placements_halted(instrument) {
+            ExchangeReply::CannotModifyOrder(
+                order_id,
+                InabilityToModifyReason::TradingHalted,
+            )
+        } else if let Some(reason) = self.books[instrument].spec.check_order(
+            Some(new_price).filter(|_| !self.books[instrument].trader_pegged_orders.contains_key(&order_id)),
+            new_size,
+        ) {
+            ExchangeReply::CannotModifyOrder(order_id, reason.into())
+        } else if !self.books[instrument].trader_pegged_orders.contains_key(&order_id) && !self.is_within_price_band(instrument, new_price) {
+            ExchangeReply::CannotModifyOrder(
+                order_id,
+                InabilityToModifyReason::OutsidePriceBand,
+            )
+        } else {
+            match self.books[instrument].trader_pending_limit_orders.get(&order_id) {
+                Some(&(price, direction, time_in_force)) => {
+                    // Pegged order keeps following its reference price
+                    let new_price = match self.books[instrument].trader_pegged_orders.get(&order_id) {
+                        Some(&peg) => { self.get_peg_price(instrument, direction, peg).unwrap_or(new_price) }
+                        None => { new_price }
+                    };
+                    // Size decrease at the same price keeps the queue position
+                    let order = self.find_trader_limit_order(instrument, order_id, price, direction);
+                    let replacement = match &order {
+                        Some(order) => { order.to_limit_order(new_size, direction, new_price, time_in_force) }
+                        None => { LimitOrder::new(order_id, new_size, direction, new_price).with_time_in_force(time_in_force) }
+                    };
+                    let keeps_priority = match order {
+                        Some(order) if price == new_price && new_size <= order.size + order.hidden_size => {
+                            order.hidden_size = max(new_size - order.size, Size(0));
+                            order.size = min(order.size, new_size);
+                            true
+                        }
+                        _ => { false }
                     };
-                    while let Some(level) = side_cursor.current() {
-                        if level.price == *price {
-                            let level_size = level.queue.len();
-                            let mut level_cursor = level.queue.cursor_front_mut();
-                            while let Some(order) = level_cursor.current() {
-                                if order.from == OrderOrigin::Trader && order.order_id == order_id {
-                                    level_cursor.remove_current();
-                                    if level_size == 1 {
-                                        side_cursor.remove_current();
-                                    }
-                                    break;
-                                }
-                                level_cursor.move_next()
+                    if keeps_priority {
+                        ExchangeReply::OrderModified(order_id, new_price, new_size)
+                    } else {
+                        // Price change or size increase sends the order to the back of the queue
+                        match self.get_post_only_price(instrument, &replacement) {
+                            Some(new_price) => {
+                                self.books[instrument].trader_pending_limit_orders.remove(&order_id);
+                                self.remove_trader_limit_order_from_ob(instrument, order_id, price, direction);
+                                let mut replacement = replacement;
+                                replacement.set_price(new_price);
+                                self.insert_limit_order::<LimitOrder, { OrderOrigin::Trader }>(instrument, replacement);
+                                ExchangeReply::OrderModified(order_id, new_price, new_size)
+                            }
+                            None => {
+                                ExchangeReply::CannotModifyOrder(
+                                    order_id,
+                                    InabilityToModifyReason::WouldTakeLiquidity,
+                                )
                             }
-                            break;
                         }
-                        side_cursor.move_next()
                     }
-                    value.remove();
-                    ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested)
                 }
-                _ => {
-                    ExchangeReply::CannotCancelOrder(
+                None => {
+                    ExchangeReply::CannotModifyOrder(
                         order_id,
-                        InabilityToCancelReason::OrderAlreadyExecuted,
+                        InabilityToModifyReason::OrderAlreadyExecuted,
                     )
                 }
             }
-        };
-        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+        }
     }
 
     pub(crate) fn cancel_market_order(&mut self, order_id: OrderID) {
@@ -150,29 +401,177 @@ Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, DEBUG, TRD_UPDATES_OB, OB_SUB
                 order_id,
                 InabilityToCancelReason::ExchangeClosed,
             )
-        } else if !self.trader_submitted_orders.contains(&order_id) {
+        } else if !self.trader_submitted_orders.contains_key(&order_id) {
             ExchangeReply::CannotCancelOrder(
                 order_id,
                 InabilityToCancelReason::OrderHasNotBeenSubmitted,
             )
         } else {
-            let mut cursor = self.trader_pending_market_orders.cursor_front_mut();
-            (
-                || {
-                    while let Some(order) = cursor.current() {
-                        if order.get_order_id() == order_id {
-                            cursor.remove_current();
-                            return ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested);
-                        }
-                        cursor.move_next()
-                    }
-                    return ExchangeReply::CannotCancelOrder(
+            let instrument = self.trader_submitted_orders[&order_id];
+            let book = &mut self.books[instrument];
+            let is_cancelled = [
+                &mut book.trader_pending_market_orders,
+                &mut book.trader_auction_market_orders,
+                &mut book.trader_close_market_orders,
+            ]
+                .into_iter()
+                .any(|orders| remove_market_order(orders, |order| order.get_order_id() == order_id));
+            if is_cancelled {
+                ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested)
+            } else {
+                ExchangeReply::CannotCancelOrder(
+                    order_id,
+                    InabilityToCancelReason::OrderAlreadyExecuted,
+                )
+            }
+        };
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    pub(crate) fn cancel_stop_order(&mut self, order_id: OrderID) {
+        let reply = if !self.is_now_trading_time() {
+            ExchangeReply::CannotCancelOrder(
+                order_id,
+                InabilityToCancelReason::ExchangeClosed,
+            )
+        } else if !self.trader_submitted_orders.contains_key(&order_id) {
+            ExchangeReply::CannotCancelOrder(
+                order_id,
+                InabilityToCancelReason::OrderHasNotBeenSubmitted,
+            )
+        } else {
+            let instrument = self.trader_submitted_orders[&order_id];
+            match self.books[instrument].trader_pending_stop_orders.iter().position(|order| order.get_order_id() == order_id) {
+                Some(i) => {
+                    self.books[instrument].trader_pending_stop_orders.remove(i);
+                    ExchangeReply::OrderCancelled(order_id, CancellationReason::TraderRequested)
+                }
+                None => {
+                    ExchangeReply::CannotCancelOrder(
                         order_id,
                         InabilityToCancelReason::OrderAlreadyExecuted,
-                    );
+                    )
                 }
-            )()
+            }
         };
-        self.event_queue.schedule_reply_for_trader::<T>(reply, self.current_dt, &mut self.rng);
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    /// Places the order as a part of the group. Returns whether the order has been accepted
+    fn place_group_order(&mut self, order: GroupOrder) -> bool {
+        let order_id = order.get_order_id();
+        let is_new = !self.trader_submitted_orders.contains_key(&order_id);
+        let owner = self.get_order_owner(order_id).expect_with(
+            || format!("Group order {:?} does not belong to any trader", order_id)
+        );
+        let request = order.into();
+        if !self.check_instrument(&request) {
+            return false;
+        }
+        if let Some(reply) = self.check_risk_limits(owner, &request) {
+            self.event_queue.schedule_reply_for_trader(reply);
+            return false;
+        }
+        self.submit_order(request);
+        is_new && self.trader_submitted_orders.contains_key(&order_id)
+    }
+
+    fn discard_group_order(&mut self, order: GroupOrder, reason: DiscardingReason) {
+        let reply = ExchangeReply::OrderPlacementDiscarded(order.get_order_id(), reason);
+        self.event_queue.schedule_reply_for_trader(reply);
+    }
+
+    pub(crate) fn place_oco_orders(&mut self, first: GroupOrder, second: GroupOrder) {
+        let first_id = first.get_order_id();
+        let second_id = second.get_order_id();
+        // The second order is not placed if the first one has not been accepted or has been executed at once
+        if !self.place_group_order(first)
+            || self.trader_executions.iter().any(|execution| execution.order_id == first_id)
+        {
+            self.discard_group_order(second, DiscardingReason::OneCancelsOther);
+            return;
+        }
+        self.trader_oco_orders.insert(first_id, second_id);
+        self.trader_oco_orders.insert(second_id, first_id);
+        if !self.place_group_order(second) {
+            self.trader_oco_orders.remove(&first_id);
+            self.trader_oco_orders.remove(&second_id);
+            self.cancel_trader_order(first_id, CancellationReason::OneCancelsOther);
+        }
+    }
+
+    pub(crate) fn place_bracket_order(&mut self, parent: GroupOrder, take_profit: GroupOrder, stop_loss: GroupOrder) {
+        let parent_id = parent.get_order_id();
+        if self.place_group_order(parent) {
+            self.trader_bracket_orders.insert(parent_id, (take_profit, stop_loss));
+        } else {
+            self.discard_group_order(take_profit, DiscardingReason::ParentNotExecuted);
+            self.discard_group_order(stop_loss, DiscardingReason::ParentNotExecuted);
+        }
+    }
+
+    /// Cancels the orders of the trader on the given side, if any, and its limit orders in the given
+    /// price range, if any
+    pub(crate) fn cancel_trader_orders(&mut self,
+                                       trader: TraderID,
+                                       side: Option<Direction>,
+                                       price_range: Option<(Price, Price)>) {
+        let order_ids: Vec<_> = self.get_active_trader_order_ids(side, price_range).into_iter()
+            .filter(|order_id| self.get_order_owner(*order_id) == Some(trader))
+            .collect();
+        let cancelled = order_ids.into_iter()
+            .filter(|order_id| self.cancel_trader_order(*order_id, CancellationReason::TraderRequested))
+            .count();
+        self.event_queue.schedule_reply_to_trader(trader, ExchangeReply::MassCancelled(cancelled));
+    }
+
+    pub(crate) fn expire_order(&mut self, order_id: OrderID) {
+        let instrument = match self.trader_submitted_orders.get(&order_id) {
+            Some(&instrument) => { instrument }
+            None => { return; }
+        };
+        // The expiry is matched against the order's time in force so that the stale events
+        // do not cancel the orders that reuse the same ID in the subsequent sessions
+        let expiry = TimeInForce::GoodTillTime(self.current_dt);
+        let expired = match self.books[instrument].trader_pending_limit_orders.get(&order_id) {
+            Some(&(price, direction, time_in_force)) if time_in_force == expiry => {
+                self.books[instrument].trader_pending_limit_orders.remove(&order_id);
+                self.remove_trader_limit_order_from_ob(instrument, order_id, price, direction);
+                true
+            }
+            _ => {
+                let is_expired = |order: &dyn Order| {
+                    order.get_order_id() == order_id && order.get_time_in_force() == expiry
+                };
+                if let Some(i) = self.books[instrument].trader_suspended_limit_orders.iter().position(|order| is_expired(order)) {
+                    self.books[instrument].trader_suspended_limit_orders.remove(i);
+                    true
+                } else if let Some(i) = self.books[instrument].trader_pending_stop_orders.iter().position(|order| is_expired(order)) {
+                    self.books[instrument].trader_pending_stop_orders.remove(i);
+                    true
+                } else {
+                    remove_market_order(&mut self.books[instrument].trader_pending_market_orders, |order| is_expired(order))
+                        || remove_market_order(&mut self.books[instrument].trader_suspended_market_orders, |order| is_expired(order))
+                }
+            }
+        };
+        if expired {
+            let reply = ExchangeReply::OrderCancelled(order_id, CancellationReason::Expired);
+            self.event_queue.schedule_reply_for_trader(reply);
+        }
+    }
+}
+
+pub(crate) fn remove_market_order<P>(orders: &mut LinkedList<MarketOrder>, predicate: P) -> bool
+    where P: Fn(&MarketOrder) -> bool
+{
+    let mut cursor = orders.cursor_front_mut();
+    while let Some(order) = cursor.current() {
+        if predicate(order) {
+            cursor.remove_current();
+            return true;
+        }
+        cursor.move_next()
     }
-}
\ No newline at end of file
+    false
+}
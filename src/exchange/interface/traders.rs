@@ -0,0 +1,177 @@
+use crate::exchange::{Exchange, types::{Event, EventBody}};
+use crate::fees::interface::FeeSchedule;
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::{ExchangeReply, InabilityToCancelReason, InabilityToModifyReason, SubscriptionUpdate, TraderRequest};
+use crate::order::Order;
+use crate::queue_position::interface::QueuePositionModel;
+use crate::trader::Trader;
+use crate::types::{DateTime, Direction, Duration, InstrumentID, OrderID, TraderID};
+use crate::utils::ExpectWith;
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    pub(crate) fn get_trader_ids(&self) -> Vec<TraderID> {
+        (0..self.traders.len()).map(TraderID).collect()
+    }
+
+    pub(crate) fn get_order_owner(&self, order_id: OrderID) -> Option<TraderID> {
+        self.order_owners.get(&order_id).map(|(owner, _)| *owner)
+    }
+
+    /// Returns the exchange ID of the trader order, assigning a new one to the IDs met for the first time
+    fn get_exchange_order_id(&mut self, trader: TraderID, order_id: OrderID) -> OrderID {
+        let order_owners = &mut self.order_owners;
+        *self.traders[trader.0].order_ids.entry(order_id).or_insert_with(
+            || {
+                let exchange_id = OrderID(order_owners.len() as u64);
+                order_owners.insert(exchange_id, (trader, order_id));
+                exchange_id
+            }
+        )
+    }
+
+    /// Returns the exchange ID of the trader order if the trader has ever placed it
+    fn find_exchange_order_id(&self, trader: TraderID, order_id: OrderID) -> Option<OrderID> {
+        self.traders[trader.0].order_ids.get(&order_id).copied()
+    }
+
+    /// Records the order submitted to the current book for the risk checks of its owner
+    pub(crate) fn record_submitted_order(&mut self, instrument: InstrumentID, order_id: OrderID, direction: Direction) {
+        self.trader_submitted_orders.insert(order_id, instrument);
+        let owner = self.get_order_owner(order_id).expect_with(
+            || format!("Order {:?} does not belong to any trader", order_id)
+        );
+        self.traders[owner.0].risk_gate.record_order(order_id, instrument, direction)
+    }
+
+    /// Translates the order IDs of the request from the trader namespace to the exchange one. Returns None if
+    /// the request refers to an order the trader has never placed, which is rejected right away
+    pub(crate) fn translate_trader_request(&mut self, trader: TraderID, mut request: TraderRequest) -> Option<TraderRequest> {
+        match &mut request {
+            TraderRequest::PlaceLimitOrder(order) => {
+                order.set_order_id(self.get_exchange_order_id(trader, order.get_order_id()))
+            }
+            TraderRequest::PlaceMarketOrder(order) => {
+                order.set_order_id(self.get_exchange_order_id(trader, order.get_order_id()))
+            }
+            TraderRequest::PlaceStopOrder(order) => {
+                order.set_order_id(self.get_exchange_order_id(trader, order.get_order_id()))
+            }
+            TraderRequest::PlaceOcoOrders(first, second) => {
+                for order in [first, second] {
+                    order.set_order_id(self.get_exchange_order_id(trader, order.get_order_id()))
+                }
+            }
+            TraderRequest::PlaceBracketOrder(parent, take_profit, stop_loss) => {
+                for order in [parent, take_profit, stop_loss] {
+                    order.set_order_id(self.get_exchange_order_id(trader, order.get_order_id()))
+                }
+            }
+            TraderRequest::CancelLimitOrder(order_id)
+            | TraderRequest::CancelMarketOrder(order_id)
+            | TraderRequest::CancelStopOrder(order_id) => {
+                match self.find_exchange_order_id(trader, *order_id) {
+                    Some(exchange_id) => { *order_id = exchange_id }
+                    None => {
+                        let reply = ExchangeReply::CannotCancelOrder(*order_id, InabilityToCancelReason::OrderHasNotBeenSubmitted);
+                        self.event_queue.schedule_reply_to_trader(trader, reply);
+                        return None;
+                    }
+                }
+            }
+            TraderRequest::ModifyLimitOrder(order_id, ..) => {
+                match self.find_exchange_order_id(trader, *order_id) {
+                    Some(exchange_id) => { *order_id = exchange_id }
+                    None => {
+                        let reply = ExchangeReply::CannotModifyOrder(*order_id, InabilityToModifyReason::OrderHasNotBeenSubmitted);
+                        self.event_queue.schedule_reply_to_trader(trader, reply);
+                        return None;
+                    }
+                }
+            }
+            TraderRequest::CancelAll | TraderRequest::CancelAllOnSide(_) | TraderRequest::CancelInPriceRange(..) => {}
+        }
+        Some(request)
+    }
+
+    /// Delivers the replies scheduled while handling the event to the traders they are addressed to or to the owners
+    /// of the orders they refer to, with the order IDs in the owner namespaces. The other replies go to all the traders
+    pub(crate) fn deliver_trader_replies(&mut self) {
+        for (trader, mut reply) in std::mem::take(&mut self.event_queue.trader_replies) {
+            let recipients = if let Some(trader) = trader {
+                vec![trader]
+            } else if let Some(order_id) = reply.mut_order_id() {
+                let (owner, trader_order_id) = *self.order_owners.get(order_id).expect_with(
+                    || format!("Order {:?} does not belong to any trader", order_id)
+                );
+                *order_id = trader_order_id;
+                vec![owner]
+            } else {
+                self.get_trader_ids()
+            };
+            for trader in recipients {
+                let latency = self.traders[trader.0].trader.exchange_to_trader_latency(&mut self.rng, self.current_dt);
+                self.event_queue.push(
+                    Event {
+                        datetime: self.current_dt + Duration::nanoseconds(latency as i64),
+                        body: EventBody::ExchangeReply(trader, reply.clone(), self.current_dt),
+                    }
+                )
+            }
+        }
+    }
+
+    /// Schedules the update for the traders subscribed to it
+    pub(crate) fn schedule_subscription_update(&mut self, update: SubscriptionUpdate) {
+        for trader in self.get_trader_ids() {
+            self.schedule_subscription_update_to_trader(trader, &update)
+        }
+    }
+
+    /// Schedules the update for the trader if it is subscribed to it
+    pub(crate) fn schedule_subscription_update_to_trader(&mut self, trader: TraderID, update: &SubscriptionUpdate) {
+        let hosted = &self.traders[trader.0];
+        if !hosted.subscriptions.is_subscribed(update) {
+            return;
+        }
+        let latency = hosted.trader.exchange_to_trader_latency(&mut self.rng, self.current_dt);
+        self.event_queue.push(
+            Event {
+                datetime: self.current_dt + Duration::nanoseconds(latency as i64),
+                body: EventBody::SubscriptionUpdate(trader, update.clone(), self.current_dt),
+            }
+        )
+    }
+
+    /// Schedules the arrival of the requests the trader has sent at the given moment
+    pub(crate) fn schedule_trader_requests(&mut self, trader: TraderID, requests: Vec<TraderRequest>, dt: DateTime) {
+        let hosted = &self.traders[trader.0];
+        let rng = &mut self.rng;
+        self.event_queue.extend(
+            requests.into_iter()
+                .map(
+                    |request| Event {
+                        datetime: dt + Duration::nanoseconds(hosted.trader.trader_to_exchange_latency(rng, dt) as i64),
+                        body: EventBody::TraderRequest(trader, request),
+                    }
+                )
+        )
+    }
+}
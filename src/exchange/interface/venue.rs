@@ -0,0 +1,43 @@
+use crate::exchange::Exchange;
+use crate::fees::interface::FeeSchedule;
+use crate::history::parser::EventProcessor;
+use crate::lags::interface::NanoSecondGenerator;
+use crate::matching::interface::MatchingPolicy;
+use crate::message::TraderRequest;
+use crate::queue_position::interface::QueuePositionModel;
+use crate::trader::Trader;
+use crate::types::{DateTime, TraderID};
+use crate::venue::Venue;
+
+impl<
+    T: Trader + ?Sized,
+    E: EventProcessor,
+    ObLagGen: NanoSecondGenerator,
+    TrdLagGen: NanoSecondGenerator,
+    WkpLagGen: NanoSecondGenerator,
+    M: MatchingPolicy,
+    Q: QueuePositionModel,
+    F: FeeSchedule,
+    const DEBUG: bool,
+    const TRD_UPDATES_OB: bool,
+    const OB_SUBSCRIPTION: bool,
+    const TRD_SUBSCRIPTION: bool,
+    const WAKEUP_SUBSCRIPTION: bool
+>
+Venue for Exchange<'_, T, E, ObLagGen, TrdLagGen, WkpLagGen, M, Q, F, DEBUG, TRD_UPDATES_OB, OB_SUBSCRIPTION, TRD_SUBSCRIPTION, WAKEUP_SUBSCRIPTION>
+{
+    fn start_trading(&mut self) { self.schedule_first_open_tryout() }
+
+    fn get_next_event_dt(&self) -> Option<DateTime> { self.event_queue.peek().map(|event| event.datetime) }
+
+    fn advance(&mut self) -> bool {
+        match self.event_queue.pop() {
+            Some(event) => { self.process_next_event(event).is_ok() }
+            None => { false }
+        }
+    }
+
+    fn send_trader_requests(&mut self, trader: TraderID, requests: Vec<TraderRequest>, dt: DateTime) {
+        self.schedule_trader_requests(trader, requests, dt)
+    }
+}
@@ -1,8 +1,9 @@
-use crate::types::{DateTime, Direction, Price, Size};
+use crate::types::{DateTime, Direction, InstrumentID, Price, Size};
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub struct OrderBookDiff {
     pub datetime: DateTime,
+    pub instrument: InstrumentID,
     pub price: Price,
     pub size: Size,
     pub direction: Direction,
@@ -1,32 +1,739 @@
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, LinkedList};
+use std::cmp::{max, min, Reverse};
+use std::collections::{btree_map, BinaryHeap, BTreeMap, HashMap, LinkedList};
+use std::ops::{Bound, Index, IndexMut};
 
+use chrono::Datelike;
 use rand::rngs::StdRng;
 
+use crate::auction::AuctionPhase;
+use crate::exchange::trades::history::TradesHistory;
+use crate::fees::interface::{Fee, FeeSchedule, Liquidity};
+use crate::halt::{PriceMonitor, TradingHalt};
 use crate::history::types::{HistoryEvent, HistoryEventBody, OrderOrigin};
+use crate::impact::MarketImpact;
+use crate::instrument::InstrumentSpec;
+use crate::matching::interface::MatchingPolicy;
 use crate::message::{ExchangeReply, SubscriptionSchedule, SubscriptionUpdate, TraderRequest};
-use crate::trader::Trader;
-use crate::types::{DateTime, Duration, OrderID, Price, Size};
+use crate::order::{LimitOrder, MarketOrder, Order, PegReference, PostOnlyMode, PricedOrder, StopOrder, TimeInForce};
+use crate::queue_position::interface::QueuePositionModel;
+use crate::risk::RiskGate;
+use crate::throttle::{MessageStatistics, MessageThrottle};
+use crate::trader::{subscriptions::{InstrumentSubscriptions, TraderSubscriptions}, Trader};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size, TraderID};
+use crate::utils::ExpectWith;
 
+/// Price levels of one side of the order book looked up by price in logarithmic time.
+/// The keys of the bid levels are negated prices so that both sides are iterated from the best level
+pub(crate) struct OrderBookSide {
+    direction: Direction,
+    levels: BTreeMap<i64, OrderBookLevel>,
+}
+
+impl OrderBookSide {
+    pub(crate) fn new(direction: Direction) -> Self {
+        OrderBookSide { direction, levels: Default::default() }
+    }
+
+    fn get_key(&self, price: Price) -> i64 {
+        match self.direction {
+            Direction::Buy => { -price.0 }
+            Direction::Sell => { price.0 }
+        }
+    }
+
+    pub(crate) fn iter(&self) -> btree_map::Values<'_, i64, OrderBookLevel> { self.levels.values() }
+
+    pub(crate) fn get_best_price(&self) -> Option<Price> { self.levels.values().next().map(|level| level.price) }
+
+    pub(crate) fn get_level_mut(&mut self, price: Price) -> Option<&mut OrderBookLevel> {
+        let key = self.get_key(price);
+        self.levels.get_mut(&key)
+    }
+
+    /// Takes out the best level worse than the given price or the best level at all if there is no price,
+    /// so that the level is matched while the exchange stays borrowable
+    pub(crate) fn take_next_level(&mut self, prev_price: Option<Price>) -> Option<OrderBookLevel> {
+        let lower_bound = match prev_price {
+            Some(price) => { Bound::Excluded(self.get_key(price)) }
+            None => { Bound::Unbounded }
+        };
+        let key = *self.levels.range((lower_bound, Bound::Unbounded)).next()?.0;
+        self.levels.remove(&key)
+    }
+
+    /// Puts back the taken level unless it has been emptied
+    pub(crate) fn restore_level(&mut self, level: OrderBookLevel) {
+        if !level.is_empty() {
+            self.levels.insert(self.get_key(level.price), level);
+        }
+    }
+
+    /// Puts the entry to the back of the level queue creating the level if needed
+    pub(crate) fn push_back(&mut self, price: Price, entry: OrderBookEntry) {
+        let key = self.get_key(price);
+        self.levels.entry(key).or_insert_with(|| OrderBookLevel::new(price)).push_back(entry);
+    }
+
+    pub(crate) fn remove_level_if_empty(&mut self, price: Price) {
+        let key = self.get_key(price);
+        if self.levels.get(&key).map_or(false, |level| level.is_empty()) {
+            self.levels.remove(&key);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) { self.levels.clear() }
+}
+
+/// Slot linking to no entry
+const NO_SLOT: usize = usize::MAX;
+
+struct LevelNode {
+    entry: OrderBookEntry,
+    prev: usize,
+    next: usize,
+    /// Neighbouring parts of the same history order split around the trader orders
+    prev_part: usize,
+    next_part: usize,
+}
+
+/// Queue of the orders at one price. The entries are kept in slab slots linked in the queue order,
+/// and the last part of each order is looked up by its ID, so the orders are found and removed in constant time
 pub(crate) struct OrderBookLevel {
     pub(crate) price: Price,
-    pub(crate) queue: LinkedList<OrderBookEntry>,
+    nodes: Vec<Option<LevelNode>>,
+    free_slots: Vec<usize>,
+    head: usize,
+    tail: usize,
+    len: usize,
+    trader_entries: usize,
+    last_parts: HashMap<(OrderOrigin, OrderID), usize>,
+}
+
+pub(crate) struct LevelEntries<'a> {
+    level: &'a OrderBookLevel,
+    slot: usize,
+}
+
+impl<'a> Iterator for LevelEntries<'a> {
+    type Item = (usize, &'a OrderBookEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.slot == NO_SLOT {
+            return None;
+        }
+        let slot = self.slot;
+        let node = self.level.node(slot);
+        self.slot = node.next;
+        Some((slot, &node.entry))
+    }
 }
 
 impl OrderBookLevel {
+    pub(crate) fn new(price: Price) -> Self {
+        OrderBookLevel {
+            price,
+            nodes: Vec::new(),
+            free_slots: Vec::new(),
+            head: NO_SLOT,
+            tail: NO_SLOT,
+            len: 0,
+            trader_entries: 0,
+            last_parts: Default::default(),
+        }
+    }
+
+    fn node(&self, slot: usize) -> &LevelNode { self.nodes[slot].as_ref().unwrap() }
+
+    fn node_mut(&mut self, slot: usize) -> &mut LevelNode { self.nodes[slot].as_mut().unwrap() }
+
+    pub(crate) fn len(&self) -> usize { self.len }
+
+    pub(crate) fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Slots and entries in the queue order
+    pub(crate) fn entries(&self) -> LevelEntries<'_> { LevelEntries { level: self, slot: self.head } }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item=&OrderBookEntry> { self.entries().map(|(_, entry)| entry) }
+
+    pub(crate) fn front(&self) -> Option<usize> { Some(self.head).filter(|slot| *slot != NO_SLOT) }
+
+    pub(crate) fn next(&self, slot: usize) -> Option<usize> { Some(self.node(slot).next).filter(|slot| *slot != NO_SLOT) }
+
+    pub(crate) fn get(&self, slot: usize) -> &OrderBookEntry { &self.node(slot).entry }
+
+    pub(crate) fn get_mut(&mut self, slot: usize) -> &mut OrderBookEntry { &mut self.node_mut(slot).entry }
+
+    /// Slot of the last part of the order
+    pub(crate) fn find(&self, from: OrderOrigin, order_id: OrderID) -> Option<usize> {
+        self.last_parts.get(&(from, order_id)).copied()
+    }
+
+    /// Puts the entry before the one in the given slot, or to the back, as the last part of its order
+    fn insert_before(&mut self, next: usize, entry: OrderBookEntry) -> usize {
+        let prev_part = self.find(entry.from, entry.order_id).unwrap_or(NO_SLOT);
+        self.link(next, entry, prev_part, NO_SLOT)
+    }
+
+    fn link(&mut self, next: usize, entry: OrderBookEntry, prev_part: usize, next_part: usize) -> usize {
+        let prev = if next == NO_SLOT { self.tail } else { self.node(next).prev };
+        let key = (entry.from, entry.order_id);
+        if entry.from == OrderOrigin::Trader {
+            self.trader_entries += 1
+        }
+        let node = LevelNode { entry, prev, next, prev_part, next_part };
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.nodes[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        match prev {
+            NO_SLOT => { self.head = slot }
+            prev => { self.node_mut(prev).next = slot }
+        }
+        match next {
+            NO_SLOT => { self.tail = slot }
+            next => { self.node_mut(next).prev = slot }
+        }
+        if prev_part != NO_SLOT {
+            self.node_mut(prev_part).next_part = slot
+        }
+        match next_part {
+            NO_SLOT => { self.last_parts.insert(key, slot); }
+            next_part => { self.node_mut(next_part).prev_part = slot }
+        }
+        self.len += 1;
+        slot
+    }
+
+    pub(crate) fn push_back(&mut self, entry: OrderBookEntry) -> usize { self.insert_before(NO_SLOT, entry) }
+
+    pub(crate) fn remove(&mut self, slot: usize) -> OrderBookEntry {
+        let node = self.nodes[slot].take().unwrap();
+        self.free_slots.push(slot);
+        match node.prev {
+            NO_SLOT => { self.head = node.next }
+            prev => { self.node_mut(prev).next = node.next }
+        }
+        match node.next {
+            NO_SLOT => { self.tail = node.prev }
+            next => { self.node_mut(next).prev = node.prev }
+        }
+        if node.prev_part != NO_SLOT {
+            self.node_mut(node.prev_part).next_part = node.next_part
+        }
+        let key = (node.entry.from, node.entry.order_id);
+        match (node.next_part, node.prev_part) {
+            (NO_SLOT, NO_SLOT) => { self.last_parts.remove(&key); }
+            (NO_SLOT, prev_part) => { self.last_parts.insert(key, prev_part); }
+            (next_part, _) => { self.node_mut(next_part).prev_part = node.prev_part }
+        }
+        if node.entry.from == OrderOrigin::Trader {
+            self.trader_entries -= 1
+        }
+        self.len -= 1;
+        node.entry
+    }
+
+    /// Puts the entry to the back of the queue. Returns its new slot
+    pub(crate) fn move_to_back(&mut self, slot: usize) -> usize {
+        let entry = self.remove(slot);
+        self.push_back(entry)
+    }
+
+    /// Splits the given size off the entry into the part right behind it. Returns the slot of the new part
+    fn split_back(&mut self, slot: usize, back_size: Size) -> usize {
+        let node = self.node_mut(slot);
+        node.entry.size -= back_size;
+        let entry = OrderBookEntry { size: back_size, ..node.entry.clone() };
+        let (next, next_part) = (node.next, node.next_part);
+        self.link(next, entry, slot, next_part)
+    }
+
     pub(crate) fn get_ob_level_size(&self) -> Size {
-        self.queue.iter().map(|order| order.size).sum()
+        self.iter().map(|order| order.size).sum()
+    }
+
+    /// Sets the new size of the history order removing it if the size is zero. Returns false if there is no such order.
+    /// The history order can be split into several entries around the trader orders. The decrease up to the `filled`
+    /// volume executed by the history trades is applied to its front entries first, as the fills come from the front
+    /// of the queue, and the rest is cancelled from its back entries first. The queue position model decides which part
+    /// of the cancelled volume has been ahead of each trader order, and the trader orders are moved so that the history
+    /// volume behind them matches the estimate
+    pub(crate) fn resize_history_order<Q: QueuePositionModel>(&mut self,
+                                                              order_id: OrderID,
+                                                              new_size: Size,
+                                                              filled: Size,
+                                                              model: &mut Q,
+                                                              rng: &mut StdRng) -> bool {
+        let last_part = match self.find(OrderOrigin::History, order_id) {
+            Some(slot) => { slot }
+            None => { return false; }
+        };
+        let mut parts = Vec::new();
+        let mut part = last_part;
+        while part != NO_SLOT {
+            parts.push(part);
+            part = self.node(part).prev_part;
+        }
+        let old_size: Size = parts.iter().map(|part| self.node(*part).entry.size).sum();
+        if new_size >= old_size {
+            self.node_mut(last_part).entry.size += new_size - old_size;
+            return true;
+        }
+        let filled = min(filled, old_size - new_size);
+        let cancelled = old_size - new_size - filled;
+        // Filled and cancelled volumes of the parts, which are listed from the back one
+        let mut decrements: Vec<_> = parts.iter().map(|part| (*part, Size(0), Size(0))).collect();
+        let mut rest = filled;
+        for (part, fill, _) in decrements.iter_mut().rev() {
+            *fill = min(self.node(*part).entry.size, rest);
+            rest -= *fill;
+        }
+        let mut rest = cancelled;
+        for (part, fill, cancel) in decrements.iter_mut() {
+            *cancel = min(self.node(*part).entry.size - *fill, rest);
+            rest -= *cancel;
+        }
+
+        // The trader orders are moved after the decrease to have the estimated history volume behind them
+        let mut moves = Vec::new();
+        if self.trader_entries != 0 {
+            let mut volume_ahead = Size(0);
+            let mut queue_filled_ahead = Size(0);
+            let mut queue_cancelled_ahead = Size(0);
+            let mut trader_orders = Vec::with_capacity(self.trader_entries);
+            for (slot, entry) in self.entries() {
+                match entry.from {
+                    OrderOrigin::History => {
+                        volume_ahead += entry.size;
+                        if entry.order_id == order_id {
+                            if let Some((_, fill, cancel)) = decrements.iter().find(|(part, ..)| *part == slot) {
+                                queue_filled_ahead += *fill;
+                                queue_cancelled_ahead += *cancel;
+                            }
+                        }
+                    }
+                    OrderOrigin::Trader => {
+                        trader_orders.push((slot, volume_ahead, queue_filled_ahead + queue_cancelled_ahead, queue_cancelled_ahead))
+                    }
+                }
+            }
+            let history_size = volume_ahead;
+            for (slot, volume_ahead, queue_decreased_ahead, queue_cancelled_ahead) in trader_orders {
+                // Fills are ahead of the trader order, and the model splits the cancel of the rest of the volume
+                let filled_ahead = min(filled, volume_ahead);
+                let volume_behind = history_size - volume_ahead - (filled - filled_ahead);
+                let volume_ahead = volume_ahead - filled_ahead;
+                let cancelled_ahead = model.get_cancelled_ahead(rng, volume_ahead, volume_behind, cancelled, queue_cancelled_ahead);
+                let cancelled_ahead = max(
+                    cancelled - min(cancelled, volume_behind),
+                    min(cancelled_ahead, min(cancelled, volume_ahead)),
+                );
+                if filled_ahead + cancelled_ahead != queue_decreased_ahead {
+                    moves.push((slot, volume_behind - (cancelled - cancelled_ahead)))
+                }
+            }
+        }
+        for (part, fill, cancel) in decrements {
+            if fill + cancel == Size(0) {
+                continue;
+            }
+            let entry = &mut self.node_mut(part).entry;
+            entry.size -= fill + cancel;
+            if entry.size == Size(0) {
+                self.remove(part);
+            }
+        }
+        for (slot, volume_behind) in moves {
+            let entry = self.remove(slot);
+            self.insert_by_volume_behind(entry, volume_behind)
+        }
+        true
+    }
+
+    // Inserts the trader order so that the history volume behind it is equal to the given one,
+    // splitting the history order on the boundary if needed
+    fn insert_by_volume_behind(&mut self, entry: OrderBookEntry, volume_behind: Size) {
+        let mut accumulated = Size(0);
+        let mut insert_before = NO_SLOT;
+        let mut slot = self.tail;
+        while slot != NO_SLOT {
+            let node = self.node(slot);
+            let prev = node.prev;
+            if node.entry.from == OrderOrigin::History {
+                if accumulated + node.entry.size > volume_behind {
+                    let back_size = volume_behind - accumulated;
+                    if back_size != Size(0) {
+                        insert_before = self.split_back(slot, back_size);
+                    }
+                    break;
+                }
+                accumulated += node.entry.size;
+                insert_before = slot;
+            }
+            slot = prev;
+        }
+        self.insert_before(insert_before, entry);
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct OrderBookEntry {
     pub(crate) order_id: OrderID,
     pub(crate) size: Size,
     pub(crate) from: OrderOrigin,
+    /// Trader the order belongs to. History orders belong to none
+    pub(crate) owner: Option<TraderID>,
+    pub(crate) peak_size: Option<Size>,
+    pub(crate) hidden_size: Size,
+    pub(crate) post_only: Option<PostOnlyMode>,
+    pub(crate) peg: Option<(PegReference, i64)>,
+}
+
+impl OrderBookEntry {
+    pub(crate) fn to_limit_order(&self,
+                                 size: Size,
+                                 direction: Direction,
+                                 price: Price,
+                                 time_in_force: TimeInForce) -> LimitOrder {
+        let order = LimitOrder::new(self.order_id, size, direction, price).with_time_in_force(time_in_force);
+        let order = match self.peak_size {
+            Some(peak_size) => { order.with_peak_size(peak_size) }
+            None => { order }
+        };
+        let order = match self.post_only {
+            Some(mode) => { order.with_post_only(mode) }
+            None => { order }
+        };
+        match self.peg {
+            Some((reference, offset)) => { order.with_peg(reference, offset) }
+            None => { order }
+        }
+    }
+
+    pub(crate) fn refill(&mut self) {
+        let refill_size = min(self.peak_size.unwrap_or(self.hidden_size), self.hidden_size);
+        self.size = refill_size;
+        self.hidden_size -= refill_size;
+    }
+}
+
+/// History order the trader orders have been executed against
+pub(crate) struct ConsumedOrder {
+    pub(crate) price: Price,
+    pub(crate) direction: Direction,
+    /// Size of the order according to the history
+    pub(crate) history_size: Size,
+    /// Consumed volume that had not recovered at the moment of the last consumption or refill
+    pub(crate) consumed: Size,
+    pub(crate) consumed_dt: DateTime,
+}
+
+impl ConsumedOrder {
+    pub(crate) fn new(price: Price, direction: Direction, history_size: Size, dt: DateTime) -> Self {
+        ConsumedOrder { price, direction, history_size, consumed: Size(0), consumed_dt: dt }
+    }
+
+    pub(crate) fn consume(&mut self, impact: MarketImpact, size: Size, dt: DateTime) {
+        self.consumed = impact.get_unrecovered(self.consumed, dt - self.consumed_dt) + size;
+        self.consumed_dt = dt;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct TradedPriceRange {
+    range: Option<(Price, Price)>,
+    /// Price of the last execution. It is kept across the sessions and is the reference price of the auctions
+    last_price: Option<Price>,
+}
+
+impl TradedPriceRange {
+    pub(crate) fn update(&mut self, price: Price) {
+        self.range = match self.range {
+            Some((min_price, max_price)) => { Some((min(min_price, price), max(max_price, price))) }
+            None => { Some((price, price)) }
+        };
+        self.last_price = Some(price);
+    }
+
+    pub(crate) fn take(&mut self) -> Option<(Price, Price)> { self.range.take() }
+
+    pub(crate) fn get_range(&self) -> Option<(Price, Price)> { self.range }
+
+    pub(crate) fn get_last_price(&self) -> Option<Price> { self.last_price }
+}
+
+/// Trader hosted by the exchange with the updates it receives and its own order ID namespace
+pub(crate) struct HostedTrader<'a, T: Trader + ?Sized> {
+    pub(crate) trader: &'a mut T,
+    pub(crate) subscriptions: TraderSubscriptions,
+    /// Exchange IDs of the trader orders by their IDs in the trader namespace
+    pub(crate) order_ids: HashMap<OrderID, OrderID>,
+    pub(crate) throttle: MessageThrottle,
+    pub(crate) message_statistics: MessageStatistics,
+    pub(crate) fee_account: FeeAccount,
+    pub(crate) risk_gate: RiskGate,
+}
+
+impl<'a, T: Trader + ?Sized> HostedTrader<'a, T> {
+    pub(crate) fn new(trader: &'a mut T) -> Self {
+        HostedTrader {
+            trader,
+            subscriptions: Default::default(),
+            order_ids: Default::default(),
+            throttle: Default::default(),
+            message_statistics: Default::default(),
+            fee_account: Default::default(),
+            risk_gate: Default::default(),
+        }
+    }
+}
+
+/// Order book of the instrument with the trader orders and the trading state kept for it
+pub(crate) struct InstrumentBook {
+    pub(crate) id: InstrumentID,
+    pub(crate) spec: InstrumentSpec,
+    pub(crate) subscriptions: InstrumentSubscriptions,
+    pub(crate) history_order_locations: HashMap<OrderID, (Direction, Price)>,
+    pub(crate) consumed_history_orders: HashMap<OrderID, ConsumedOrder>,
+    /// Volume of the history orders executed by the history trades that their order book diffs have not reflected yet
+    pub(crate) history_fills: HashMap<OrderID, Size>,
+
+    pub(crate) bids: OrderBookSide,
+    pub(crate) asks: OrderBookSide,
+
+    pub(crate) trader_pending_market_orders: LinkedList<MarketOrder>,
+    pub(crate) trader_pending_limit_orders: HashMap<OrderID, (Price, Direction, TimeInForce)>,
+    pub(crate) trader_pending_stop_orders: Vec<StopOrder>,
+    pub(crate) trader_pegged_orders: HashMap<OrderID, (PegReference, i64)>,
+    /// Best bid and ask the pegged orders were last repriced against
+    pub(crate) peg_reference_prices: (Option<Price>, Option<Price>),
+    pub(crate) trader_suspended_market_orders: LinkedList<MarketOrder>,
+    pub(crate) trader_suspended_limit_orders: Vec<LimitOrder>,
+    pub(crate) trader_auction_market_orders: LinkedList<MarketOrder>,
+    pub(crate) trader_close_market_orders: LinkedList<MarketOrder>,
+    pub(crate) trader_close_limit_orders: Vec<LimitOrder>,
+    pub(crate) auction_phase: Option<AuctionPhase>,
+    pub(crate) halt: Option<TradingHalt>,
+    pub(crate) price_monitor: PriceMonitor,
+
+    pub(crate) executed_trades: TradesHistory,
+    pub(crate) traded_price_range: TradedPriceRange,
+}
+
+impl InstrumentBook {
+    pub(crate) fn new(id: InstrumentID, spec: InstrumentSpec) -> Self {
+        InstrumentBook {
+            id,
+            spec,
+            subscriptions: Default::default(),
+            history_order_locations: Default::default(),
+            consumed_history_orders: Default::default(),
+            history_fills: Default::default(),
+            bids: OrderBookSide::new(Direction::Buy),
+            asks: OrderBookSide::new(Direction::Sell),
+            trader_pending_market_orders: Default::default(),
+            trader_pending_limit_orders: Default::default(),
+            trader_pending_stop_orders: Default::default(),
+            trader_pegged_orders: Default::default(),
+            peg_reference_prices: Default::default(),
+            trader_suspended_market_orders: Default::default(),
+            trader_suspended_limit_orders: Default::default(),
+            trader_auction_market_orders: Default::default(),
+            trader_close_market_orders: Default::default(),
+            trader_close_limit_orders: Default::default(),
+            auction_phase: None,
+            halt: None,
+            price_monitor: Default::default(),
+            executed_trades: Default::default(),
+            traded_price_range: Default::default(),
+        }
+    }
+
+    pub(crate) fn has_active_trader_order(&self, order_id: OrderID) -> bool {
+        self.trader_pending_limit_orders.contains_key(&order_id)
+            || self.trader_pending_stop_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_pending_market_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_suspended_limit_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_suspended_market_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_auction_market_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_close_market_orders.iter().any(|order| order.get_order_id() == order_id)
+            || self.trader_close_limit_orders.iter().any(|order| order.get_order_id() == order_id)
+    }
+
+    /// Returns the number of the open trader orders the predicate holds for
+    pub(crate) fn get_open_trader_order_count(&self, is_counted: impl Fn(OrderID) -> bool) -> usize {
+        self.trader_pending_limit_orders.keys()
+            .copied()
+            .chain(self.trader_close_limit_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_suspended_limit_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_pending_market_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_auction_market_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_close_market_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_suspended_market_orders.iter().map(|order| order.get_order_id()))
+            .chain(self.trader_pending_stop_orders.iter().map(|order| order.get_order_id()))
+            .filter(|order_id| is_counted(*order_id))
+            .count()
+    }
+
+    /// Returns the IDs of the trader orders on the given side, if any, and of the limit orders in the given price
+    /// range, if any
+    pub(crate) fn get_active_trader_order_ids(&self,
+                                              side: Option<Direction>,
+                                              price_range: Option<(Price, Price)>) -> Vec<OrderID> {
+        let on_side = |direction: Direction| side.map_or(true, |side| side == direction);
+        let mut order_ids: Vec<_> = self.trader_pending_limit_orders.iter()
+            .filter(
+                |(_, (price, direction, _))| on_side(*direction)
+                    && price_range.map_or(true, |(min_price, max_price)| min_price <= *price && *price <= max_price)
+            )
+            .map(|(order_id, _)| *order_id)
+            .collect();
+        order_ids.sort();
+        order_ids.extend(
+            self.trader_close_limit_orders.iter()
+                .chain(self.trader_suspended_limit_orders.iter())
+                .filter(
+                    |order| on_side(order.get_order_direction())
+                        && price_range.map_or(true, |(min_price, max_price)| min_price <= order.get_price() && order.get_price() <= max_price)
+                )
+                .map(|order| order.get_order_id())
+        );
+        if price_range.is_none() {
+            order_ids.extend(
+                self.trader_pending_market_orders.iter()
+                    .chain(self.trader_auction_market_orders.iter())
+                    .chain(self.trader_close_market_orders.iter())
+                    .chain(self.trader_suspended_market_orders.iter())
+                    .filter(|order| on_side(order.get_order_direction()))
+                    .map(|order| order.get_order_id())
+                    .chain(
+                        self.trader_pending_stop_orders.iter()
+                            .filter(|order| on_side(order.get_order_direction()))
+                            .map(|order| order.get_order_id())
+                    )
+            )
+        }
+        order_ids
+    }
+}
+
+/// Order books of the instruments added to the exchange in the order of their IDs
+pub(crate) struct InstrumentBooks(Vec<InstrumentBook>);
+
+impl InstrumentBooks {
+    pub(crate) fn new(book: InstrumentBook) -> Self { InstrumentBooks(vec![book]) }
+
+    /// Adds the book, replacing the one of the same instrument, if any
+    pub(crate) fn insert(&mut self, book: InstrumentBook) {
+        match self.0.binary_search_by_key(&book.id, |added| added.id) {
+            Ok(i) => { self.0[i] = book }
+            Err(i) => { self.0.insert(i, book) }
+        }
+    }
+
+    pub(crate) fn get(&self, instrument: InstrumentID) -> Option<&InstrumentBook> {
+        let i = self.0.binary_search_by_key(&instrument, |book| book.id).ok()?;
+        Some(&self.0[i])
+    }
+
+    pub(crate) fn get_mut(&mut self, instrument: InstrumentID) -> Option<&mut InstrumentBook> {
+        let i = self.0.binary_search_by_key(&instrument, |book| book.id).ok()?;
+        Some(&mut self.0[i])
+    }
+
+    pub(crate) fn contains(&self, instrument: InstrumentID) -> bool { self.get(instrument).is_some() }
+
+    pub(crate) fn count(&self) -> usize { self.0.len() }
+
+    /// Instrument of the book at the given position. Lets the exchange go over the instruments while handling them
+    pub(crate) fn get_id(&self, i: usize) -> InstrumentID { self.0[i].id }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item=&InstrumentBook> { self.0.iter() }
+}
+
+impl Index<InstrumentID> for InstrumentBooks {
+    type Output = InstrumentBook;
+
+    fn index(&self, instrument: InstrumentID) -> &InstrumentBook {
+        self.get(instrument).expect_with(|| format!("Instrument {:?} has not been added to the exchange", instrument))
+    }
 }
 
+impl IndexMut<InstrumentID> for InstrumentBooks {
+    fn index_mut(&mut self, instrument: InstrumentID) -> &mut InstrumentBook {
+        self.get_mut(instrument).expect_with(|| format!("Instrument {:?} has not been added to the exchange", instrument))
+    }
+}
+
+/// Pluggable policies of the exchange, replaced together when any of them changes its type
+pub(crate) struct ExchangePolicies<M: MatchingPolicy, Q: QueuePositionModel, F: FeeSchedule> {
+    pub(crate) matching: M,
+    pub(crate) queue_position: Q,
+    pub(crate) fees: F,
+}
+
+/// Trader volume executed in the current calendar month and the fees of the partially executed orders
+/// before the minimum order fee
 #[derive(Default)]
-pub(crate) struct EventQueue(pub(crate) BinaryHeap<Reverse<Event>>);
+pub(crate) struct FeeAccount {
+    month: Option<(i32, u32)>,
+    monthly_volume: Size,
+    order_fees: HashMap<OrderID, Fee>,
+}
+
+impl FeeAccount {
+    /// Sets the fee of the execution to the increase of the order fee
+    pub(crate) fn charge<F: FeeSchedule>(&mut self,
+                                         schedule: &mut F,
+                                         instrument: &InstrumentSpec,
+                                         datetime: DateTime,
+                                         liquidity: Liquidity,
+                                         execution: &mut TraderExecution) {
+        let month = Some((datetime.year(), datetime.month()));
+        if self.month != month {
+            self.month = month;
+            self.monthly_volume = Size(0);
+        }
+        let TraderExecution { order_id, size, price, .. } = *execution;
+        let fee_before = self.order_fees.remove(&order_id).unwrap_or_default();
+        let fee_after = fee_before + schedule.get_fee(instrument, liquidity, price, size, self.monthly_volume);
+        execution.fee = schedule.get_order_fee(fee_after, self.monthly_volume)
+            - schedule.get_order_fee(fee_before, self.monthly_volume);
+        if !execution.is_executed {
+            self.order_fees.insert(order_id, fee_after);
+        }
+        self.monthly_volume += size;
+    }
+
+    pub(crate) fn forget_order(&mut self, order_id: OrderID) { self.order_fees.remove(&order_id); }
+
+    pub(crate) fn retain_orders(&mut self, mut is_retained: impl FnMut(OrderID) -> bool) {
+        self.order_fees.retain(|order_id, _| is_retained(*order_id))
+    }
+}
+
+pub(crate) struct TraderExecution {
+    pub(crate) order_id: OrderID,
+    pub(crate) instrument: InstrumentID,
+    pub(crate) size: Size,
+    pub(crate) price: Price,
+    pub(crate) fee: Fee,
+    /// Whether the order has been executed in full
+    pub(crate) is_executed: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct EventQueue {
+    pub(crate) events: BinaryHeap<Reverse<Event>>,
+    /// Replies scheduled while handling the event along with the traders they are addressed to, if any
+    pub(crate) trader_replies: Vec<(Option<TraderID>, ExchangeReply)>,
+}
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) struct Event {
@@ -37,12 +744,18 @@ pub(crate) struct Event {
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) enum EventBody {
     ExchangeOpenTryout,
-    HistoryEvent(HistoryEventBody),
-    TraderRequest(TraderRequest),
-    ExchangeReply(ExchangeReply, DateTime),
-    SubscriptionUpdate(SubscriptionUpdate, DateTime),
+    HistoryEvent(InstrumentID, HistoryEventBody),
+    TraderRequest(TraderID, TraderRequest),
+    ExchangeReply(TraderID, ExchangeReply, DateTime),
+    SubscriptionUpdate(TraderID, SubscriptionUpdate, DateTime),
     SubscriptionSchedule(SubscriptionSchedule),
-    TraderWakeUp,
+    TraderWakeUp(TraderID),
+    TraderOrderExpiry(OrderID),
+    HistoryOrderRefill(InstrumentID, OrderID),
+    DelayedRequestsRelease(TraderID),
+    ClosingAuctionStart,
+    AuctionUncross,
+    TradingResumption(InstrumentID),
     ExchangeClosed,
 }
 
@@ -50,46 +763,44 @@ impl Extend<Event> for EventQueue {
     fn extend<I>(&mut self, iter: I)
         where I: IntoIterator<Item=Event>
     {
-        self.0.extend(iter.into_iter().map(Reverse))
+        self.events.extend(iter.into_iter().map(Reverse))
     }
 }
 
 impl EventQueue {
     pub(crate) fn peek(&self) -> Option<&Event> {
-        match self.0.peek() {
+        match self.events.peek() {
             Some(Reverse(event)) => { Some(event) }
             _ => { None }
         }
     }
 
     pub(crate) fn pop(&mut self) -> Option<Event> {
-        match self.0.pop() {
+        match self.events.pop() {
             Some(Reverse(event)) => { Some(event) }
             _ => { None }
         }
     }
 
     pub(crate) fn push(&mut self, item: Event) {
-        self.0.push(Reverse(item))
+        self.events.push(Reverse(item))
     }
 
-    pub(crate) fn schedule_reply_for_trader<T: Trader>(&mut self,
-                                                       reply: ExchangeReply,
-                                                       exchange_dt: DateTime,
-                                                       rng: &mut StdRng) {
-        self.push(
-            Event {
-                datetime: exchange_dt + Duration::nanoseconds(T::exchange_to_trader_latency(rng, exchange_dt) as i64),
-                body: EventBody::ExchangeReply(reply, exchange_dt),
-            }
-        )
+    /// Replies are delivered to the traders once the event has been handled
+    pub(crate) fn schedule_reply_for_trader(&mut self, reply: ExchangeReply) {
+        self.trader_replies.push((None, reply))
+    }
+
+    /// Replies referring to no order of the trader, such as the mass-cancel ones
+    pub(crate) fn schedule_reply_to_trader(&mut self, trader: TraderID, reply: ExchangeReply) {
+        self.trader_replies.push((Some(trader), reply))
     }
 
     pub(crate) fn schedule_history_event(&mut self, event: HistoryEvent) {
         self.push(
             Event {
                 datetime: event.datetime,
-                body: EventBody::HistoryEvent(event.event),
+                body: EventBody::HistoryEvent(event.instrument, event.event),
             }
         )
     }
@@ -0,0 +1,38 @@
+use derive_more::{Add, AddAssign, Sub, SubAssign, Sum};
+
+use crate::instrument::InstrumentSpec;
+use crate::types::{Price, Size};
+
+/// Whether the executed trader order has added the liquidity to the order book or removed it
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum Liquidity {
+    Added,
+    Removed,
+    /// Execution at the uncrossing of an auction
+    Auction,
+}
+
+/// Fee in millionths of the instrument currency. Negative fees are rebates
+#[derive(Debug, Default, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy, Add, Sum, Sub, AddAssign, SubAssign)]
+pub struct Fee(pub i64);
+
+impl Fee {
+    pub fn from_f64(value: f64) -> Self { Fee((value * 1e6).round() as i64) }
+
+    pub fn to_f64(&self) -> f64 { self.0 as f64 / 1e6 }
+}
+
+pub trait FeeSchedule {
+    /// Returns the fee of the trader execution. `monthly_volume` is the trader volume executed
+    /// in the calendar month before this execution
+    fn get_fee(&mut self,
+               instrument: &InstrumentSpec,
+               liquidity: Liquidity,
+               price: Price,
+               size: Size,
+               monthly_volume: Size) -> Fee;
+
+    /// Returns the fee of the whole order given the sum of the fees of its executions, which is where
+    /// the minimum fee of the order, if any, applies
+    fn get_order_fee(&self, fee: Fee, _monthly_volume: Size) -> Fee { fee }
+}
@@ -0,0 +1,90 @@
+use crate::fees::interface::{Fee, FeeSchedule, Liquidity};
+use crate::instrument::InstrumentSpec;
+use crate::types::{Price, Size};
+
+/// Executions are free
+pub struct NoFees;
+
+impl FeeSchedule for NoFees {
+    fn get_fee(&mut self, _: &InstrumentSpec, _: Liquidity, _: Price, _: Size, _: Size) -> Fee { Fee(0) }
+}
+
+/// Rate charged for an execution. Negative rates are rebates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeRate {
+    /// Currency per unit of the executed size
+    PerShare(f64),
+    /// Basis points of the executed notional
+    Bps(f64),
+}
+
+impl FeeRate {
+    fn get_fee(&self, instrument: &InstrumentSpec, price: Price, size: Size) -> f64 {
+        match self {
+            FeeRate::PerShare(rate) => { rate * size.0 as f64 }
+            FeeRate::Bps(rate) => { rate * 1e-4 * instrument.get_notional(price, size) }
+        }
+    }
+}
+
+/// Maker and taker rates. Auction executions are charged the taker rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MakerTaker {
+    pub maker: FeeRate,
+    pub taker: FeeRate,
+    /// Minimum fee of an order that is charged a fee rather than paid a rebate
+    pub min_fee: f64,
+}
+
+impl FeeSchedule for MakerTaker {
+    fn get_fee(&mut self, instrument: &InstrumentSpec, liquidity: Liquidity, price: Price, size: Size, _: Size) -> Fee {
+        let rate = match liquidity {
+            Liquidity::Added => { self.maker }
+            Liquidity::Removed | Liquidity::Auction => { self.taker }
+        };
+        Fee::from_f64(rate.get_fee(instrument, price, size))
+    }
+
+    fn get_order_fee(&self, fee: Fee, _: Size) -> Fee {
+        if fee > Fee(0) { fee.max(Fee::from_f64(self.min_fee)) } else { fee }
+    }
+}
+
+/// Maker-taker rates depending on the trader volume executed in the month. Each tier applies from its minimum
+/// monthly volume, and the first tier applies below all of them
+pub struct Tiered {
+    tiers: Vec<(Size, MakerTaker)>,
+}
+
+impl Tiered {
+    pub fn new(mut tiers: Vec<(Size, MakerTaker)>) -> Self {
+        if tiers.is_empty() {
+            panic!("Tiered fee schedule should have at least one tier")
+        }
+        tiers.sort_by_key(|(min_volume, _)| *min_volume);
+        Tiered { tiers }
+    }
+
+    fn get_tier(&self, monthly_volume: Size) -> MakerTaker {
+        self.tiers.iter()
+            .rev()
+            .find(|(min_volume, _)| *min_volume <= monthly_volume)
+            .unwrap_or(&self.tiers[0])
+            .1
+    }
+}
+
+impl FeeSchedule for Tiered {
+    fn get_fee(&mut self,
+               instrument: &InstrumentSpec,
+               liquidity: Liquidity,
+               price: Price,
+               size: Size,
+               monthly_volume: Size) -> Fee {
+        self.get_tier(monthly_volume).get_fee(instrument, liquidity, price, size, monthly_volume)
+    }
+
+    fn get_order_fee(&self, fee: Fee, monthly_volume: Size) -> Fee {
+        self.get_tier(monthly_volume).get_order_fee(fee, monthly_volume)
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use crate::types::{DateTime, Duration, Price};
+
+/// Band around the last traded price outside of which the trader limit orders are discarded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceBand {
+    /// Half-width of the band in basis points of the reference price
+    pub width_bps: i64,
+}
+
+impl PriceBand {
+    pub(crate) fn contains(&self, reference_price: Price, price: Price) -> bool {
+        !is_beyond_threshold(reference_price, price, self.width_bps)
+    }
+}
+
+/// Traded price movement that halts the trading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HaltTrigger {
+    /// Move from the session reference price: the last traded price before the open, or the first traded price of
+    /// the session if there is none. Triggers at most once a session
+    CircuitBreaker { threshold_bps: i64 },
+    /// Move between any two trades within the window
+    Volatility { threshold_bps: i64, window: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HaltResumption {
+    /// Order placements are discarded during the halt, and the continuous trading resumes after it
+    Continuous,
+    /// The halt is the call phase of the reopening auction
+    Auction { indicative_interval: Option<Duration> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradingHalt {
+    pub trigger: HaltTrigger,
+    pub duration: Duration,
+    pub resumption: HaltResumption,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum HaltReason {
+    CircuitBreaker,
+    Volatility,
+}
+
+impl TradingHalt {
+    pub fn get_reason(&self) -> HaltReason {
+        match self.trigger {
+            HaltTrigger::CircuitBreaker { .. } => { HaltReason::CircuitBreaker }
+            HaltTrigger::Volatility { .. } => { HaltReason::Volatility }
+        }
+    }
+}
+
+/// Traded prices the halt triggers are checked against
+#[derive(Default)]
+pub(crate) struct PriceMonitor {
+    reference_price: Option<Price>,
+    circuit_breaker_triggered: bool,
+    recent_prices: VecDeque<(DateTime, Price)>,
+}
+
+impl PriceMonitor {
+    pub(crate) fn start_session(&mut self, reference_price: Option<Price>) {
+        self.reference_price = reference_price;
+        self.circuit_breaker_triggered = false;
+        self.recent_prices.clear();
+    }
+
+    /// Records the price range traded at the moment and returns the first halt it triggers
+    pub(crate) fn update(&mut self,
+                         halts: &[TradingHalt],
+                         datetime: DateTime,
+                         (min_price, max_price): (Price, Price)) -> Option<TradingHalt> {
+        let reference_price = *self.reference_price.get_or_insert(min_price);
+        self.recent_prices.extend([(datetime, min_price), (datetime, max_price)]);
+        let max_window = halts.iter()
+            .filter_map(
+                |halt| match halt.trigger {
+                    HaltTrigger::Volatility { window, .. } => { Some(window) }
+                    HaltTrigger::CircuitBreaker { .. } => { None }
+                }
+            )
+            .max()
+            .unwrap_or_else(Duration::zero);
+        while matches!(self.recent_prices.front(), Some((dt, _)) if *dt < datetime - max_window) {
+            self.recent_prices.pop_front();
+        }
+
+        let halt = halts.iter().copied().find(
+            |halt| match halt.trigger {
+                HaltTrigger::CircuitBreaker { threshold_bps } => {
+                    !self.circuit_breaker_triggered
+                        && (is_beyond_threshold(reference_price, min_price, threshold_bps)
+                        || is_beyond_threshold(reference_price, max_price, threshold_bps))
+                }
+                HaltTrigger::Volatility { threshold_bps, window } => {
+                    let mut prices = self.recent_prices.iter()
+                        .filter(|(dt, _)| *dt >= datetime - window)
+                        .map(|(_, price)| *price);
+                    let first = prices.next().unwrap_or(min_price);
+                    let (low, high) = prices.fold((first, first), |(low, high), price| (low.min(price), high.max(price)));
+                    is_beyond_threshold(low, high, threshold_bps)
+                }
+            }
+        )?;
+        if halt.get_reason() == HaltReason::CircuitBreaker {
+            self.circuit_breaker_triggered = true
+        }
+        // Prices before the halt do not trigger the next one
+        self.recent_prices.clear();
+        Some(halt)
+    }
+}
+
+fn is_beyond_threshold(reference_price: Price, price: Price, threshold_bps: i64) -> bool {
+    (price.0 - reference_price.0).abs() as i128 * 10_000 > threshold_bps as i128 * reference_price.0.abs() as i128
+}
@@ -7,7 +7,7 @@ use crate::history::{
     types::{HistoryEvent, HistoryEventBody},
 };
 use crate::input::InputInterface;
-use crate::types::{DateTime, Direction, OrderID, Price, Size};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size};
 
 pub mod interface;
 
@@ -17,8 +17,8 @@ pub struct HistoryParser<'a, ParsingInfo>
     ob_diff_history_parser: OBDiffHistoryReader<'a, ParsingInfo>,
     trade_history_parser: TradeHistoryReader<'a, ParsingInfo>,
 
-    last_ob_diff: Option<(DateTime, Size, Direction, Price, OrderID)>,
-    last_trd: Option<(DateTime, Size, Direction, OrderID)>,
+    last_ob_diff: Option<(DateTime, Size, Direction, Price, OrderID, InstrumentID)>,
+    last_trd: Option<(DateTime, Size, Direction, OrderID, InstrumentID)>,
 
     last_dt: DateTime,
 }
@@ -53,13 +53,14 @@ impl<T: InputInterface> EventProcessor for HistoryParser<'_, T>
     {
         match (&self.last_trd, &self.last_ob_diff) {
             (
-                Some((trd_dt, trd_size, trd_dir, trd_id)),
-                Some((ob_diff_dt, ob_diff_size, ob_diff_dir, ob_diff_price, ob_diff_id))
+                Some((trd_dt, trd_size, trd_dir, trd_id, trd_instrument)),
+                Some((ob_diff_dt, ob_diff_size, ob_diff_dir, ob_diff_price, ob_diff_id, ob_diff_instrument))
             ) => {
                 match (ob_diff_dt.cmp(trd_dt), trd_id > ob_diff_id) {
                     (Ordering::Less, _) | (Ordering::Equal, true) => {
                         let res = HistoryEvent {
                             datetime: *ob_diff_dt,
+                            instrument: *ob_diff_instrument,
                             event: HistoryEventBody::OrderBookDiff(*ob_diff_size, *ob_diff_dir, *ob_diff_price, *ob_diff_id),
                         };
                         if res.datetime < self.last_dt {
@@ -70,7 +71,11 @@ impl<T: InputInterface> EventProcessor for HistoryParser<'_, T>
                         Some(res)
                     }
                     (Ordering::Greater, _) | (Ordering::Equal, false) => {
-                        let res = HistoryEvent { datetime: *trd_dt, event: HistoryEventBody::Trade(*trd_size, *trd_dir) };
+                        let res = HistoryEvent {
+                            datetime: *trd_dt,
+                            instrument: *trd_instrument,
+                            event: HistoryEventBody::Trade(*trd_size, *trd_dir),
+                        };
                         if res.datetime < self.last_dt {
                             panic!("History file entries are not stored in ascending order by time")
                         }
@@ -80,8 +85,12 @@ impl<T: InputInterface> EventProcessor for HistoryParser<'_, T>
                     }
                 }
             }
-            (Some((trd_dt, trd_size, trd_dir, _)), None) => {
-                let res = HistoryEvent { datetime: *trd_dt, event: HistoryEventBody::Trade(*trd_size, *trd_dir) };
+            (Some((trd_dt, trd_size, trd_dir, _, trd_instrument)), None) => {
+                let res = HistoryEvent {
+                    datetime: *trd_dt,
+                    instrument: *trd_instrument,
+                    event: HistoryEventBody::Trade(*trd_size, *trd_dir),
+                };
                 if res.datetime < self.last_dt {
                     panic!("History file entries are not stored in ascending order by time")
                 }
@@ -89,9 +98,10 @@ impl<T: InputInterface> EventProcessor for HistoryParser<'_, T>
                 self.last_trd = self.trade_history_parser.next();
                 Some(res)
             }
-            (None, Some((ob_diff_dt, ob_diff_size, ob_diff_dir, ob_diff_price, ob_diff_id))) => {
+            (None, Some((ob_diff_dt, ob_diff_size, ob_diff_dir, ob_diff_price, ob_diff_id, ob_diff_instrument))) => {
                 let res = HistoryEvent {
                     datetime: *ob_diff_dt,
+                    instrument: *ob_diff_instrument,
                     event: HistoryEventBody::OrderBookDiff(*ob_diff_size, *ob_diff_dir, *ob_diff_price, *ob_diff_id),
                 };
                 if res.datetime < self.last_dt {
@@ -4,16 +4,17 @@ use std::io::{BufRead, BufReader};
 
 use csv::ReaderBuilder;
 
-use crate::history::types::{HistoryEvent, OBDiffHistoryColumnIndexInfo, TradeHistoryColumnIndexInfo};
+use crate::history::types::{HistoryEvent, OBDiffHistoryColumnIndexInfo, SymbolTable, TradeHistoryColumnIndexInfo};
 use crate::input::InputInterface;
-use crate::types::{DateTime, Direction, OrderID, Price, Size};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size};
 use crate::utils::ExpectWith;
 
 pub(crate)
 struct OBDiffHistoryReader<'a, ParsingInfo: InputInterface>
 {
     files_to_parse: VecDeque<String>,
-    buffered_entries: VecDeque<(DateTime, Size, Direction, Price, OrderID)>,
+    buffered_entries: VecDeque<(DateTime, Size, Direction, Price, OrderID, InstrumentID)>,
+    symbols: SymbolTable,
     args: &'a ParsingInfo,
 }
 
@@ -31,6 +32,7 @@ impl<ParsingInfo: InputInterface> OBDiffHistoryReader<'_, ParsingInfo>
         let mut res = OBDiffHistoryReader {
             files_to_parse,
             buffered_entries: VecDeque::new(),
+            symbols: SymbolTable::new(args),
             args,
         };
         res.buffer_next_file().expect("No history files provided");
@@ -38,7 +40,7 @@ impl<ParsingInfo: InputInterface> OBDiffHistoryReader<'_, ParsingInfo>
     }
 
     pub(crate)
-    fn next(&mut self) -> Option<(DateTime, Size, Direction, Price, OrderID)>
+    fn next(&mut self) -> Option<(DateTime, Size, Direction, Price, OrderID, InstrumentID)>
     {
         match self.buffered_entries.pop_front() {
             None => loop {
@@ -60,15 +62,15 @@ impl<ParsingInfo: InputInterface> OBDiffHistoryReader<'_, ParsingInfo>
     pub(crate)
     fn buffer_next_file(&mut self) -> Result<(), ()>
     {
-        let file_to_read = match self.files_to_parse.pop_front() {
-            Some(file_to_read) => { file_to_read }
+        let (file_to_read, file_instrument) = match self.files_to_parse.pop_front() {
+            Some(line) => { self.symbols.parse_file_line(&line, self.args.get_csv_sep()) }
             None => { return Err(()); }
         };
+        let file_instrument = file_instrument.unwrap_or_default();
         let cur_file_content = read_to_string(&file_to_read).expect_with(
             || format!("Cannot read the following file: {}", file_to_read)
         );
         let col_idx_info = OBDiffHistoryColumnIndexInfo::new_for_csv(&file_to_read, self.args);
-        let price_step = self.args.get_price_step();
         let datetime_format = self.args.get_datetime_format();
         self.buffered_entries.extend(
             ReaderBuilder::new()
@@ -83,8 +85,9 @@ impl<ParsingInfo: InputInterface> OBDiffHistoryReader<'_, ParsingInfo>
                                 || format!("Cannot parse {}-th CSV-record for the file: {}", row, file_to_read)
                             ),
                             &col_idx_info,
-                            price_step,
                             datetime_format,
+                            &self.symbols,
+                            file_instrument,
                         )
                 )
         );
@@ -96,7 +99,8 @@ pub(crate)
 struct TradeHistoryReader<'a, ParsingInfo: InputInterface>
 {
     files_to_parse: VecDeque<String>,
-    buffered_entries: VecDeque<(DateTime, Size, Direction, OrderID)>,
+    buffered_entries: VecDeque<(DateTime, Size, Direction, OrderID, InstrumentID)>,
+    symbols: SymbolTable,
     args: &'a ParsingInfo,
 }
 
@@ -114,6 +118,7 @@ impl<ParsingInfo: InputInterface> TradeHistoryReader<'_, ParsingInfo>
         let mut res = TradeHistoryReader {
             files_to_parse,
             buffered_entries: VecDeque::new(),
+            symbols: SymbolTable::new(args),
             args,
         };
         res.buffer_next_file().expect("No history files provided");
@@ -121,7 +126,7 @@ impl<ParsingInfo: InputInterface> TradeHistoryReader<'_, ParsingInfo>
     }
 
     pub(crate)
-    fn next(&mut self) -> Option<(DateTime, Size, Direction, OrderID)>
+    fn next(&mut self) -> Option<(DateTime, Size, Direction, OrderID, InstrumentID)>
     {
         match self.buffered_entries.pop_front() {
             None => loop {
@@ -143,10 +148,11 @@ impl<ParsingInfo: InputInterface> TradeHistoryReader<'_, ParsingInfo>
     pub(crate)
     fn buffer_next_file(&mut self) -> Result<(), ()>
     {
-        let file_to_read = match self.files_to_parse.pop_front() {
-            Some(file_to_read) => { file_to_read }
+        let (file_to_read, file_instrument) = match self.files_to_parse.pop_front() {
+            Some(line) => { self.symbols.parse_file_line(&line, self.args.get_csv_sep()) }
             None => { return Err(()); }
         };
+        let file_instrument = file_instrument.unwrap_or_default();
         let cur_file_content = read_to_string(&file_to_read).expect_with(
             || format!("Cannot read the following file: {}", file_to_read)
         );
@@ -166,6 +172,8 @@ impl<ParsingInfo: InputInterface> TradeHistoryReader<'_, ParsingInfo>
                             ),
                             &col_idx_info,
                             datetime_format,
+                            &self.symbols,
+                            file_instrument,
                         )
                 )
         );
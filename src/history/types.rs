@@ -3,10 +3,10 @@ use std::str::FromStr;
 use csv::{ReaderBuilder, StringRecord};
 
 use crate::input::InputInterface;
-use crate::types::{DateTime, Direction, OrderID, Price, Size};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size};
 use crate::utils::ExpectWith;
 
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub(crate) enum OrderOrigin {
     History,
     Trader,
@@ -22,9 +22,75 @@ pub enum HistoryEventBody {
 pub struct HistoryEvent
 {
     pub datetime: DateTime,
+    pub instrument: InstrumentID,
     pub event: HistoryEventBody,
 }
 
+/// Symbols listed by the input with their price steps. The instrument of each symbol is its position in the list
+pub(crate) struct SymbolTable {
+    symbols: Vec<(String, f64)>,
+    price_step: f64,
+}
+
+impl SymbolTable
+{
+    pub(crate)
+    fn new<ParsingInfo: InputInterface>(args: &ParsingInfo) -> SymbolTable
+    {
+        let price_step = args.get_price_step();
+        SymbolTable {
+            symbols: args.get_symbols()
+                .split(',')
+                .map(|symbol| symbol.trim())
+                .filter(|symbol| !symbol.is_empty())
+                .map(
+                    |symbol| match symbol.split_once(':') {
+                        Some((symbol, step)) => {
+                            let step = f64::from_str(step.trim()).expect_with(
+                                || format!("Cannot parse the price step of the symbol {}: {}", symbol, step)
+                            );
+                            (symbol.trim().to_string(), step)
+                        }
+                        None => { (symbol.to_string(), price_step) }
+                    }
+                )
+                .collect(),
+            price_step,
+        }
+    }
+
+    pub(crate)
+    fn get_instrument(&self, symbol: &str) -> InstrumentID
+    {
+        match self.symbols.iter().position(|(listed, _)| listed == symbol) {
+            Some(i) => { InstrumentID(i as u64) }
+            None => {
+                let listed: Vec<_> = self.symbols.iter().map(|(listed, _)| listed).collect();
+                panic!("Symbol {} is not listed in the input symbols: {:?}", symbol, listed)
+            }
+        }
+    }
+
+    /// Price step of the symbol of the instrument. The default instrument of the runs without symbols
+    /// uses the input price step
+    pub(crate)
+    fn get_price_step(&self, instrument: InstrumentID) -> f64
+    {
+        self.symbols.get(instrument.0 as usize).map_or(self.price_step, |(_, price_step)| *price_step)
+    }
+
+    /// Splits the line of the history files list into the path and the instrument of the per-file symbol, if any.
+    /// The symbol follows the path after the CSV-file separator
+    pub(crate)
+    fn parse_file_line(&self, line: &str, csv_sep: char) -> (String, Option<InstrumentID>)
+    {
+        match line.split_once(csv_sep) {
+            Some((path, symbol)) => { (path.to_string(), Some(self.get_instrument(symbol.trim()))) }
+            None => { (line.to_string(), None) }
+        }
+    }
+}
+
 pub(crate) struct OBDiffHistoryColumnIndexInfo
 {
     price_idx: usize,
@@ -32,6 +98,7 @@ pub(crate) struct OBDiffHistoryColumnIndexInfo
     datetime_idx: usize,
     buy_sell_flag_idx: usize,
     order_id_idx: usize,
+    symbol_idx: Option<usize>,
 }
 
 pub(crate) struct TradeHistoryColumnIndexInfo
@@ -40,20 +107,26 @@ pub(crate) struct TradeHistoryColumnIndexInfo
     datetime_idx: usize,
     buy_sell_flag_idx: usize,
     order_id_idx: usize,
+    symbol_idx: Option<usize>,
 }
 
 impl HistoryEvent
 {
     pub(crate) fn parse_ob_diff(record: StringRecord,
                                 col_idx_info: &OBDiffHistoryColumnIndexInfo,
-                                price_step: f64,
-                                dt_format: &str) -> (DateTime, Size, Direction, Price, OrderID)
+                                dt_format: &str,
+                                symbols: &SymbolTable,
+                                file_instrument: InstrumentID) -> (DateTime, Size, Direction, Price, OrderID, InstrumentID)
     {
         let datetime = &record[col_idx_info.datetime_idx];
         let order_id = &record[col_idx_info.order_id_idx];
         let price = &record[col_idx_info.price_idx];
         let size = &record[col_idx_info.size_idx];
         let bs_flag = &record[col_idx_info.buy_sell_flag_idx];
+        let instrument = match col_idx_info.symbol_idx {
+            Some(symbol_idx) => { symbols.get_instrument(&record[symbol_idx]) }
+            None => { file_instrument }
+        };
         (
             DateTime::parse_from_str(datetime, dt_format).expect_with(
                 || format!("Cannot parse to NaiveDateTime: {}. Datetime format used: {}", datetime, dt_format)
@@ -68,18 +141,21 @@ impl HistoryEvent
                 "1" | "S" | "s" | "True" | "true" => { Direction::Sell }
                 _ => { panic!("Cannot parse buy-sell flag: {}", bs_flag) }
             },
-            Price::from_decimal_str(price, price_step),
+            Price::from_decimal_str(price, symbols.get_price_step(instrument)),
             OrderID(
                 u64::from_str(order_id).expect_with(
                     || format!("Cannot parse to u64: {}", order_id)
                 )
-            )
+            ),
+            instrument,
         )
     }
 
     pub(crate) fn parser_trade(record: StringRecord,
                                col_idx_info: &TradeHistoryColumnIndexInfo,
-                               dt_format: &str) -> (DateTime, Size, Direction, OrderID)
+                               dt_format: &str,
+                               symbols: &SymbolTable,
+                               file_instrument: InstrumentID) -> (DateTime, Size, Direction, OrderID, InstrumentID)
     {
         let datetime = &record[col_idx_info.datetime_idx];
         let order_id = &record[col_idx_info.order_id_idx];
@@ -103,7 +179,11 @@ impl HistoryEvent
                 u64::from_str(order_id).expect_with(
                     || format!("Cannot parse to u64: {}", order_id)
                 )
-            )
+            ),
+            match col_idx_info.symbol_idx {
+                Some(symbol_idx) => { symbols.get_instrument(&record[symbol_idx]) }
+                None => { file_instrument }
+            }
         )
     }
 }
@@ -119,12 +199,14 @@ impl OBDiffHistoryColumnIndexInfo
         let mut size_idx: Option<usize> = None;
         let mut price_idx: Option<usize> = None;
         let mut buy_sell_flag_idx: Option<usize> = None;
+        let mut symbol_idx: Option<usize> = None;
 
         let order_id_colname = args.get_order_id_colname();
         let datetime_colname = args.get_order_datetime_colname();
         let size_colname = args.get_order_size_colname();
         let price_colname = args.get_order_price_colname();
         let bs_flag_colname = args.get_order_bs_flag_colname();
+        let symbol_colname = args.get_symbol_colname();
 
         for (i, header) in ReaderBuilder::new()
             .delimiter(args.get_csv_sep() as u8)
@@ -160,6 +242,11 @@ impl OBDiffHistoryColumnIndexInfo
                     panic!("Duplicate column {} in the file: {}", bs_flag_colname, path)
                 }
                 buy_sell_flag_idx = Some(i)
+            } else if header == symbol_colname {
+                if let Some(_) = symbol_idx {
+                    panic!("Duplicate column {} in the file: {}", symbol_colname, path)
+                }
+                symbol_idx = Some(i)
             }
         };
         OBDiffHistoryColumnIndexInfo {
@@ -178,6 +265,7 @@ impl OBDiffHistoryColumnIndexInfo
             order_id_idx: order_id_idx.expect_with(
                 || format!("Cannot find {} column in the CSV-file: {}", order_id_colname, path)
             ),
+            symbol_idx,
         }
     }
 }
@@ -192,11 +280,13 @@ impl TradeHistoryColumnIndexInfo
         let mut datetime_idx: Option<usize> = None;
         let mut size_idx: Option<usize> = None;
         let mut buy_sell_flag_idx: Option<usize> = None;
+        let mut symbol_idx: Option<usize> = None;
 
         let order_id_colname = args.get_order_id_colname();
         let datetime_colname = args.get_order_datetime_colname();
         let size_colname = args.get_order_size_colname();
         let bs_flag_colname = args.get_order_bs_flag_colname();
+        let symbol_colname = args.get_symbol_colname();
 
         for (i, header) in ReaderBuilder::new()
             .delimiter(args.get_csv_sep() as u8)
@@ -227,6 +317,11 @@ impl TradeHistoryColumnIndexInfo
                     panic!("Duplicate column {} in the file: {}", bs_flag_colname, path)
                 }
                 buy_sell_flag_idx = Some(i)
+            } else if header == symbol_colname {
+                if let Some(_) = symbol_idx {
+                    panic!("Duplicate column {} in the file: {}", symbol_colname, path)
+                }
+                symbol_idx = Some(i)
             }
         };
         TradeHistoryColumnIndexInfo {
@@ -242,6 +337,7 @@ impl TradeHistoryColumnIndexInfo
             order_id_idx: order_id_idx.expect_with(
                 || format!("Cannot find {} column in the CSV-file: {}", order_id_colname, path)
             ),
+            symbol_idx,
         }
     }
 }
\ No newline at end of file
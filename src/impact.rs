@@ -0,0 +1,26 @@
+use crate::types::{Duration, Size};
+
+/// What happens to the history liquidity consumed by the trader orders. Later order book diffs of the consumed history
+/// orders are reduced by the consumed volume that has not recovered yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarketImpact {
+    /// The consumed volume never recovers
+    Permanent,
+    /// The consumed volume recovers exponentially. The recovery shows up at the next order book diff of the order
+    TransientDecay { half_life: Duration },
+    /// The exchange refills the consumed orders by `refill_size` every `refill_interval`
+    Resiliency { refill_size: Size, refill_interval: Duration },
+}
+
+impl MarketImpact {
+    pub(crate) fn get_unrecovered(&self, consumed: Size, elapsed: Duration) -> Size {
+        match self {
+            MarketImpact::Permanent | MarketImpact::Resiliency { .. } => { consumed }
+            MarketImpact::TransientDecay { half_life } => {
+                let half_lives = elapsed.num_nanoseconds().unwrap_or(i64::MAX) as f64
+                    / half_life.num_nanoseconds().unwrap_or(i64::MAX) as f64;
+                Size((consumed.0 as f64 * 0.5f64.powf(half_lives)).round() as i64)
+            }
+        }
+    }
+}
@@ -10,6 +10,12 @@ pub trait InputInterface {
     fn get_order_price_colname(&self) -> &str;
     fn get_order_size_colname(&self) -> &str;
     fn get_order_bs_flag_colname(&self) -> &str;
+    /// Column with the symbols of the history entries. Files without it trade the symbol given for the file
+    /// in the files list, if any, or the default instrument
+    fn get_symbol_colname(&self) -> &str;
+    /// Comma-separated symbols. The instrument of each symbol is its position in the list. A symbol may be followed
+    /// by its own price step after a colon, e.g. `AAPL:0.01`
+    fn get_symbols(&self) -> &str;
     fn get_datetime_format(&self) -> &str;
     fn get_csv_sep(&self) -> char;
     fn get_price_step(&self) -> f64;
@@ -27,6 +27,13 @@ pub struct ArgumentParser {
     /// Sets the name of the order buy-sell flag columns in the input csv files
     #[clap(long = "--bs-flag-colname", default_value = ORDER_BS_FLAG_COLNAME)]
     order_bs_flag_colname: String,
+    /// Sets the name of the optional symbol columns in the input csv files
+    #[clap(long = "--symbol-colname", default_value = SYMBOL_COLNAME)]
+    symbol_colname: String,
+    /// Comma-separated symbols, each optionally followed by its price step after a colon. The history files list may
+    /// give the symbol of each file after the CSV-file separator
+    #[clap(long = "--symbols", default_value = SYMBOLS)]
+    symbols: String,
     /// Sets the datetime format to parse timestamp columns
     #[clap(short, long, default_value = DATETIME_FORMAT)]
     datetime_format: String,
@@ -46,6 +53,8 @@ impl InputInterface for ArgumentParser {
     fn get_order_price_colname(&self) -> &str { self.order_price_colname.as_str() }
     fn get_order_size_colname(&self) -> &str { self.order_size_colname.as_str() }
     fn get_order_bs_flag_colname(&self) -> &str { self.order_bs_flag_colname.as_str() }
+    fn get_symbol_colname(&self) -> &str { self.symbol_colname.as_str() }
+    fn get_symbols(&self) -> &str { self.symbols.as_str() }
     fn get_datetime_format(&self) -> &str { self.datetime_format.as_str() }
     fn get_csv_sep(&self) -> char { self.csv_sep }
     fn get_price_step(&self) -> f64 { self.price_step }
@@ -3,6 +3,8 @@ pub const ORDER_ID_COLNAME: &str = "ORDER_ID";
 pub const ORDER_PRICE_COLNAME: &str = "PRICE";
 pub const ORDER_SIZE_COLNAME: &str = "SIZE";
 pub const ORDER_BS_FLAG_COLNAME: &str = "BUY_SELL_FLAG";
+pub const SYMBOL_COLNAME: &str = "SYMBOL";
+pub const SYMBOLS: &str = "";
 pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
 pub const CSV_SEP: &str = ",";
 pub const PRICE_STEP: &str = "0.0025";
\ No newline at end of file
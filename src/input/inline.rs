@@ -8,6 +8,8 @@ pub struct StaticInput {
     order_price_colname: String,
     order_size_colname: String,
     order_bs_flag_colname: String,
+    symbol_colname: String,
+    symbols: String,
     datetime_format: String,
     csv_sep: char,
     price_step: f64,
@@ -23,6 +25,8 @@ impl Default for StaticInput {
             order_price_colname: ORDER_PRICE_COLNAME.to_string(),
             order_size_colname: ORDER_SIZE_COLNAME.to_string(),
             order_bs_flag_colname: ORDER_BS_FLAG_COLNAME.to_string(),
+            symbol_colname: SYMBOL_COLNAME.to_string(),
+            symbols: SYMBOLS.to_string(),
             datetime_format: DATETIME_FORMAT.to_string(),
             csv_sep: CSV_SEP.parse().unwrap(),
             price_step: PRICE_STEP.parse().unwrap(),
@@ -61,6 +65,16 @@ impl StaticInput {
         self.order_bs_flag_colname = order_bs_flag_colname.to_string();
         self
     }
+    pub fn with_symbol_colname(mut self, symbol_colname: &str) -> Self {
+        self.symbol_colname = symbol_colname.to_string();
+        self
+    }
+    /// Comma-separated symbols. The instrument of each symbol is its position in the list. A symbol may be followed
+    /// by its own price step after a colon, e.g. `AAPL:0.01`
+    pub fn with_symbols(mut self, symbols: &str) -> Self {
+        self.symbols = symbols.to_string();
+        self
+    }
     pub fn with_datetime_format(mut self, datetime_format: &str) -> Self {
         self.datetime_format = datetime_format.to_string();
         self
@@ -93,6 +107,8 @@ impl InputInterface for StaticInput {
     fn get_order_price_colname(&self) -> &str { self.order_price_colname.as_str() }
     fn get_order_size_colname(&self) -> &str { self.order_size_colname.as_str() }
     fn get_order_bs_flag_colname(&self) -> &str { self.order_bs_flag_colname.as_str() }
+    fn get_symbol_colname(&self) -> &str { self.symbol_colname.as_str() }
+    fn get_symbols(&self) -> &str { self.symbols.as_str() }
     fn get_datetime_format(&self) -> &str { self.datetime_format.as_str() }
     fn get_csv_sep(&self) -> char { self.csv_sep }
     fn get_price_step(&self) -> f64 { self.price_step }
@@ -0,0 +1,191 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::history::types::SymbolTable;
+use crate::input::{default::PRICE_STEP, InputInterface};
+use crate::message::{DiscardingReason, InabilityToModifyReason};
+use crate::types::{InstrumentID, Price, PriceStep, Size};
+
+/// Reference data of the traded instrument. The exchange discards the trader orders that do not conform to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentSpec {
+    price_step: f64,
+    tick_table: Vec<(Price, i64)>,
+    lot_size: Size,
+    min_order_size: Size,
+    max_order_size: Option<Size>,
+    multiplier: f64,
+    currency: String,
+}
+
+/// Reason to reject the setting of the instrument spec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidSpec {
+    NonPositiveTickSize(i64),
+    NonPositiveLotSize(Size),
+}
+
+impl Display for InvalidSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidSpec::NonPositiveTickSize(tick) => { write!(f, "Tick sizes should be positive: {}", tick) }
+            InvalidSpec::NonPositiveLotSize(lot_size) => { write!(f, "Lot size should be positive: {:?}", lot_size) }
+        }
+    }
+}
+
+impl Error for InvalidSpec {}
+
+/// Way in which the order does not conform to the instrument spec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SpecViolation {
+    OffTickPrice,
+    InvalidLotSize,
+    SizeTooSmall,
+    SizeTooLarge,
+}
+
+impl From<SpecViolation> for DiscardingReason {
+    fn from(violation: SpecViolation) -> Self {
+        match violation {
+            SpecViolation::OffTickPrice => { DiscardingReason::OffTickPrice }
+            SpecViolation::InvalidLotSize => { DiscardingReason::InvalidLotSize }
+            SpecViolation::SizeTooSmall => { DiscardingReason::SizeTooSmall }
+            SpecViolation::SizeTooLarge => { DiscardingReason::SizeTooLarge }
+        }
+    }
+}
+
+impl From<SpecViolation> for InabilityToModifyReason {
+    fn from(violation: SpecViolation) -> Self {
+        match violation {
+            SpecViolation::OffTickPrice => { InabilityToModifyReason::OffTickPrice }
+            SpecViolation::InvalidLotSize => { InabilityToModifyReason::InvalidLotSize }
+            SpecViolation::SizeTooSmall => { InabilityToModifyReason::SizeTooSmall }
+            SpecViolation::SizeTooLarge => { InabilityToModifyReason::SizeTooLarge }
+        }
+    }
+}
+
+impl Default for InstrumentSpec {
+    fn default() -> Self { InstrumentSpec::new(PRICE_STEP.parse().unwrap()) }
+}
+
+impl InstrumentSpec {
+    /// Price step is the value of `Price(1)`. Every price step is a tick unless a tick table is given
+    pub fn new(price_step: f64) -> Self {
+        InstrumentSpec {
+            price_step,
+            tick_table: vec![],
+            lot_size: Size(1),
+            min_order_size: Size(1),
+            max_order_size: None,
+            multiplier: 1.0,
+            currency: String::new(),
+        }
+    }
+
+    /// Spec with the price step the input gives to the symbol of the instrument, so that the exchange
+    /// and the history agree on the value of `Price(1)`
+    pub fn from_input<I: InputInterface>(input: &I, instrument: InstrumentID) -> Self {
+        InstrumentSpec::new(SymbolTable::new(input).get_price_step(instrument))
+    }
+
+    /// Tick sizes in price steps, each applied from the given price up to the next one
+    pub fn with_tick_table(mut self, mut tick_table: Vec<(Price, i64)>) -> Result<Self, InvalidSpec> {
+        if let Some(&(_, tick)) = tick_table.iter().find(|(_, tick)| *tick <= 0) {
+            return Err(InvalidSpec::NonPositiveTickSize(tick));
+        }
+        tick_table.sort();
+        self.tick_table = tick_table;
+        Ok(self)
+    }
+    pub fn with_lot_size(mut self, lot_size: Size) -> Result<Self, InvalidSpec> {
+        if lot_size <= Size(0) {
+            return Err(InvalidSpec::NonPositiveLotSize(lot_size));
+        }
+        self.lot_size = lot_size;
+        Ok(self)
+    }
+    pub fn with_min_order_size(mut self, min_order_size: Size) -> Self {
+        self.min_order_size = min_order_size;
+        self
+    }
+    pub fn with_max_order_size(mut self, max_order_size: Size) -> Self {
+        self.max_order_size = Some(max_order_size);
+        self
+    }
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+    pub fn with_currency(mut self, currency: &str) -> Self {
+        self.currency = currency.to_string();
+        self
+    }
+
+    /// Tick size in price steps at the given price
+    pub fn get_tick_size(&self, price: Price) -> i64 {
+        self.tick_table.iter()
+            .take_while(|(from_price, _)| *from_price <= price)
+            .last()
+            .map_or(1, |(_, tick)| *tick)
+    }
+    pub fn is_on_tick(&self, price: Price) -> bool { price.0.rem_euclid(self.get_tick_size(price)) == 0 }
+    pub fn get_lot_size(&self) -> Size { self.lot_size }
+    pub fn get_min_order_size(&self) -> Size { self.min_order_size }
+    pub fn get_max_order_size(&self) -> Option<Size> { self.max_order_size }
+    pub fn get_multiplier(&self) -> f64 { self.multiplier }
+    pub fn get_currency(&self) -> &str { self.currency.as_str() }
+
+    /// Value of the given size at the given price in the instrument currency
+    pub fn get_notional(&self, price: Price, size: Size) -> f64 {
+        price.to_f64(self) * size.0 as f64 * self.multiplier
+    }
+
+    /// Returns how the order with the given limit price, if any, and size violates the spec, if it does
+    pub(crate) fn check_order(&self, price: Option<Price>, size: Size) -> Option<SpecViolation> {
+        if price.map_or(false, |price| !self.is_on_tick(price)) {
+            Some(SpecViolation::OffTickPrice)
+        } else if size.0 % self.lot_size.0 != 0 {
+            Some(SpecViolation::InvalidLotSize)
+        } else if size < self.min_order_size {
+            Some(SpecViolation::SizeTooSmall)
+        } else if self.max_order_size.map_or(false, |max_order_size| size > max_order_size) {
+            Some(SpecViolation::SizeTooLarge)
+        } else {
+            None
+        }
+    }
+
+    /// Closest price on the tick grid at or below the given one
+    pub(crate) fn round_down(&self, mut price: Price) -> Price {
+        while !self.is_on_tick(price) {
+            price = Price(price.0 - price.0.rem_euclid(self.get_tick_size(price)))
+        }
+        price
+    }
+
+    /// Closest price on the tick grid at or above the given one
+    pub(crate) fn round_up(&self, mut price: Price) -> Price {
+        while !self.is_on_tick(price) {
+            let tick = self.get_tick_size(price);
+            price = Price(price.0 + tick - price.0.rem_euclid(tick))
+        }
+        price
+    }
+
+    /// Returns the price on the tick grid the given number of ticks above the given one,
+    /// or below it if the number is negative
+    pub(crate) fn shift_by_ticks(&self, price: Price, ticks: i64) -> Price {
+        if ticks >= 0 {
+            (0..ticks).fold(price, |price, _| self.round_up(Price(price.0 + 1)))
+        } else {
+            (ticks..0).fold(price, |price, _| self.round_down(Price(price.0 - 1)))
+        }
+    }
+}
+
+impl PriceStep for &InstrumentSpec {
+    fn get_price_step(&self) -> f64 { self.price_step }
+}
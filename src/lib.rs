@@ -15,31 +15,64 @@ mod history;
 
 pub mod trader;
 pub mod message;
+pub mod auction;
+pub mod fees;
+pub mod halt;
+pub mod impact;
 pub mod input;
+pub mod instrument;
 pub mod lags;
+pub mod matching;
+pub mod queue_position;
+pub mod risk;
+pub mod throttle;
+pub mod venue;
 
 pub mod prelude {
     pub use crate::{
-        exchange::{Exchange, interface::public::ExchangeBuilder, trades::history::OrderBookDiff},
+        auction::{AuctionInfo, AuctionPhase, CallAuction},
+        exchange::{Exchange, interface::public::{ExchangeBuilder, VoidNanoSecGen}, trades::history::OrderBookDiff},
+        fees,
+        fees::interface::{Fee, FeeSchedule, Liquidity},
+        halt::{HaltReason, HaltResumption, HaltTrigger, PriceBand, TradingHalt},
         history::{
             parser::{HistoryParser, interface::EventProcessor},
             types::{HistoryEvent, HistoryEventBody},
         },
+        impact::MarketImpact,
         input,
         input::{cli::{ArgumentParser, Parser}, inline::StaticInput, InputInterface},
+        instrument::{InstrumentSpec, InvalidSpec},
         lags,
         lags::interface::NanoSecondGenerator,
+        matching,
+        matching::interface::MatchingPolicy,
+        queue_position,
+        queue_position::interface::QueuePositionModel,
+        risk::{KillSwitchReason, KillSwitchScope, LossLimits, RiskLimits},
+        throttle::{MessageStatistics, RateLimit, ThrottlePolicy},
         message::{
             CancellationReason,
             DiscardingReason,
             ExchangeReply,
             InabilityToCancelReason,
+            InabilityToModifyReason,
             TraderRequest,
         },
-        order::{LimitOrder, MarketOrder},
+        order::{
+            GroupOrder,
+            LimitOrder,
+            MarketOrder,
+            MarketOrderPolicy,
+            PegReference,
+            PostOnlyMode,
+            SelfTradePrevention,
+            StopOrder,
+            TimeInForce,
+        },
         trader::{
             examples,
-            subscriptions::{HandleSubscriptionUpdates, OrderBookSnapshot},
+            subscriptions::{HandleSubscriptionUpdates, InstrumentSubscriptions, OrderBookSnapshot, TraderSubscriptions},
             Trader,
         },
         types::{
@@ -47,18 +80,23 @@ pub mod prelude {
             DateTime,
             Direction,
             Duration,
+            InstrumentID,
             NonZeroU64,
             NonZeroUsize,
             OrderID,
             Price,
+            PriceStep,
             Rng,
             SeedableRng,
             Size,
             StdRng,
             Time,
             Timelike,
+            TraderID,
+            VenueID,
         },
         utils::ExpectWith,
+        venue::{MultiVenue, MultiVenueTrader, Venue, VenueConnections, VenueLink},
     };
 }
 
@@ -126,4 +164,4 @@ mod integration {
 
         exchange.run_trades()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,3 @@
+pub mod fifo;
+pub mod interface;
+pub mod pro_rata;
@@ -0,0 +1,28 @@
+use std::cmp::min;
+
+use crate::matching::interface::MatchingPolicy;
+use crate::types::Size;
+
+/// Price-time priority: the orders are executed in the queue order
+pub struct Fifo;
+
+impl MatchingPolicy for Fifo {
+    const IN_QUEUE_ORDER: bool = true;
+
+    fn allocate(&self, executed_size: Size, order_sizes: &[Size]) -> Vec<Size> {
+        let mut allocations = vec![Size(0); order_sizes.len()];
+        allocate_in_queue_order(executed_size, order_sizes, &mut allocations);
+        allocations
+    }
+}
+
+pub(crate) fn allocate_in_queue_order(mut rest: Size, order_sizes: &[Size], allocations: &mut [Size]) {
+    for (size, allocation) in order_sizes.iter().zip(allocations.iter_mut()) {
+        if rest == Size(0) {
+            break;
+        }
+        let fill = min(*size - *allocation, rest);
+        *allocation += fill;
+        rest -= fill
+    }
+}
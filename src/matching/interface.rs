@@ -0,0 +1,11 @@
+use crate::types::Size;
+
+pub trait MatchingPolicy {
+    /// Whether the orders are executed one by one in the queue order. The level is then walked in place,
+    /// and `allocate` is not called
+    const IN_QUEUE_ORDER: bool = false;
+
+    /// Splits the executed size among the orders of the price level, which sizes are given in the queue order.
+    /// Allocations must sum up to the executed size and must not exceed the order sizes
+    fn allocate(&self, executed_size: Size, order_sizes: &[Size]) -> Vec<Size>;
+}
@@ -0,0 +1,50 @@
+use std::cmp::min;
+
+use crate::matching::{fifo::allocate_in_queue_order, interface::MatchingPolicy};
+use crate::types::Size;
+
+/// Each order gets the share of the executed size proportional to its size rounded down.
+/// Shares less than `min_allocation` are not allocated, and the rest is allocated in the queue order
+pub struct ProRata {
+    pub min_allocation: Size,
+}
+
+impl MatchingPolicy for ProRata {
+    fn allocate(&self, executed_size: Size, order_sizes: &[Size]) -> Vec<Size> {
+        let total_size: Size = order_sizes.iter().copied().sum();
+        if total_size == Size(0) {
+            return vec![Size(0); order_sizes.len()];
+        }
+        let mut allocations: Vec<Size> = order_sizes.iter()
+            .map(|size| Size((executed_size.0 as i128 * size.0 as i128 / total_size.0 as i128) as i64))
+            .map(|share| if share < self.min_allocation { Size(0) } else { share })
+            .collect();
+        let rest = executed_size - allocations.iter().copied().sum();
+        allocate_in_queue_order(rest, order_sizes, &mut allocations);
+        allocations
+    }
+}
+
+/// The first order in the queue is executed first, then the rest is allocated with `ProRata`
+pub struct TopOrderProRata {
+    pub min_allocation: Size,
+}
+
+impl MatchingPolicy for TopOrderProRata {
+    fn allocate(&self, executed_size: Size, order_sizes: &[Size]) -> Vec<Size> {
+        let top = match order_sizes.iter().position(|size| *size != Size(0)) {
+            Some(top) => { top }
+            None => { return vec![Size(0); order_sizes.len()]; }
+        };
+        let top_allocation = min(order_sizes[top], executed_size);
+        let mut allocations = vec![Size(0); top];
+        allocations.push(top_allocation);
+        allocations.extend(
+            ProRata { min_allocation: self.min_allocation }.allocate(
+                executed_size - top_allocation,
+                &order_sizes[top + 1..],
+            )
+        );
+        allocations
+    }
+}
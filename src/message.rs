@@ -1,51 +1,168 @@
+use crate::auction::{AuctionInfo, AuctionPhase};
 use crate::exchange::trades::history::OrderBookDiff;
-use crate::order::{LimitOrder, MarketOrder};
+use crate::fees::interface::{Fee, Liquidity};
+use crate::halt::HaltReason;
+use crate::order::{GroupOrder, LimitOrder, MarketOrder, StopOrder};
+use crate::risk::KillSwitchReason;
+use crate::throttle::MessageStatistics;
 use crate::trader::subscriptions::OrderBookSnapshot;
-use crate::types::{OrderID, Price, Size};
+use crate::types::{Direction, InstrumentID, OrderID, Price, Size};
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum TraderRequest {
+    /// Mass-cancel requests apply to the orders of all the instruments
+    CancelAll,
+    CancelAllOnSide(Direction),
+    /// Limit orders with the price between the given ones inclusive
+    CancelInPriceRange(Price, Price),
     CancelLimitOrder(OrderID),
     CancelMarketOrder(OrderID),
+    CancelStopOrder(OrderID),
+    ModifyLimitOrder(OrderID, Price, Size),
+    /// Parent order, then take-profit and stop-loss orders placed as an OCO pair once the parent is executed
+    PlaceBracketOrder(GroupOrder, GroupOrder, GroupOrder),
     PlaceLimitOrder(LimitOrder),
     PlaceMarketOrder(MarketOrder),
+    /// Two orders, the execution of either of which cancels the other one
+    PlaceOcoOrders(GroupOrder, GroupOrder),
+    PlaceStopOrder(StopOrder),
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+impl From<GroupOrder> for TraderRequest {
+    fn from(order: GroupOrder) -> Self {
+        match order {
+            GroupOrder::Limit(order) => { TraderRequest::PlaceLimitOrder(order) }
+            GroupOrder::Market(order) => { TraderRequest::PlaceMarketOrder(order) }
+            GroupOrder::Stop(order) => { TraderRequest::PlaceStopOrder(order) }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub enum ExchangeReply {
     OrderAccepted(OrderID),
     OrderPlacementDiscarded(OrderID, DiscardingReason),
-    OrderPartiallyExecuted(OrderID, Size, Price),
-    OrderExecuted(OrderID, Size, Price),
+    // Simultaneous replies are delivered in the order of declaration
+    OrderTriggered(OrderID),
+    OrderRepriced(OrderID, Price),
+    /// Executed size and price, whether the order has added or removed the liquidity, and the fee charged
+    OrderPartiallyExecuted(OrderID, Size, Price, Liquidity, Fee),
+    OrderExecuted(OrderID, Size, Price, Liquidity, Fee),
+    OrderRemainderPending(OrderID, Size),
+    OrderConvertedToLimit(OrderID, Price),
     OrderCancelled(OrderID, CancellationReason),
+    /// Number of the orders cancelled by the mass-cancel request
+    MassCancelled(usize),
     CannotCancelOrder(OrderID, InabilityToCancelReason),
+    OrderModified(OrderID, Price, Size),
+    CannotModifyOrder(OrderID, InabilityToModifyReason),
+    /// Matching of the instrument stops until `TradingResumed`
+    TradingHalted(InstrumentID, HaltReason),
+    TradingResumed(InstrumentID),
+    /// Active trader orders are cancelled and new placements are discarded while the kill switch is engaged
+    KillSwitchEngaged(KillSwitchReason),
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+impl ExchangeReply {
+    /// ID of the order the reply refers to, if any
+    pub(crate) fn mut_order_id(&mut self) -> Option<&mut OrderID> {
+        match self {
+            ExchangeReply::OrderAccepted(order_id)
+            | ExchangeReply::OrderPlacementDiscarded(order_id, _)
+            | ExchangeReply::OrderTriggered(order_id)
+            | ExchangeReply::OrderRepriced(order_id, _)
+            | ExchangeReply::OrderPartiallyExecuted(order_id, ..)
+            | ExchangeReply::OrderExecuted(order_id, ..)
+            | ExchangeReply::OrderRemainderPending(order_id, _)
+            | ExchangeReply::OrderConvertedToLimit(order_id, _)
+            | ExchangeReply::OrderCancelled(order_id, _)
+            | ExchangeReply::CannotCancelOrder(order_id, _)
+            | ExchangeReply::OrderModified(order_id, ..)
+            | ExchangeReply::CannotModifyOrder(order_id, _) => { Some(order_id) }
+            ExchangeReply::MassCancelled(_)
+            | ExchangeReply::TradingHalted(..)
+            | ExchangeReply::TradingResumed(_)
+            | ExchangeReply::KillSwitchEngaged(_) => { None }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub enum DiscardingReason {
     OrderWithSuchIDAlreadySubmitted,
     ZeroSize,
     ExchangeClosed,
+    InsufficientLiquidity,
+    AlreadyExpired,
+    WouldTakeLiquidity,
+    OneCancelsOther,
+    ParentNotExecuted,
+    OutsidePriceBand,
+    TradingHalted,
+    OffTickPrice,
+    InvalidLotSize,
+    SizeTooSmall,
+    SizeTooLarge,
+    RateLimited,
+    MaxOrderSizeExceeded,
+    MaxNotionalExceeded,
+    MaxOpenOrdersExceeded,
+    MaxPositionExceeded,
+    FatFingerPrice,
+    KillSwitch,
+    /// The order instrument has not been added to the exchange
+    UnknownInstrument,
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub enum CancellationReason {
     TraderRequested,
     ExchangeClosed,
+    ImmediateOrCancel,
+    Expired,
+    InsufficientLiquidity,
+    OneCancelsOther,
+    SelfTradePrevention,
+    KillSwitch,
+    OutsidePriceBand,
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub enum InabilityToCancelReason {
     OrderHasNotBeenSubmitted,
     OrderAlreadyExecuted,
     ExchangeClosed,
+    RateLimited,
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub enum InabilityToModifyReason {
+    OrderHasNotBeenSubmitted,
+    OrderAlreadyExecuted,
+    ZeroSize,
+    ExchangeClosed,
+    WouldTakeLiquidity,
+    OutsidePriceBand,
+    TradingHalted,
+    OffTickPrice,
+    InvalidLotSize,
+    SizeTooSmall,
+    SizeTooLarge,
+    RateLimited,
+    MaxOrderSizeExceeded,
+    MaxNotionalExceeded,
+    MaxPositionExceeded,
+    FatFingerPrice,
+    KillSwitch,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub(crate) enum SubscriptionUpdate {
     ExchangeOpen,
     OrderBook(OrderBookSnapshot),
     TradeInfo(Vec<OrderBookDiff>),
+    AuctionInfo(AuctionInfo),
+    MessageStatistics(MessageStatistics),
     ExchangeClosed,
 }
 
@@ -53,4 +170,5 @@ pub(crate) enum SubscriptionUpdate {
 pub(crate) enum SubscriptionSchedule {
     OrderBook,
     TradeInfo,
+    AuctionInfo(InstrumentID, AuctionPhase),
 }
\ No newline at end of file
@@ -1,41 +1,233 @@
-use crate::types::{Direction, OrderID, Price, Size};
+use crate::types::{DateTime, Direction, InstrumentID, OrderID, Price, Size};
 
 pub(crate) trait Order {
     fn get_order_id(&self) -> OrderID;
     fn get_order_size(&self) -> Size;
     fn mut_order_size(&mut self) -> &mut Size;
     fn get_order_direction(&self) -> Direction;
+    fn get_time_in_force(&self) -> TimeInForce;
 }
 
 pub(crate) trait PricedOrder: Order {
     fn get_price(&self) -> Price;
+    fn get_peak_size(&self) -> Option<Size>;
+    fn get_post_only(&self) -> Option<PostOnlyMode>;
+    fn get_peg(&self) -> Option<(PegReference, i64)>;
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum TimeInForce {
+    Day,
+    GoodTillCancel,
+    GoodTillTime(DateTime),
+    ImmediateOrCancel,
+    FillOrKill,
+    /// Market-on-close or limit-on-close order that takes part in the closing auction only
+    AtTheClose,
+}
+
+impl TimeInForce {
+    pub(crate) const fn outlives_session(self) -> bool {
+        matches!(self, TimeInForce::GoodTillCancel | TimeInForce::GoodTillTime(_))
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum PostOnlyMode {
+    Reject,
+    Reprice,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum PegReference {
+    /// Best price on the same side
+    Primary,
+    /// Best price on the opposite side
+    Market,
+    /// Middle of the best bid and the best ask
+    Midpoint,
+}
+
+/// What happens to the remainder of a trader market order that has exhausted the opposite side
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum MarketOrderPolicy {
+    CancelRemainder,
+    KeepPending,
+    ConvertToLimit { protection_ticks: i64 },
+}
+
+/// What happens when a trader order would be executed against another order of the same trader
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum SelfTradePrevention {
+    CancelNewest,
+    CancelOldest,
+    CancelBoth,
+    /// The smaller order is cancelled and the larger one is decreased by its size
+    DecrementAndCancel,
+}
+
+/// Member of the OCO or bracket order group
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum GroupOrder {
+    Limit(LimitOrder),
+    Market(MarketOrder),
+    Stop(StopOrder),
+}
+
+impl GroupOrder {
+    pub(crate) const fn get_order_id(&self) -> OrderID {
+        match self {
+            GroupOrder::Limit(order) => { order.get_order_id() }
+            GroupOrder::Market(order) => { order.get_order_id() }
+            GroupOrder::Stop(order) => { order.get_order_id() }
+        }
+    }
+
+    pub(crate) fn set_order_id(&mut self, order_id: OrderID) {
+        match self {
+            GroupOrder::Limit(order) => { order.set_order_id(order_id) }
+            GroupOrder::Market(order) => { order.set_order_id(order_id) }
+            GroupOrder::Stop(order) => { order.set_order_id(order_id) }
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct MarketOrder {
+    instrument: InstrumentID,
     order_id: OrderID,
     size: Size,
     direction: Direction,
+    time_in_force: TimeInForce,
 }
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct LimitOrder {
+    instrument: InstrumentID,
     order_id: OrderID,
     size: Size,
     direction: Direction,
     price: Price,
+    time_in_force: TimeInForce,
+    peak_size: Option<Size>,
+    post_only: Option<PostOnlyMode>,
+    peg: Option<(PegReference, i64)>,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StopOrder {
+    instrument: InstrumentID,
+    order_id: OrderID,
+    size: Size,
+    direction: Direction,
+    stop_price: Price,
+    limit_price: Option<Price>,
+    time_in_force: TimeInForce,
 }
 
 impl MarketOrder {
     pub const fn new(order_id: OrderID, size: Size, direction: Direction) -> MarketOrder {
-        MarketOrder { order_id, size, direction }
+        MarketOrder { instrument: InstrumentID(0), order_id, size, direction, time_in_force: TimeInForce::Day }
+    }
+
+    pub const fn with_instrument(mut self, instrument: InstrumentID) -> MarketOrder {
+        self.instrument = instrument;
+        self
     }
+
+    pub const fn with_time_in_force(mut self, time_in_force: TimeInForce) -> MarketOrder {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub(crate) const fn get_instrument(&self) -> InstrumentID { self.instrument }
+
+    pub(crate) fn set_order_id(&mut self, order_id: OrderID) { self.order_id = order_id }
 }
 
 impl LimitOrder {
     pub const fn new(order_id: OrderID, size: Size, direction: Direction, price: Price) -> LimitOrder {
-        LimitOrder { order_id, size, direction, price }
+        LimitOrder {
+            instrument: InstrumentID(0),
+            order_id,
+            size,
+            direction,
+            price,
+            time_in_force: TimeInForce::Day,
+            peak_size: None,
+            post_only: None,
+            peg: None,
+        }
     }
+
+    pub const fn with_instrument(mut self, instrument: InstrumentID) -> LimitOrder {
+        self.instrument = instrument;
+        self
+    }
+
+    pub const fn with_time_in_force(mut self, time_in_force: TimeInForce) -> LimitOrder {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub const fn with_peak_size(mut self, peak_size: Size) -> LimitOrder {
+        self.peak_size = Some(peak_size);
+        self
+    }
+
+    pub const fn with_post_only(mut self, mode: PostOnlyMode) -> LimitOrder {
+        self.post_only = Some(mode);
+        self
+    }
+
+    /// The order price follows the reference price shifted by `offset` ticks
+    pub const fn with_peg(mut self, reference: PegReference, offset: i64) -> LimitOrder {
+        self.peg = Some((reference, offset));
+        self
+    }
+
+    pub(crate) const fn get_instrument(&self) -> InstrumentID { self.instrument }
+
+    pub(crate) fn set_price(&mut self, price: Price) { self.price = price }
+
+    pub(crate) fn set_order_id(&mut self, order_id: OrderID) { self.order_id = order_id }
+}
+
+impl StopOrder {
+    pub const fn new(order_id: OrderID, size: Size, direction: Direction, stop_price: Price) -> StopOrder {
+        StopOrder {
+            instrument: InstrumentID(0),
+            order_id,
+            size,
+            direction,
+            stop_price,
+            limit_price: None,
+            time_in_force: TimeInForce::Day,
+        }
+    }
+
+    pub const fn with_instrument(mut self, instrument: InstrumentID) -> StopOrder {
+        self.instrument = instrument;
+        self
+    }
+
+    pub const fn with_limit_price(mut self, limit_price: Price) -> StopOrder {
+        self.limit_price = Some(limit_price);
+        self
+    }
+
+    pub const fn with_time_in_force(mut self, time_in_force: TimeInForce) -> StopOrder {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub(crate) const fn get_instrument(&self) -> InstrumentID { self.instrument }
+
+    pub(crate) const fn get_stop_price(&self) -> Price { self.stop_price }
+
+    pub(crate) const fn get_limit_price(&self) -> Option<Price> { self.limit_price }
+
+    pub(crate) fn set_order_id(&mut self, order_id: OrderID) { self.order_id = order_id }
 }
 
 impl const Order for MarketOrder {
@@ -43,6 +235,7 @@ impl const Order for MarketOrder {
     fn get_order_size(&self) -> Size { self.size }
     fn mut_order_size(&mut self) -> &mut Size { &mut self.size }
     fn get_order_direction(&self) -> Direction { self.direction }
+    fn get_time_in_force(&self) -> TimeInForce { self.time_in_force }
 }
 
 impl const Order for LimitOrder {
@@ -50,8 +243,20 @@ impl const Order for LimitOrder {
     fn get_order_size(&self) -> Size { self.size }
     fn mut_order_size(&mut self) -> &mut Size { &mut self.size }
     fn get_order_direction(&self) -> Direction { self.direction }
+    fn get_time_in_force(&self) -> TimeInForce { self.time_in_force }
 }
 
 impl const PricedOrder for LimitOrder {
     fn get_price(&self) -> Price { self.price }
-}
\ No newline at end of file
+    fn get_peak_size(&self) -> Option<Size> { self.peak_size }
+    fn get_post_only(&self) -> Option<PostOnlyMode> { self.post_only }
+    fn get_peg(&self) -> Option<(PegReference, i64)> { self.peg }
+}
+
+impl const Order for StopOrder {
+    fn get_order_id(&self) -> OrderID { self.order_id }
+    fn get_order_size(&self) -> Size { self.size }
+    fn mut_order_size(&mut self) -> &mut Size { &mut self.size }
+    fn get_order_direction(&self) -> Direction { self.direction }
+    fn get_time_in_force(&self) -> TimeInForce { self.time_in_force }
+}
@@ -0,0 +1,13 @@
+use crate::types::{Size, StdRng};
+
+pub trait QueuePositionModel {
+    /// Returns the part of the history volume cancelled at the price level that has been ahead of the trader order.
+    /// `volume_ahead` and `volume_behind` are the estimated history volumes around the trader order before the cancel,
+    /// and `queue_cancelled_ahead` is the part of the cancelled volume standing ahead of it in the order book queue
+    fn get_cancelled_ahead(&mut self,
+                           rng: &mut StdRng,
+                           volume_ahead: Size,
+                           volume_behind: Size,
+                           cancelled: Size,
+                           queue_cancelled_ahead: Size) -> Size;
+}
@@ -0,0 +1,78 @@
+use std::cmp::min;
+
+use crate::queue_position::interface::QueuePositionModel;
+use crate::types::{Rng, Size, StdRng};
+
+/// The trader order moves forward by the history volume cancelled ahead of it in the order book queue
+pub struct Exact;
+
+impl QueuePositionModel for Exact {
+    fn get_cancelled_ahead(&mut self, _: &mut StdRng, _: Size, _: Size, _: Size, queue_cancelled_ahead: Size) -> Size {
+        queue_cancelled_ahead
+    }
+}
+
+/// Cancels come from behind the trader order as long as there is some volume behind it
+pub struct Pessimistic;
+
+impl QueuePositionModel for Pessimistic {
+    fn get_cancelled_ahead(&mut self, _: &mut StdRng, _: Size, volume_behind: Size, cancelled: Size, _: Size) -> Size {
+        cancelled - min(cancelled, volume_behind)
+    }
+}
+
+/// Cancels are split between the volumes ahead and behind the trader order proportionally to them
+pub struct ProportionalCancel;
+
+impl QueuePositionModel for ProportionalCancel {
+    fn get_cancelled_ahead(&mut self,
+                           _: &mut StdRng,
+                           volume_ahead: Size,
+                           volume_behind: Size,
+                           cancelled: Size,
+                           _: Size) -> Size {
+        let volume = volume_ahead.0 as i128 + volume_behind.0 as i128;
+        if volume == 0 {
+            return Size(0);
+        }
+        Size((cancelled.0 as i128 * volume_ahead.0 as i128 / volume) as i64)
+    }
+}
+
+/// The whole cancel comes from ahead of the trader order with the probability
+/// `volume_ahead ^ power / (volume_ahead ^ power + volume_behind ^ power)`
+pub struct Probabilistic {
+    power: f64,
+}
+
+impl Probabilistic {
+    /// The power should be positive, so that the empty volume ahead or behind gets no cancels
+    pub fn new(power: f64) -> Self {
+        if power.is_nan() || power <= 0.0 {
+            panic!("Power of the probabilistic queue position model should be positive: {}", power)
+        }
+        Probabilistic { power }
+    }
+}
+
+impl QueuePositionModel for Probabilistic {
+    fn get_cancelled_ahead(&mut self,
+                           rng: &mut StdRng,
+                           volume_ahead: Size,
+                           volume_behind: Size,
+                           cancelled: Size,
+                           _: Size) -> Size {
+        let weight_ahead = (volume_ahead.0 as f64).powf(self.power);
+        let weight_behind = (volume_behind.0 as f64).powf(self.power);
+        let probability = if weight_ahead + weight_behind > 0.0 {
+            weight_ahead / (weight_ahead + weight_behind)
+        } else {
+            0.0
+        };
+        if rng.gen_bool(probability) {
+            min(cancelled, volume_ahead)
+        } else {
+            cancelled - min(cancelled, volume_behind)
+        }
+    }
+}
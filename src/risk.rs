@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use crate::fees::interface::Fee;
+use crate::message::DiscardingReason;
+use crate::types::{Direction, InstrumentID, OrderID, Size};
+
+/// Pre-trade limits the trader requests are checked against before the exchange handles them.
+/// Absent limits are not checked
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RiskLimits {
+    pub max_order_size: Option<Size>,
+    /// Order value in the instrument currency. Market orders are valued at the touch they are executed against
+    pub max_notional: Option<f64>,
+    /// Trader orders not yet executed, cancelled or expired, including the stop orders
+    pub max_open_orders: Option<usize>,
+    /// Absolute net position in the instrument the trader would have if the order were executed in full
+    pub max_position: Option<Size>,
+    /// Distance in basis points of the limit price from the touch on the opposite side
+    pub max_price_distance_bps: Option<i64>,
+}
+
+/// Post-trade limits on the trader profit and loss, which is realised plus marked to the last traded price,
+/// fees included. Breaching either of them engages the kill switch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LossLimits {
+    /// Loss in the instrument currency since the start of the scope
+    pub max_loss: Option<f64>,
+    /// Decline of the profit and loss from its peak within the scope
+    pub max_drawdown: Option<f64>,
+    pub scope: KillSwitchScope,
+}
+
+/// How long the kill switch stays engaged, which is also the period the losses are measured over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KillSwitchScope {
+    /// Until the next session opens
+    Session,
+    /// Until the end of the run
+    Run,
+}
+
+/// Pre-trade limit breached by the order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RiskLimitBreach {
+    MaxOrderSizeExceeded,
+    MaxNotionalExceeded,
+    MaxOpenOrdersExceeded,
+    MaxPositionExceeded,
+    FatFingerPrice,
+}
+
+impl From<RiskLimitBreach> for DiscardingReason {
+    fn from(breach: RiskLimitBreach) -> Self {
+        match breach {
+            RiskLimitBreach::MaxOrderSizeExceeded => { DiscardingReason::MaxOrderSizeExceeded }
+            RiskLimitBreach::MaxNotionalExceeded => { DiscardingReason::MaxNotionalExceeded }
+            RiskLimitBreach::MaxOpenOrdersExceeded => { DiscardingReason::MaxOpenOrdersExceeded }
+            RiskLimitBreach::MaxPositionExceeded => { DiscardingReason::MaxPositionExceeded }
+            RiskLimitBreach::FatFingerPrice => { DiscardingReason::FatFingerPrice }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub enum KillSwitchReason {
+    MaxLoss,
+    MaxDrawdown,
+}
+
+/// Positions and the profit and loss of the trader the limits are checked against
+#[derive(Default)]
+pub(crate) struct RiskGate {
+    positions: HashMap<InstrumentID, i64>,
+    orders: HashMap<OrderID, (InstrumentID, Direction)>,
+    /// Cash received for the executed sizes less the fees paid
+    cash: f64,
+    scope_start_pnl: f64,
+    peak_pnl: f64,
+    is_kill_switch_engaged: bool,
+}
+
+impl RiskGate {
+    pub(crate) fn get_position(&self, instrument: InstrumentID) -> Size {
+        Size(self.positions.get(&instrument).copied().unwrap_or(0))
+    }
+
+    pub(crate) fn is_kill_switch_engaged(&self) -> bool { self.is_kill_switch_engaged }
+
+    pub(crate) fn record_order(&mut self, order_id: OrderID, instrument: InstrumentID, direction: Direction) {
+        self.orders.insert(order_id, (instrument, direction));
+    }
+
+    pub(crate) fn forget_order(&mut self, order_id: OrderID) { self.orders.remove(&order_id); }
+
+    pub(crate) fn retain_orders(&mut self, mut is_retained: impl FnMut(OrderID) -> bool) {
+        self.orders.retain(|order_id, _| is_retained(*order_id))
+    }
+
+    pub(crate) fn record_execution(&mut self, order_id: OrderID, size: Size, notional: f64, fee: Fee) {
+        let (position, direction) = match self.orders.get(&order_id) {
+            Some(&(instrument, direction)) => { (self.positions.entry(instrument).or_default(), direction) }
+            None => { return; }
+        };
+        match direction {
+            Direction::Buy => {
+                *position += size.0;
+                self.cash -= notional
+            }
+            Direction::Sell => {
+                *position -= size.0;
+                self.cash += notional
+            }
+        }
+        self.cash -= fee.to_f64()
+    }
+
+    /// Returns whether the order of the given size and direction would take the instrument position beyond the limit
+    pub(crate) fn exceeds_position(&self,
+                                   limits: &RiskLimits,
+                                   instrument: InstrumentID,
+                                   direction: Direction,
+                                   size: Size) -> bool {
+        let position = self.get_position(instrument).0;
+        let position = match direction {
+            Direction::Buy => { position + size.0 }
+            Direction::Sell => { position - size.0 }
+        };
+        limits.max_position.map_or(false, |max_position| position.abs() > max_position.0)
+    }
+
+    /// Releases the kill switch engaged for the session and starts measuring the losses anew.
+    /// `position_value` is the value of the positions in all the instruments
+    pub(crate) fn start_session(&mut self, loss_limits: Option<LossLimits>, position_value: f64) {
+        if let Some(LossLimits { scope: KillSwitchScope::Session, .. }) = loss_limits {
+            self.is_kill_switch_engaged = false;
+            self.scope_start_pnl = self.cash + position_value;
+            self.peak_pnl = self.scope_start_pnl;
+        }
+    }
+
+    /// Engages the kill switch if the profit and loss with the position of the given value breaches the limits
+    pub(crate) fn check_losses(&mut self, loss_limits: Option<LossLimits>, position_value: f64) -> Option<KillSwitchReason> {
+        let limits = loss_limits.filter(|_| !self.is_kill_switch_engaged)?;
+        let pnl = self.cash + position_value;
+        self.peak_pnl = self.peak_pnl.max(pnl);
+        let reason = if limits.max_loss.map_or(false, |max_loss| self.scope_start_pnl - pnl > max_loss) {
+            KillSwitchReason::MaxLoss
+        } else if limits.max_drawdown.map_or(false, |max_drawdown| self.peak_pnl - pnl > max_drawdown) {
+            KillSwitchReason::MaxDrawdown
+        } else {
+            return None;
+        };
+        self.is_kill_switch_engaged = true;
+        Some(reason)
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+
+use crate::message::TraderRequest;
+use crate::types::{DateTime, Duration};
+
+/// At most `max_messages` trader requests are handled within any `window`. Mass-cancel requests count towards
+/// the limit but are never throttled, so that the trader can always pull its orders
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub max_messages: usize,
+    pub window: Duration,
+}
+
+/// What happens to the requests exceeding the rate limits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThrottlePolicy {
+    /// The requests are discarded with the `RateLimited` reason
+    Discard,
+    /// The requests are queued and handled in the order of arrival as soon as the rate limits allow
+    Delay,
+}
+
+/// Trader message counts of the session, reported when the exchange closes
+#[derive(Debug, Default, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+pub struct MessageStatistics {
+    /// Requests handled by the exchange
+    pub messages: u64,
+    /// Requests discarded or delayed by the rate limits
+    pub rate_limited: u64,
+    /// Executions of the trader orders, including the partial ones
+    pub trades: u64,
+}
+
+impl MessageStatistics {
+    /// Messages per trade, none if there have been no trades
+    pub fn get_order_to_trade_ratio(&self) -> Option<f64> {
+        if self.trades == 0 {
+            None
+        } else {
+            Some(self.messages as f64 / self.trades as f64)
+        }
+    }
+}
+
+/// Requests of the trader handled within the rate limit windows and the ones waiting for the rate limits
+#[derive(Default)]
+pub(crate) struct MessageThrottle {
+    /// Requests waiting for the rate limits with the delay policy
+    pub(crate) delayed: VecDeque<TraderRequest>,
+    handled: VecDeque<DateTime>,
+}
+
+impl MessageThrottle {
+    /// Returns the moment the next request can be handled at, or None if it can be handled now
+    pub(crate) fn get_next_slot(&mut self, limits: &[RateLimit], datetime: DateTime) -> Option<DateTime> {
+        let max_window = limits.iter().map(|limit| limit.window).max()?;
+        while matches!(self.handled.front(), Some(dt) if *dt <= datetime - max_window) {
+            self.handled.pop_front();
+        }
+        limits.iter()
+            .filter_map(
+                |limit| {
+                    let in_window = self.handled.iter().rev().take_while(|dt| **dt > datetime - limit.window).count();
+                    if in_window < limit.max_messages {
+                        None
+                    } else {
+                        Some(self.handled[self.handled.len() - limit.max_messages] + limit.window)
+                    }
+                }
+            )
+            .max()
+    }
+
+    pub(crate) fn record(&mut self, limits: &[RateLimit], datetime: DateTime) {
+        if !limits.is_empty() {
+            self.handled.push_back(datetime)
+        }
+    }
+}
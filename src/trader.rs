@@ -6,8 +6,9 @@ pub mod examples;
 pub mod subscriptions;
 
 pub trait Trader: HandleSubscriptionUpdates {
-    fn exchange_to_trader_latency(rng: &mut StdRng, dt: DateTime) -> u64;
-    fn trader_to_exchange_latency(rng: &mut StdRng, dt: DateTime) -> u64;
+    /// Latencies are taken from the trader instance, so the traders hosted by one exchange can differ in them
+    fn exchange_to_trader_latency(&self, rng: &mut StdRng, dt: DateTime) -> u64;
+    fn trader_to_exchange_latency(&self, rng: &mut StdRng, dt: DateTime) -> u64;
     fn handle_exchange_reply(&mut self,
                              exchange_dt: DateTime,
                              delivery_dt: DateTime,
@@ -1,6 +1,8 @@
+use crate::auction::AuctionInfo;
 use crate::exchange::trades::history::OrderBookDiff;
-use crate::message::TraderRequest;
-use crate::types::{DateTime, Price, Size};
+use crate::message::{SubscriptionUpdate, TraderRequest};
+use crate::throttle::MessageStatistics;
+use crate::types::{DateTime, InstrumentID, Price, Size};
 
 pub trait HandleSubscriptionUpdates {
     fn handle_order_book_snapshot(&mut self,
@@ -12,10 +14,61 @@ pub trait HandleSubscriptionUpdates {
                                 delivery_dt: DateTime,
                                 trade_info: Vec<OrderBookDiff>) -> Vec<TraderRequest>;
     fn handle_wakeup(&mut self, dt: DateTime) -> Vec<TraderRequest>;
+    /// Indicative uncrossing published during the auction call phases with the configured interval
+    fn handle_auction_info(&mut self,
+                           _exchange_dt: DateTime,
+                           _delivery_dt: DateTime,
+                           _auction_info: AuctionInfo) -> Vec<TraderRequest> {
+        vec![]
+    }
+    /// Message counts of the session sent when the exchange closes
+    fn handle_message_statistics(&mut self,
+                                 _exchange_dt: DateTime,
+                                 _delivery_dt: DateTime,
+                                 _statistics: MessageStatistics) {}
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub struct OrderBookSnapshot {
+    pub instrument: InstrumentID,
     pub bids: Vec<(Price, Size)>,
     pub asks: Vec<(Price, Size)>,
-}
\ No newline at end of file
+}
+/// Market data of the instrument sent to the trader with the subscriptions configured for the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstrumentSubscriptions {
+    pub order_book: bool,
+    /// Depth of the order book snapshots, or None for the depth configured for the exchange
+    pub ob_depth: Option<usize>,
+    pub trade_info: bool,
+}
+
+impl Default for InstrumentSubscriptions {
+    fn default() -> Self { InstrumentSubscriptions { order_book: true, ob_depth: None, trade_info: true } }
+}
+
+/// Updates the trader hosted by the exchange receives. The update intervals are configured for the exchange
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraderSubscriptions {
+    pub order_book: bool,
+    pub trade_info: bool,
+    pub auction_info: bool,
+    pub wakeup: bool,
+}
+
+impl Default for TraderSubscriptions {
+    fn default() -> Self { TraderSubscriptions { order_book: true, trade_info: true, auction_info: true, wakeup: true } }
+}
+
+impl TraderSubscriptions {
+    pub(crate) fn is_subscribed(&self, update: &SubscriptionUpdate) -> bool {
+        match update {
+            SubscriptionUpdate::OrderBook(_) => { self.order_book }
+            SubscriptionUpdate::TradeInfo(_) => { self.trade_info }
+            SubscriptionUpdate::AuctionInfo(_) => { self.auction_info }
+            SubscriptionUpdate::ExchangeOpen
+            | SubscriptionUpdate::MessageStatistics(_)
+            | SubscriptionUpdate::ExchangeClosed => { true }
+        }
+    }
+}
@@ -13,6 +13,18 @@ pub struct Price(pub i64);
 #[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy, Add, Sub, AddAssign, SubAssign)]
 pub struct OrderID(pub u64);
 
+/// Identifier of the traded instrument. Single-instrument runs trade the default `InstrumentID(0)`
+#[derive(Debug, Default, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+pub struct InstrumentID(pub u64);
+
+/// Position of the trader among the ones hosted by the exchange. Single-trader runs host `TraderID(0)` only
+#[derive(Debug, Default, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+pub struct TraderID(pub usize);
+
+/// Exchange of the multi-venue simulation the trader is connected to
+#[derive(Debug, Default, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+pub struct VenueID(pub u64);
+
 #[derive(Debug, Default, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy, Add, Sum, Sub, AddAssign, SubAssign)]
 pub struct Size(pub i64);
 
@@ -22,10 +34,19 @@ pub enum Direction {
     Sell,
 }
 
+/// Value of `Price(1)`, given either directly or by the instrument specification
+pub trait PriceStep {
+    fn get_price_step(&self) -> f64;
+}
+
+impl PriceStep for f64 {
+    fn get_price_step(&self) -> f64 { *self }
+}
+
 impl Price
 {
     pub
-    fn from_decimal_str(string: &str, price_step: f64) -> Self
+    fn from_decimal_str<S: PriceStep>(string: &str, price_step: S) -> Self
     {
         let parsed_f64 = f64::from_str(string).expect_with(
             || format!("Cannot parse to f64: {}", string)
@@ -34,7 +55,8 @@ impl Price
     }
 
     pub
-    fn from_f64(value: f64, price_step: f64) -> Self {
+    fn from_f64<S: PriceStep>(value: f64, price_step: S) -> Self {
+        let price_step = price_step.get_price_step();
         let price_steps = value / price_step;
         let rounded_price_steps = price_steps.round();
         if (rounded_price_steps - price_steps).abs() > 10e-12 {
@@ -49,8 +71,8 @@ impl Price
     }
 
     pub
-    fn to_f64(&self, price_step: f64) -> f64 {
-        self.0 as f64 * price_step
+    fn to_f64<S: PriceStep>(&self, price_step: S) -> f64 {
+        self.0 as f64 * price_step.get_price_step()
     }
 }
 
@@ -0,0 +1,249 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::auction::AuctionInfo;
+use crate::exchange::trades::history::OrderBookDiff;
+use crate::message::{DiscardingReason, ExchangeReply, InabilityToCancelReason, InabilityToModifyReason, TraderRequest};
+use crate::order::Order;
+use crate::throttle::MessageStatistics;
+use crate::trader::{subscriptions::{HandleSubscriptionUpdates, OrderBookSnapshot}, Trader};
+use crate::types::{DateTime, StdRng, TraderID, VenueID};
+
+/// Trader connected to several venues. The messages come with the venue they have been sent by, and the requests
+/// go with the venue they are sent to
+pub trait MultiVenueTrader {
+    /// Latencies between the trader and the venue
+    fn exchange_to_trader_latency(&self, venue: VenueID, rng: &mut StdRng, dt: DateTime) -> u64;
+    fn trader_to_exchange_latency(&self, venue: VenueID, rng: &mut StdRng, dt: DateTime) -> u64;
+    fn handle_exchange_reply(&mut self,
+                             venue: VenueID,
+                             exchange_dt: DateTime,
+                             delivery_dt: DateTime,
+                             reply: ExchangeReply) -> Vec<(VenueID, TraderRequest)>;
+    fn handle_order_book_snapshot(&mut self,
+                                  venue: VenueID,
+                                  exchange_dt: DateTime,
+                                  delivery_dt: DateTime,
+                                  ob_snapshot: OrderBookSnapshot) -> Vec<(VenueID, TraderRequest)>;
+    fn handle_trade_info_update(&mut self,
+                                venue: VenueID,
+                                exchange_dt: DateTime,
+                                delivery_dt: DateTime,
+                                trade_info: Vec<OrderBookDiff>) -> Vec<(VenueID, TraderRequest)>;
+    fn handle_wakeup(&mut self, venue: VenueID, dt: DateTime) -> Vec<(VenueID, TraderRequest)>;
+    fn handle_auction_info(&mut self,
+                           _venue: VenueID,
+                           _exchange_dt: DateTime,
+                           _delivery_dt: DateTime,
+                           _auction_info: AuctionInfo) -> Vec<(VenueID, TraderRequest)> {
+        vec![]
+    }
+    fn handle_message_statistics(&mut self,
+                                 _venue: VenueID,
+                                 _exchange_dt: DateTime,
+                                 _delivery_dt: DateTime,
+                                 _statistics: MessageStatistics) {}
+    fn exchange_open(&mut self, venue: VenueID, exchange_dt: DateTime, delivery_dt: DateTime);
+    fn exchange_closed(&mut self, venue: VenueID, exchange_dt: DateTime, delivery_dt: DateTime);
+}
+
+/// Multi-venue trader shared by the venues along with the requests it has sent to the venues other than
+/// the one whose message it has been handling
+pub struct VenueConnections<'a, T: MultiVenueTrader + ?Sized> {
+    trader: RefCell<&'a mut T>,
+    forwarded_requests: RefCell<Vec<(VenueID, TraderRequest, DateTime)>>,
+}
+
+impl<'a, T: MultiVenueTrader + ?Sized> VenueConnections<'a, T> {
+    pub fn new(trader: &'a mut T) -> Self {
+        VenueConnections { trader: RefCell::new(trader), forwarded_requests: Default::default() }
+    }
+
+    /// Trader the exchange of the venue is to be built with
+    pub fn link(&self, venue: VenueID) -> VenueLink<'_, 'a, T> {
+        VenueLink { venue, connections: self }
+    }
+}
+
+/// Connection of the multi-venue trader to the venue, hosted by the exchange of the venue as its trader
+pub struct VenueLink<'c, 'a, T: MultiVenueTrader + ?Sized> {
+    venue: VenueID,
+    connections: &'c VenueConnections<'a, T>,
+}
+
+impl<T: MultiVenueTrader + ?Sized> VenueLink<'_, '_, T> {
+    /// Returns the requests to the venue of the link and puts aside the ones to the other venues
+    fn route(&self, requests: Vec<(VenueID, TraderRequest)>, dt: DateTime) -> Vec<TraderRequest> {
+        let mut forwarded_requests = self.connections.forwarded_requests.borrow_mut();
+        let mut own_requests = vec![];
+        for (venue, request) in requests {
+            if venue == self.venue {
+                own_requests.push(request)
+            } else {
+                forwarded_requests.push((venue, request, dt))
+            }
+        }
+        own_requests
+    }
+}
+
+impl<T: MultiVenueTrader + ?Sized> HandleSubscriptionUpdates for VenueLink<'_, '_, T> {
+    fn handle_order_book_snapshot(&mut self,
+                                  exchange_dt: DateTime,
+                                  delivery_dt: DateTime,
+                                  ob_snapshot: OrderBookSnapshot) -> Vec<TraderRequest> {
+        let requests = self.connections.trader.borrow_mut()
+            .handle_order_book_snapshot(self.venue, exchange_dt, delivery_dt, ob_snapshot);
+        self.route(requests, delivery_dt)
+    }
+    fn handle_trade_info_update(&mut self,
+                                exchange_dt: DateTime,
+                                delivery_dt: DateTime,
+                                trade_info: Vec<OrderBookDiff>) -> Vec<TraderRequest> {
+        let requests = self.connections.trader.borrow_mut()
+            .handle_trade_info_update(self.venue, exchange_dt, delivery_dt, trade_info);
+        self.route(requests, delivery_dt)
+    }
+    fn handle_wakeup(&mut self, dt: DateTime) -> Vec<TraderRequest> {
+        let requests = self.connections.trader.borrow_mut().handle_wakeup(self.venue, dt);
+        self.route(requests, dt)
+    }
+    fn handle_auction_info(&mut self,
+                           exchange_dt: DateTime,
+                           delivery_dt: DateTime,
+                           auction_info: AuctionInfo) -> Vec<TraderRequest> {
+        let requests = self.connections.trader.borrow_mut()
+            .handle_auction_info(self.venue, exchange_dt, delivery_dt, auction_info);
+        self.route(requests, delivery_dt)
+    }
+    fn handle_message_statistics(&mut self, exchange_dt: DateTime, delivery_dt: DateTime, statistics: MessageStatistics) {
+        self.connections.trader.borrow_mut().handle_message_statistics(self.venue, exchange_dt, delivery_dt, statistics)
+    }
+}
+
+impl<T: MultiVenueTrader + ?Sized> Trader for VenueLink<'_, '_, T> {
+    fn exchange_to_trader_latency(&self, rng: &mut StdRng, dt: DateTime) -> u64 {
+        self.connections.trader.borrow().exchange_to_trader_latency(self.venue, rng, dt)
+    }
+    fn trader_to_exchange_latency(&self, rng: &mut StdRng, dt: DateTime) -> u64 {
+        self.connections.trader.borrow().trader_to_exchange_latency(self.venue, rng, dt)
+    }
+    fn handle_exchange_reply(&mut self,
+                             exchange_dt: DateTime,
+                             delivery_dt: DateTime,
+                             reply: ExchangeReply) -> Vec<TraderRequest> {
+        let requests = self.connections.trader.borrow_mut()
+            .handle_exchange_reply(self.venue, exchange_dt, delivery_dt, reply);
+        self.route(requests, delivery_dt)
+    }
+    fn exchange_open(&mut self, exchange_dt: DateTime, delivery_dt: DateTime) {
+        self.connections.trader.borrow_mut().exchange_open(self.venue, exchange_dt, delivery_dt)
+    }
+    fn exchange_closed(&mut self, exchange_dt: DateTime, delivery_dt: DateTime) {
+        self.connections.trader.borrow_mut().exchange_closed(self.venue, exchange_dt, delivery_dt)
+    }
+}
+
+/// Exchange trading as a venue of the multi-venue simulation
+pub trait Venue {
+    /// Schedules the first session of the exchange
+    fn start_trading(&mut self);
+    fn get_next_event_dt(&self) -> Option<DateTime>;
+    /// Processes the next event of the exchange. Returns whether the exchange keeps trading
+    fn advance(&mut self) -> bool;
+    /// Schedules the arrival of the requests the hosted trader has sent at the given moment
+    fn send_trader_requests(&mut self, trader: TraderID, requests: Vec<TraderRequest>, dt: DateTime);
+}
+
+/// Venues trading off their own histories under one event clock, with the trader connected to all of them
+pub struct MultiVenue<'v, 'a, T: MultiVenueTrader + ?Sized> {
+    connections: &'v VenueConnections<'a, T>,
+    /// Exchanges of the venues along with the IDs they host the links of the venues with
+    venues: BTreeMap<VenueID, (TraderID, Box<dyn Venue + 'v>)>,
+}
+
+impl<'v, 'a, T: MultiVenueTrader + ?Sized> MultiVenue<'v, 'a, T> {
+    pub fn new(connections: &'v VenueConnections<'a, T>) -> Self {
+        MultiVenue { connections, venues: Default::default() }
+    }
+
+    /// Adds the venue, whose exchange hosts the link of the venue as the trader with the given ID
+    pub fn with_venue<V: Venue + 'v>(mut self, venue: VenueID, trader: TraderID, exchange: V) -> Self {
+        self.venues.insert(venue, (trader, Box::new(exchange)));
+        self
+    }
+
+    /// Events of the same moment are processed in the order of the venue IDs. The requests to the venues
+    /// that have finished trading or have not been added are rejected as if the exchange was closed
+    pub fn run_trades(&mut self) {
+        for (_, exchange) in self.venues.values_mut() {
+            exchange.start_trading()
+        }
+        let mut trading_venues: BTreeSet<VenueID> = self.venues.keys().copied().collect();
+        loop {
+            let next_venue = trading_venues.iter()
+                .filter_map(|venue| Some((self.venues[venue].1.get_next_event_dt()?, *venue)))
+                .min()
+                .map(|(_, venue)| venue);
+            let venue = match next_venue {
+                Some(venue) => { venue }
+                None => { return; }
+            };
+            if !self.venues.get_mut(&venue).unwrap().1.advance() {
+                trading_venues.remove(&venue);
+            }
+            loop {
+                let forwarded_requests = std::mem::take(&mut *self.connections.forwarded_requests.borrow_mut());
+                if forwarded_requests.is_empty() {
+                    break;
+                }
+                for (venue, request, dt) in forwarded_requests {
+                    match self.venues.get_mut(&venue) {
+                        Some((trader, exchange)) if trading_venues.contains(&venue) => {
+                            exchange.send_trader_requests(*trader, vec![request], dt)
+                        }
+                        _ => { self.reject_request(venue, request, dt) }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replies to the request at once, since there is no exchange to handle it. The requests the trader sends
+    /// in response are forwarded as usual
+    fn reject_request(&self, venue: VenueID, request: TraderRequest, dt: DateTime) {
+        let mut trader = self.connections.trader.borrow_mut();
+        for reply in get_closed_venue_replies(request) {
+            let requests = trader.handle_exchange_reply(venue, dt, dt, reply);
+            self.connections.forwarded_requests.borrow_mut().extend(
+                requests.into_iter().map(|(venue, request)| (venue, request, dt))
+            )
+        }
+    }
+}
+
+fn get_closed_venue_replies(request: TraderRequest) -> Vec<ExchangeReply> {
+    let discard = |order_id| ExchangeReply::OrderPlacementDiscarded(order_id, DiscardingReason::ExchangeClosed);
+    match request {
+        TraderRequest::PlaceLimitOrder(order) => { vec![discard(order.get_order_id())] }
+        TraderRequest::PlaceMarketOrder(order) => { vec![discard(order.get_order_id())] }
+        TraderRequest::PlaceStopOrder(order) => { vec![discard(order.get_order_id())] }
+        TraderRequest::PlaceOcoOrders(first, second) => {
+            vec![discard(first.get_order_id()), discard(second.get_order_id())]
+        }
+        TraderRequest::PlaceBracketOrder(parent, take_profit, stop_loss) => {
+            [parent, take_profit, stop_loss].iter().map(|order| discard(order.get_order_id())).collect()
+        }
+        TraderRequest::CancelLimitOrder(order_id)
+        | TraderRequest::CancelMarketOrder(order_id)
+        | TraderRequest::CancelStopOrder(order_id) => {
+            vec![ExchangeReply::CannotCancelOrder(order_id, InabilityToCancelReason::ExchangeClosed)]
+        }
+        TraderRequest::ModifyLimitOrder(order_id, ..) => {
+            vec![ExchangeReply::CannotModifyOrder(order_id, InabilityToModifyReason::ExchangeClosed)]
+        }
+        TraderRequest::CancelAll | TraderRequest::CancelAllOnSide(_) | TraderRequest::CancelInPriceRange(..) => {
+            vec![ExchangeReply::MassCancelled(0)]
+        }
+    }
+}
@@ -0,0 +1,207 @@
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use exchange_backtester::prelude::*;
+
+#[derive(Default)]
+pub struct HistoryHolder(VecDeque<HistoryEvent>, InstrumentID);
+
+impl HistoryHolder {
+    /// Events added next belong to the instrument
+    pub fn for_instrument(mut self, instrument: u64) -> Self {
+        self.1 = InstrumentID(instrument);
+        self
+    }
+
+    pub fn add_ob_diff(mut self, time: &str, size: i64, direction: Direction, price: i64, order_id: u64) -> Self {
+        self.0.push_back(HistoryEvent {
+            datetime: parse_dt(time),
+            instrument: self.1,
+            event: HistoryEventBody::OrderBookDiff(Size(size), direction, Price(price), OrderID(order_id)),
+        });
+        self
+    }
+
+    pub fn add_trade(mut self, time: &str, size: i64, direction: Direction) -> Self {
+        self.0.push_back(HistoryEvent {
+            datetime: parse_dt(time),
+            instrument: self.1,
+            event: HistoryEventBody::Trade(Size(size), direction),
+        });
+        self
+    }
+}
+
+impl EventProcessor for HistoryHolder {
+    fn yield_next_event(&mut self) -> Option<HistoryEvent> { self.0.pop_front() }
+}
+
+pub struct ScriptedTrader {
+    pub script: VecDeque<(DateTime, TraderRequest)>,
+    pub replies: Vec<ExchangeReply>,
+    pub ob_snapshots: Vec<(DateTime, OrderBookSnapshot)>,
+    pub auction_infos: Vec<(DateTime, AuctionInfo)>,
+    pub message_statistics: Vec<MessageStatistics>,
+}
+
+impl ScriptedTrader {
+    pub fn new(script: Vec<(&str, TraderRequest)>) -> Self {
+        ScriptedTrader {
+            script: script.into_iter().map(|(time, request)| (parse_dt(time), request)).collect(),
+            replies: vec![],
+            ob_snapshots: vec![],
+            auction_infos: vec![],
+            message_statistics: vec![],
+        }
+    }
+}
+
+impl HandleSubscriptionUpdates for ScriptedTrader {
+    fn handle_order_book_snapshot(&mut self,
+                                  exchange_dt: DateTime,
+                                  _: DateTime,
+                                  ob_snapshot: OrderBookSnapshot) -> Vec<TraderRequest> {
+        self.ob_snapshots.push((exchange_dt, ob_snapshot));
+        vec![]
+    }
+    fn handle_trade_info_update(&mut self, _: DateTime, _: DateTime, _: Vec<OrderBookDiff>) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_wakeup(&mut self, dt: DateTime) -> Vec<TraderRequest> {
+        let mut requests = vec![];
+        while matches!(self.script.front(), Some((request_dt, _)) if *request_dt <= dt) {
+            requests.push(self.script.pop_front().unwrap().1)
+        }
+        requests
+    }
+    fn handle_auction_info(&mut self,
+                           exchange_dt: DateTime,
+                           _: DateTime,
+                           auction_info: AuctionInfo) -> Vec<TraderRequest> {
+        self.auction_infos.push((exchange_dt, auction_info));
+        vec![]
+    }
+    fn handle_message_statistics(&mut self, _: DateTime, _: DateTime, statistics: MessageStatistics) {
+        self.message_statistics.push(statistics)
+    }
+}
+
+impl Trader for ScriptedTrader {
+    fn exchange_to_trader_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn trader_to_exchange_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn handle_exchange_reply(&mut self, _: DateTime, _: DateTime, reply: ExchangeReply) -> Vec<TraderRequest> {
+        self.replies.push(reply);
+        vec![]
+    }
+    fn exchange_open(&mut self, _: DateTime, _: DateTime) {}
+    fn exchange_closed(&mut self, _: DateTime, _: DateTime) {}
+}
+
+/// Sends the scripted requests on the wakeups of any venue. Venue 1 is one second away from the trader
+pub struct ScriptedVenueTrader {
+    pub script: VecDeque<(DateTime, VenueID, TraderRequest)>,
+    pub replies: Vec<(VenueID, DateTime, ExchangeReply)>,
+}
+
+impl ScriptedVenueTrader {
+    pub fn new(script: Vec<(&str, VenueID, TraderRequest)>) -> Self {
+        ScriptedVenueTrader {
+            script: script.into_iter().map(|(time, venue, request)| (parse_dt(time), venue, request)).collect(),
+            replies: vec![],
+        }
+    }
+}
+
+impl MultiVenueTrader for ScriptedVenueTrader {
+    fn exchange_to_trader_latency(&self, venue: VenueID, _: &mut StdRng, _: DateTime) -> u64 {
+        if venue == VenueID(1) { 1_000_000_000 } else { 0 }
+    }
+    fn trader_to_exchange_latency(&self, venue: VenueID, _: &mut StdRng, _: DateTime) -> u64 {
+        if venue == VenueID(1) { 1_000_000_000 } else { 0 }
+    }
+    fn handle_exchange_reply(&mut self,
+                             venue: VenueID,
+                             _: DateTime,
+                             delivery_dt: DateTime,
+                             reply: ExchangeReply) -> Vec<(VenueID, TraderRequest)> {
+        self.replies.push((venue, delivery_dt, reply));
+        vec![]
+    }
+    fn handle_order_book_snapshot(&mut self,
+                                  _: VenueID,
+                                  _: DateTime,
+                                  _: DateTime,
+                                  _: OrderBookSnapshot) -> Vec<(VenueID, TraderRequest)> {
+        vec![]
+    }
+    fn handle_trade_info_update(&mut self,
+                                _: VenueID,
+                                _: DateTime,
+                                _: DateTime,
+                                _: Vec<OrderBookDiff>) -> Vec<(VenueID, TraderRequest)> {
+        vec![]
+    }
+    fn handle_wakeup(&mut self, _: VenueID, dt: DateTime) -> Vec<(VenueID, TraderRequest)> {
+        let mut requests = vec![];
+        while matches!(self.script.front(), Some((request_dt, ..)) if *request_dt <= dt) {
+            let (_, venue, request) = self.script.pop_front().unwrap();
+            requests.push((venue, request))
+        }
+        requests
+    }
+    fn exchange_open(&mut self, _: VenueID, _: DateTime, _: DateTime) {}
+    fn exchange_closed(&mut self, _: VenueID, _: DateTime, _: DateTime) {}
+}
+
+pub fn parse_dt(time: &str) -> DateTime {
+    if time.contains(' ') {
+        DateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").unwrap()
+    } else {
+        DateTime::parse_from_str(&format!("2021-06-01 {}", time), "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+}
+
+pub type ScriptedExchange<'a, M = matching::fifo::Fifo, Q = queue_position::models::Exact, S = fees::schedules::NoFees> = Exchange<
+    'a, ScriptedTrader, HistoryHolder,
+    lags::constant::ConstNanoSecondGenerator, VoidNanoSecGen, lags::constant::ConstNanoSecondGenerator, M, Q, S,
+    false, true, true, false, true
+>;
+
+pub fn run_scripted(history: HistoryHolder, trader: &mut ScriptedTrader) {
+    run_scripted_with(history, trader, |exchange| exchange)
+}
+
+pub fn run_scripted_with<F, M, Q, S>(history: HistoryHolder, trader: &mut ScriptedTrader, configure: F)
+    where F: FnOnce(ScriptedExchange) -> ScriptedExchange<M, Q, S>,
+          M: MatchingPolicy,
+          Q: QueuePositionModel,
+          S: FeeSchedule
+{
+    run_built_with(ExchangeBuilder::with_market_order_policy(MarketOrderPolicy::KeepPending), history, trader, configure)
+}
+
+pub fn run_scripted_with_policy(history: HistoryHolder, trader: &mut ScriptedTrader, policy: MarketOrderPolicy) {
+    run_built_with(ExchangeBuilder::with_market_order_policy(policy), history, trader, |exchange| exchange)
+}
+
+fn run_built_with<F, M, Q, S>(builder: ExchangeBuilder<ScriptedTrader, HistoryHolder>,
+                              history: HistoryHolder,
+                              trader: &mut ScriptedTrader,
+                              configure: F)
+    where F: FnOnce(ScriptedExchange) -> ScriptedExchange<M, Q, S>,
+          M: MatchingPolicy,
+          Q: QueuePositionModel,
+          S: FeeSchedule
+{
+    let get_next_open_dt = |datetime: DateTime| {
+        datetime.date().and_hms(10, 0, 0)
+    };
+    let get_next_close_dt = |datetime: DateTime| {
+        datetime.date().and_hms(18, 0, 0)
+    };
+    let exchange = builder.build::<true>(history, trader, get_next_open_dt, get_next_close_dt)
+        .ob_level_subscription_depth(lags::constant::ONE_MINUTE, 1)
+        .with_periodic_wakeup(lags::constant::ONE_SECOND);
+    configure(exchange).run_trades()
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn instrument_spec() {
+    let spec = InstrumentSpec::new(0.01)
+        .with_tick_table(vec![(Price(0), 1), (Price(100), 5)]).unwrap()
+        .with_lot_size(Size(10)).unwrap()
+        .with_min_order_size(Size(20))
+        .with_max_order_size(Size(100))
+        .with_multiplier(10.0)
+        .with_currency("USD");
+    assert_eq!(Price::from_decimal_str("1.05", &spec), Price(105));
+    assert_eq!(Price(105).to_f64(&spec), 1.05);
+    assert_eq!(spec.get_notional(Price(105), Size(20)), 210.0);
+    assert_eq!(InstrumentSpec::new(0.01).with_lot_size(Size(0)), Err(InvalidSpec::NonPositiveLotSize(Size(0))));
+
+    // Symbols without their own price step use the input one
+    let input = StaticInput::new().with_symbols("AAPL:0.01,ES").with_price_step(0.25);
+    assert_eq!(InstrumentSpec::from_input(&input, InstrumentID(0)), InstrumentSpec::new(0.01));
+    assert_eq!(InstrumentSpec::from_input(&input, InstrumentID(1)), InstrumentSpec::new(0.25));
+
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 100, Direction::Buy, 90, 1)
+        .add_ob_diff("10:00:00", 100, Direction::Sell, 110, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(20), Direction::Buy, Price(102)))),
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(25), Direction::Buy, Price(95)))),
+        ("10:00:03", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(10), Direction::Buy, Price(95)))),
+        ("10:00:04", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(4), Size(110), Direction::Buy))),
+        ("10:00:05", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(5), Size(20), Direction::Buy, Price(105)))),
+        ("10:00:06", TraderRequest::ModifyLimitOrder(OrderID(5), Price(103), Size(20))),
+        ("10:00:07", TraderRequest::ModifyLimitOrder(OrderID(5), Price(99), Size(20))),
+    ]);
+    run_scripted_with(history, &mut trader, |exchange| exchange.with_instrument_spec(spec));
+    assert_eq!(
+        trader.replies,
+        vec![
+            // The tick is 5 price steps from 100
+            ExchangeReply::OrderPlacementDiscarded(OrderID(1), DiscardingReason::OffTickPrice),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(2), DiscardingReason::InvalidLotSize),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::SizeTooSmall),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(4), DiscardingReason::SizeTooLarge),
+            ExchangeReply::OrderAccepted(OrderID(5)),
+            ExchangeReply::CannotModifyOrder(OrderID(5), InabilityToModifyReason::OffTickPrice),
+            ExchangeReply::OrderModified(OrderID(5), Price(99), Size(20)),
+            ExchangeReply::OrderCancelled(OrderID(5), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn tick_aligned_prices() {
+    let spec = InstrumentSpec::new(0.01)
+        .with_tick_table(vec![(Price(0), 1), (Price(100), 5)]).unwrap();
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 115, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(1), Direction::Buy, Price(120)).with_post_only(PostOnlyMode::Reprice)
+            )
+        ),
+        (
+            "10:00:02",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(2), Size(1), Direction::Sell, Price(100)).with_post_only(PostOnlyMode::Reprice)
+            )
+        ),
+        (
+            "10:00:03",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(100)).with_peg(PegReference::Midpoint, 0)
+            )
+        ),
+        (
+            "10:00:04",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(4), Size(1), Direction::Sell, Price(125)).with_peg(PegReference::Primary, 1)
+            )
+        ),
+    ]);
+    run_scripted_with(history, &mut trader, |exchange| exchange.with_instrument_spec(spec));
+    assert_eq!(
+        trader.replies,
+        vec![
+            // The tick is 5 price steps from 100
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(110)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderRepriced(OrderID(2), Price(115)),
+            // The midpoint 112.5 is rounded away from the opposite side
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderRepriced(OrderID(3), Price(110)),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderRepriced(OrderID(4), Price(120)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(4), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn multiple_instruments() {
+    // History order IDs are unique within the instrument only
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .for_instrument(1)
+        .add_ob_diff("10:00:00", 5, Direction::Buy, 50, 1)
+        .add_ob_diff("10:00:00", 5, Direction::Sell, 52, 2)
+        .add_ob_diff("10:00:00", 4, Direction::Sell, 53, 3)
+        .add_trade("10:00:30", 2, Direction::Sell);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(3), Direction::Buy).with_instrument(InstrumentID(1)))),
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(2), Direction::Buy, Price(101)))),
+        ("10:00:03", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(10)).with_instrument(InstrumentID(7)))),
+        ("10:00:04", TraderRequest::CancelLimitOrder(OrderID(2))),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange
+            .with_instrument(InstrumentID(1), InstrumentSpec::default())
+            .with_instrument_subscriptions(InstrumentID(0), InstrumentSubscriptions { order_book: false, ..Default::default() })
+            .with_instrument_subscriptions(InstrumentID(1), InstrumentSubscriptions { ob_depth: Some(2), ..Default::default() }),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(3), Price(52), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::UnknownInstrument),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::TraderRequested),
+        ]
+    );
+    assert_eq!(
+        trader.ob_snapshots[0],
+        (
+            parse_dt("10:01:00"),
+            OrderBookSnapshot {
+                instrument: InstrumentID(1),
+                bids: vec![(Price(50), Size(3))],
+                asks: vec![(Price(52), Size(2)), (Price(53), Size(4))],
+            }
+        )
+    );
+    assert!(trader.ob_snapshots.iter().all(|(_, snapshot)| snapshot.instrument == InstrumentID(1)));
+}
@@ -0,0 +1,273 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn matching_policies() {
+    fn run_with<M: MatchingPolicy>(policy: M) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+            .add_ob_diff("10:00:02", 30, Direction::Sell, 105, 3)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(10), Direction::Sell, Price(105)))),
+            ("10:00:03", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(2), Size(20), Direction::Buy))),
+        ]);
+        run_scripted_with(history, &mut trader, |exchange| exchange.with_matching_policy(policy));
+        trader.replies
+    }
+
+    assert_eq!(
+        run_with(matching::fifo::Fifo),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(10), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(10), Price(105), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(10), Price(105), Liquidity::Removed, Fee(0)),
+        ]
+    );
+    assert_eq!(
+        run_with(matching::pro_rata::ProRata { min_allocation: Size(2) }),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(4), Price(105), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(4), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(4), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(12), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    assert_eq!(
+        run_with(matching::pro_rata::ProRata { min_allocation: Size(5) }),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(8), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(12), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    assert_eq!(
+        run_with(matching::pro_rata::TopOrderProRata { min_allocation: Size(2) }),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(105), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(3), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(10), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(7), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn queue_position_models() {
+    fn run_with<Q: QueuePositionModel>(model: Q) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+            .add_ob_diff("10:00:02", 10, Direction::Sell, 105, 3)
+            // Cancel of the history order ahead of the trader one
+            .add_ob_diff("10:00:03", 0, Direction::Sell, 105, 2)
+            .add_trade("10:00:04", 8, Direction::Buy)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Sell, Price(105)))),
+        ]);
+        run_scripted_with(history, &mut trader, |exchange| exchange.with_queue_position_model(model));
+        trader.replies
+    }
+
+    assert_eq!(
+        run_with(queue_position::models::Exact),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(5), Price(105), Liquidity::Added, Fee(0)),
+        ]
+    );
+    assert_eq!(
+        run_with(queue_position::models::Pessimistic),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    // Half of the cancel is ahead, so the trader order stands in the middle of the history order 3
+    assert_eq!(
+        run_with(queue_position::models::ProportionalCancel),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(105), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn queue_position_of_fills() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:02", 10, Direction::Sell, 105, 3)
+        // The trade decreases the history order ahead of the trader one at its next order book diff
+        .add_trade("10:00:03", 4, Direction::Buy)
+        .add_ob_diff("10:00:03", 6, Direction::Sell, 105, 2)
+        .add_trade("10:00:04", 8, Direction::Buy)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Sell, Price(105)))),
+    ]);
+    let get_next_open_dt = |datetime: DateTime| {
+        datetime.date().and_hms(10, 0, 0)
+    };
+    let get_next_close_dt = |datetime: DateTime| {
+        datetime.date().and_hms(18, 0, 0)
+    };
+    // The pessimistic model would take the decrease for a cancel behind the trader order
+    ExchangeBuilder::new::<false>(history, &mut trader, get_next_open_dt, get_next_close_dt)
+        .with_periodic_wakeup(lags::constant::ONE_SECOND)
+        .with_queue_position_model(queue_position::models::Pessimistic)
+        .run_trades();
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(2), Price(105), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn market_impact() {
+    fn run_with(impact: Option<MarketImpact>) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+            .add_ob_diff("10:00:00", 100, Direction::Sell, 110, 3)
+            .add_ob_diff("10:00:05", 8, Direction::Sell, 105, 2)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(6), Direction::Buy))),
+            ("10:00:06", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(2), Size(10), Direction::Buy))),
+        ]);
+        run_scripted_with(
+            history,
+            &mut trader,
+            |exchange| match impact {
+                Some(impact) => { exchange.with_market_impact(impact) }
+                None => { exchange }
+            },
+        );
+        trader.replies
+    }
+    let replies = |size_at_105: i64| vec![
+        ExchangeReply::OrderAccepted(OrderID(1)),
+        ExchangeReply::OrderExecuted(OrderID(1), Size(6), Price(105), Liquidity::Removed, Fee(0)),
+        ExchangeReply::OrderAccepted(OrderID(2)),
+        ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(size_at_105), Price(105), Liquidity::Removed, Fee(0)),
+        ExchangeReply::OrderExecuted(OrderID(2), Size(10 - size_at_105), Price(110), Liquidity::Removed, Fee(0)),
+    ];
+
+    // The history order 2 is restored in full by the diff
+    assert_eq!(run_with(None), replies(8));
+    assert_eq!(run_with(Some(MarketImpact::Permanent)), replies(2));
+    assert_eq!(run_with(Some(MarketImpact::TransientDecay { half_life: Duration::seconds(4) })), replies(5));
+    assert_eq!(
+        run_with(Some(MarketImpact::Resiliency { refill_size: Size(2), refill_interval: Duration::seconds(1) })),
+        replies(8)
+    );
+    assert_eq!(
+        run_with(Some(MarketImpact::Resiliency { refill_size: Size(1), refill_interval: Duration::seconds(1) })),
+        replies(6)
+    );
+
+    // The history order 2 moves to 106, and the consumed volume follows it there
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:00", 100, Direction::Sell, 110, 3)
+        .add_ob_diff("10:00:05", 8, Direction::Sell, 106, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(6), Direction::Buy))),
+        ("10:00:06", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(2), Size(10), Direction::Buy))),
+    ]);
+    run_scripted_with(history, &mut trader, |exchange| exchange.with_market_impact(MarketImpact::Permanent));
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(6), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(2), Price(106), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(8), Price(110), Liquidity::Removed, Fee(0)),
+        ]
+    );
+}
+
+#[test]
+fn fees() {
+    fn run_with<S: FeeSchedule>(schedule: S) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 2, Direction::Sell, 105, 2)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 4)
+            .add_trade("10:00:10", 5, Direction::Sell)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 110, 3);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(104)))),
+            ("10:00:20", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(2), Size(3), Direction::Buy))),
+        ]);
+        run_scripted_with(
+            history,
+            &mut trader,
+            |exchange| exchange
+                .with_instrument_spec(InstrumentSpec::new(0.01))
+                .with_fee_schedule(schedule),
+        );
+        trader.replies
+    }
+
+    // Maker rebate per share; taker fee of 3 bps of 2.10 is raised to the minimum fee of the order,
+    // which covers the fee of the second execution as well
+    let maker_taker = fees::schedules::MakerTaker {
+        maker: fees::schedules::FeeRate::PerShare(-0.002),
+        taker: fees::schedules::FeeRate::Bps(3.0),
+        min_fee: 0.01,
+    };
+    assert_eq!(
+        run_with(maker_taker),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(5), Price(104), Liquidity::Added, Fee(-10_000)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(2), Price(105), Liquidity::Removed, Fee(10_000)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(1), Price(105), Liquidity::Removed, Fee(0)),
+        ]
+    );
+
+    // The second execution falls into the tier of the volume executed before it
+    let tier = |taker| fees::schedules::MakerTaker {
+        maker: fees::schedules::FeeRate::PerShare(0.0),
+        taker: fees::schedules::FeeRate::PerShare(taker),
+        min_fee: 0.0,
+    };
+    let tiered = fees::schedules::Tiered::new(vec![(Size(5), tier(0.001)), (Size(0), tier(0.003))]);
+    assert_eq!(
+        run_with(tiered),
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(5), Price(104), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(2), Size(2), Price(105), Liquidity::Removed, Fee(2_000)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(1), Price(105), Liquidity::Removed, Fee(1_000)),
+        ]
+    );
+}
@@ -0,0 +1,636 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn modify_limit_order() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:05", 4, Direction::Buy, 100, 3)
+        .add_trade("10:00:10", 12, Direction::Sell)
+        .add_trade("10:00:20", 5, Direction::Sell)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 110, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(100)))),
+        // Size decrease keeps the place ahead of the history order 3
+        ("10:00:02", TraderRequest::ModifyLimitOrder(OrderID(1), Price(100), Size(3))),
+        // Size increase moves the order behind the history order 3
+        ("10:00:11", TraderRequest::ModifyLimitOrder(OrderID(1), Price(100), Size(4))),
+        // Price change crosses the ask
+        ("10:00:21", TraderRequest::ModifyLimitOrder(OrderID(1), Price(105), Size(3))),
+        ("10:00:22", TraderRequest::ModifyLimitOrder(OrderID(1), Price(105), Size(1))),
+        ("10:00:23", TraderRequest::ModifyLimitOrder(OrderID(2), Price(105), Size(1))),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderModified(OrderID(1), Price(100), Size(3)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(2), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderModified(OrderID(1), Price(100), Size(4)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(1), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(3), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderModified(OrderID(1), Price(105), Size(3)),
+            ExchangeReply::CannotModifyOrder(OrderID(1), InabilityToModifyReason::OrderAlreadyExecuted),
+            ExchangeReply::CannotModifyOrder(OrderID(2), InabilityToModifyReason::OrderHasNotBeenSubmitted),
+        ]
+    )
+}
+
+#[test]
+fn time_in_force() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3)
+        .add_trade("2021-06-02 10:00:05", 5, Direction::Buy)
+        .add_ob_diff("2021-06-02 17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(15), Direction::Buy, Price(105))
+                    .with_time_in_force(TimeInForce::ImmediateOrCancel)
+            )
+        ),
+        (
+            "10:00:02",
+            TraderRequest::PlaceMarketOrder(
+                MarketOrder::new(OrderID(2), Size(20), Direction::Sell)
+                    .with_time_in_force(TimeInForce::FillOrKill)
+            )
+        ),
+        (
+            "10:00:03",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(3), Size(5), Direction::Sell, Price(110))
+                    .with_time_in_force(TimeInForce::GoodTillCancel)
+            )
+        ),
+        (
+            "10:00:03",
+            TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(4), Size(5), Direction::Sell, Price(111)))
+        ),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(10), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ImmediateOrCancel),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(2), DiscardingReason::InsufficientLiquidity),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderCancelled(OrderID(4), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderExecuted(OrderID(3), Size(5), Price(110), Liquidity::Added, Fee(0)),
+        ]
+    )
+}
+
+#[test]
+fn good_till_time() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3)
+        .add_ob_diff("2021-06-02 10:00:05", 1, Direction::Sell, 120, 3)
+        .add_ob_diff("2021-06-02 17:00:00", 1, Direction::Sell, 121, 4);
+    let good_till = |time| TimeInForce::GoodTillTime(parse_dt(time));
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(101))
+                    .with_time_in_force(good_till("10:00:30"))
+            )
+        ),
+        (
+            "10:00:02",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(2), Size(5), Direction::Buy, Price(99))
+                    .with_time_in_force(good_till("2021-06-02 10:00:10"))
+            )
+        ),
+        (
+            "10:00:03",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(3), Size(5), Direction::Buy, Price(99))
+                    .with_time_in_force(good_till("09:00:00"))
+            )
+        ),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::AlreadyExpired),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::Expired),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::Expired),
+        ]
+    )
+}
+
+#[test]
+fn stop_orders() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 5, Direction::Buy, 99, 2)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 3)
+        .add_trade("10:00:10", 2, Direction::Sell)
+        .add_trade("10:00:20", 1, Direction::Buy)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceStopOrder(StopOrder::new(OrderID(1), Size(3), Direction::Sell, Price(100)))),
+        (
+            "10:00:01",
+            TraderRequest::PlaceStopOrder(
+                StopOrder::new(OrderID(2), Size(4), Direction::Buy, Price(105)).with_limit_price(Price(106))
+            )
+        ),
+        ("10:00:02", TraderRequest::PlaceStopOrder(StopOrder::new(OrderID(3), Size(1), Direction::Sell, Price(90)))),
+        ("10:00:03", TraderRequest::CancelStopOrder(OrderID(3))),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::TraderRequested),
+            ExchangeReply::OrderTriggered(OrderID(1)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(3), Price(100), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderTriggered(OrderID(2)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(4), Price(105), Liquidity::Removed, Fee(0)),
+        ]
+    )
+}
+
+#[test]
+fn fill_or_kill_stop_order() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 5, Direction::Buy, 99, 2)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 3)
+        .add_trade("10:00:10", 2, Direction::Sell)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceStopOrder(
+                StopOrder::new(OrderID(1), Size(20), Direction::Sell, Price(100))
+                    .with_time_in_force(TimeInForce::FillOrKill)
+            )
+        ),
+    ]);
+    run_scripted(history, &mut trader);
+    // 13 left at 100 and 99 after the trade
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderTriggered(OrderID(1)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::InsufficientLiquidity),
+        ]
+    )
+}
+
+#[test]
+fn iceberg_order() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:02", 4, Direction::Buy, 100, 3)
+        .add_trade("10:00:10", 15, Direction::Sell)
+        .add_trade("10:02:00", 20, Direction::Sell)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(10), Direction::Buy, Price(100)).with_peak_size(Size(3))
+            )
+        ),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(1), Price(100), Liquidity::Added, Fee(0)),
+        ]
+    );
+    let (_, ob_snapshot) = trader.ob_snapshots.iter()
+        .find(|(dt, _)| *dt == parse_dt("10:01:00"))
+        .unwrap();
+    assert_eq!(
+        *ob_snapshot,
+        OrderBookSnapshot { instrument: InstrumentID(0), bids: vec![(Price(100), Size(5))], asks: vec![(Price(105), Size(10))] }
+    )
+}
+
+#[test]
+fn post_only() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(105))
+                    .with_post_only(PostOnlyMode::Reject)
+            )
+        ),
+        (
+            "10:00:02",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(2), Size(5), Direction::Buy, Price(106))
+                    .with_post_only(PostOnlyMode::Reprice)
+            )
+        ),
+        (
+            "10:00:03",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(3), Size(5), Direction::Sell, Price(100))
+                    .with_post_only(PostOnlyMode::Reprice)
+            )
+        ),
+        (
+            "10:00:04",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(4), Size(5), Direction::Buy, Price(103))
+                    .with_post_only(PostOnlyMode::Reject)
+            )
+        ),
+        ("10:00:05", TraderRequest::ModifyLimitOrder(OrderID(4), Price(105), Size(5))),
+        ("10:00:06", TraderRequest::ModifyLimitOrder(OrderID(2), Price(110), Size(5))),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderPlacementDiscarded(OrderID(1), DiscardingReason::WouldTakeLiquidity),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderRepriced(OrderID(2), Price(104)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderRepriced(OrderID(3), Price(105)),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::CannotModifyOrder(OrderID(4), InabilityToModifyReason::WouldTakeLiquidity),
+            ExchangeReply::OrderModified(OrderID(2), Price(104), Size(5)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(4), CancellationReason::ExchangeClosed),
+        ]
+    );
+    let (_, ob_snapshot) = trader.ob_snapshots.iter()
+        .find(|(dt, _)| *dt == parse_dt("10:01:00"))
+        .unwrap();
+    assert_eq!(
+        *ob_snapshot,
+        OrderBookSnapshot { instrument: InstrumentID(0), bids: vec![(Price(104), Size(5))], asks: vec![(Price(105), Size(15))] }
+    )
+}
+
+#[test]
+fn market_order_policy() {
+    let history = || HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 2, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:00", 4, Direction::Sell, 106, 3)
+        .add_ob_diff("10:00:10", 3, Direction::Buy, 101, 4)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 5);
+
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(15), Direction::Sell))),
+    ]);
+    run_scripted_with_policy(history(), &mut trader, MarketOrderPolicy::KeepPending);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(10), Price(100), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderRemainderPending(OrderID(1), Size(5)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(101), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(15), Direction::Sell))),
+    ]);
+    run_scripted_with_policy(history(), &mut trader, MarketOrderPolicy::CancelRemainder);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(10), Price(100), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::InsufficientLiquidity),
+        ]
+    );
+
+    // The protection price is one tick away from the best ask at arrival
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(10), Direction::Buy))),
+    ]);
+    run_scripted_with_policy(history(), &mut trader, MarketOrderPolicy::ConvertToLimit { protection_ticks: 1 });
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(2), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(4), Price(106), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderConvertedToLimit(OrderID(1), Price(106)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    let (_, ob_snapshot) = trader.ob_snapshots.iter()
+        .find(|(dt, _)| *dt == parse_dt("10:01:00"))
+        .unwrap();
+    assert_eq!(
+        *ob_snapshot,
+        OrderBookSnapshot { instrument: InstrumentID(0), bids: vec![(Price(106), Size(4))], asks: vec![] }
+    );
+
+    // The protection price of a sell order does not fall below the minimum tick
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(15), Direction::Sell))),
+    ]);
+    run_scripted_with_policy(history(), &mut trader, MarketOrderPolicy::ConvertToLimit { protection_ticks: 1000 });
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(10), Price(100), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderConvertedToLimit(OrderID(1), Price(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(1), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    )
+}
+
+#[test]
+fn pegged_orders() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("10:00:10", 3, Direction::Buy, 101, 3)
+        .add_ob_diff("10:00:20", 2, Direction::Sell, 102, 4)
+        .add_trade("10:00:30", 3, Direction::Sell)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 5);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(90)).with_peg(PegReference::Primary, 0)
+            )
+        ),
+        (
+            "10:00:02",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(2), Size(5), Direction::Sell, Price(110)).with_peg(PegReference::Midpoint, 0)
+            )
+        ),
+        // The price of the pegged order is set by the exchange
+        ("10:00:40", TraderRequest::ModifyLimitOrder(OrderID(1), Price(50), Size(3))),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(100)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderRepriced(OrderID(2), Price(103)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(101)),
+            ExchangeReply::OrderRepriced(OrderID(2), Price(102)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(100)),
+            ExchangeReply::OrderRepriced(OrderID(2), Price(101)),
+            ExchangeReply::OrderModified(OrderID(1), Price(100), Size(3)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+        ]
+    );
+    let (_, ob_snapshot) = trader.ob_snapshots.iter()
+        .find(|(dt, _)| *dt == parse_dt("10:01:00"))
+        .unwrap();
+    assert_eq!(
+        *ob_snapshot,
+        OrderBookSnapshot { instrument: InstrumentID(0), bids: vec![(Price(100), Size(13))], asks: vec![(Price(101), Size(5))] }
+    )
+}
+
+#[test]
+fn suspended_pegged_order() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3)
+        .add_ob_diff("2021-06-02 10:00:00", 10, Direction::Buy, 98, 4)
+        .add_ob_diff("2021-06-02 10:00:00", 10, Direction::Sell, 103, 5)
+        .add_ob_diff("2021-06-02 17:00:00", 1, Direction::Sell, 120, 6);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(90))
+                    .with_peg(PegReference::Primary, 0)
+                    .with_time_in_force(TimeInForce::GoodTillCancel)
+            )
+        ),
+    ]);
+    run_scripted(history, &mut trader);
+    // The order stays pegged in the next session
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(100)),
+            ExchangeReply::OrderRepriced(OrderID(1), Price(98)),
+        ]
+    );
+}
+
+#[test]
+fn order_groups() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_trade("10:00:05", 20, Direction::Buy)
+        .add_trade("10:00:20", 15, Direction::Sell)
+        .add_trade("10:00:30", 5, Direction::Buy)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceOcoOrders(
+                GroupOrder::Limit(LimitOrder::new(OrderID(1), Size(5), Direction::Sell, Price(106))),
+                GroupOrder::Limit(LimitOrder::new(OrderID(2), Size(5), Direction::Sell, Price(107))),
+            )
+        ),
+        (
+            "10:00:10",
+            TraderRequest::PlaceBracketOrder(
+                GroupOrder::Limit(LimitOrder::new(OrderID(3), Size(5), Direction::Buy, Price(100))),
+                GroupOrder::Limit(LimitOrder::new(OrderID(4), Size(5), Direction::Sell, Price(110))),
+                GroupOrder::Stop(StopOrder::new(OrderID(5), Size(5), Direction::Sell, Price(95))),
+            )
+        ),
+        (
+            "10:00:40",
+            TraderRequest::PlaceBracketOrder(
+                GroupOrder::Limit(LimitOrder::new(OrderID(6), Size(5), Direction::Buy, Price(90))),
+                GroupOrder::Limit(LimitOrder::new(OrderID(7), Size(5), Direction::Sell, Price(110))),
+                GroupOrder::Stop(StopOrder::new(OrderID(8), Size(5), Direction::Sell, Price(85))),
+            )
+        ),
+        ("10:00:41", TraderRequest::CancelLimitOrder(OrderID(6))),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            // The sweep does not reach the other order of the pair
+            ExchangeReply::OrderExecuted(OrderID(1), Size(5), Price(106), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::OneCancelsOther),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            // Children are placed at the moment the parent is executed
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderAccepted(OrderID(5)),
+            ExchangeReply::OrderExecuted(OrderID(3), Size(5), Price(100), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(4), Size(5), Price(110), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(5), CancellationReason::OneCancelsOther),
+            ExchangeReply::OrderAccepted(OrderID(6)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(7), DiscardingReason::ParentNotExecuted),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(8), DiscardingReason::ParentNotExecuted),
+            ExchangeReply::OrderCancelled(OrderID(6), CancellationReason::TraderRequested),
+        ]
+    );
+}
+
+#[test]
+fn self_trade_prevention() {
+    let cases = [
+        (
+            SelfTradePrevention::CancelNewest,
+            vec![
+                ExchangeReply::OrderAccepted(OrderID(1)),
+                ExchangeReply::OrderAccepted(OrderID(2)),
+                ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::SelfTradePrevention),
+                ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+            ]
+        ),
+        (
+            SelfTradePrevention::CancelOldest,
+            vec![
+                ExchangeReply::OrderAccepted(OrderID(1)),
+                ExchangeReply::OrderAccepted(OrderID(2)),
+                ExchangeReply::OrderExecuted(OrderID(2), Size(8), Price(105), Liquidity::Removed, Fee(0)),
+                ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::SelfTradePrevention),
+            ]
+        ),
+        (
+            SelfTradePrevention::CancelBoth,
+            vec![
+                ExchangeReply::OrderAccepted(OrderID(1)),
+                ExchangeReply::OrderAccepted(OrderID(2)),
+                ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::SelfTradePrevention),
+                ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::SelfTradePrevention),
+            ]
+        ),
+        (
+            SelfTradePrevention::DecrementAndCancel,
+            vec![
+                ExchangeReply::OrderAccepted(OrderID(1)),
+                ExchangeReply::OrderAccepted(OrderID(2)),
+                ExchangeReply::OrderExecuted(OrderID(2), Size(3), Price(105), Liquidity::Removed, Fee(0)),
+                ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::SelfTradePrevention),
+            ]
+        ),
+    ];
+    for (mode, replies) in cases {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Sell, Price(104)))),
+            ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(8), Direction::Buy, Price(105)))),
+        ]);
+        run_scripted_with(history, &mut trader, |exchange| exchange.with_self_trade_prevention(mode));
+        assert_eq!(trader.replies, replies, "{:?}", mode);
+    }
+}
+
+#[test]
+fn mass_cancel() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(95)))),
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(5), Direction::Buy, Price(98)))),
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(5), Direction::Sell, Price(110)))),
+        ("10:00:02", TraderRequest::CancelInPriceRange(Price(97), Price(99))),
+        ("10:00:03", TraderRequest::CancelAllOnSide(Direction::Sell)),
+        ("10:00:04", TraderRequest::CancelAll),
+        ("10:00:05", TraderRequest::CancelAll),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(1),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(1),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(1),
+            ExchangeReply::MassCancelled(0),
+        ]
+    );
+
+    // The orders suspended between the sessions are cancelled as well
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3)
+        .add_ob_diff("2021-06-02 10:00:00", 10, Direction::Buy, 98, 4)
+        .add_ob_diff("2021-06-02 17:00:00", 1, Direction::Sell, 120, 5);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(95))
+                    .with_time_in_force(TimeInForce::GoodTillCancel)
+            )
+        ),
+        ("2021-06-02 09:00:00", TraderRequest::CancelAll),
+    ]);
+    run_scripted(history, &mut trader);
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(1),
+        ]
+    );
+}
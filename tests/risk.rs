@@ -0,0 +1,203 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn rate_limits() {
+    fn run_with(policy: ThrottlePolicy) -> (Vec<ExchangeReply>, Vec<MessageStatistics>) {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 90, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 2)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(1), Direction::Buy, Price(95)))),
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(1), Direction::Buy, Price(95)))),
+            ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(95)))),
+            ("10:00:02", TraderRequest::CancelLimitOrder(OrderID(1))),
+            ("10:00:03", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(4), Size(1), Direction::Buy))),
+        ]);
+        run_scripted_with(
+            history,
+            &mut trader,
+            |exchange| exchange
+                .with_rate_limit(RateLimit { max_messages: 2, window: Duration::seconds(1) })
+                .with_throttle_policy(policy),
+        );
+        (trader.replies, trader.message_statistics)
+    }
+
+    let (replies, statistics) = run_with(ThrottlePolicy::Discard);
+    assert_eq!(
+        replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::RateLimited),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderExecuted(OrderID(4), Size(1), Price(110), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+        ]
+    );
+    assert_eq!(statistics, vec![MessageStatistics { messages: 4, rate_limited: 1, trades: 1 }]);
+    assert_eq!(statistics[0].get_order_to_trade_ratio(), Some(4.0));
+
+    // The cancellation waits behind the delayed order
+    let (replies, statistics) = run_with(ThrottlePolicy::Delay);
+    assert_eq!(
+        replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderExecuted(OrderID(4), Size(1), Price(110), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::ExchangeClosed),
+        ]
+    );
+    assert_eq!(statistics, vec![MessageStatistics { messages: 5, rate_limited: 2, trades: 1 }]);
+
+    // Mass-cancel requests are never throttled but count towards the limits
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 90, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(1), Direction::Buy, Price(95)))),
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(1), Direction::Buy, Price(95)))),
+        ("10:00:02", TraderRequest::CancelAll),
+        ("10:00:02", TraderRequest::CancelAll),
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(95)))),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange.with_rate_limit(RateLimit { max_messages: 2, window: Duration::seconds(1) }),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::RateLimited),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(0),
+            ExchangeReply::MassCancelled(2),
+        ]
+    );
+    assert_eq!(trader.message_statistics, vec![MessageStatistics { messages: 4, rate_limited: 1, trades: 0 }]);
+}
+
+#[test]
+fn risk_limits() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(11), Direction::Buy, Price(100)))),
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(10), Direction::Buy, Price(104)))),
+        ("10:00:03", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(3), Direction::Buy, Price(80)))),
+        ("10:00:04", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(4), Size(3), Direction::Buy, Price(100)))),
+        ("10:00:05", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(5), Size(3), Direction::Buy))),
+        ("10:00:06", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(6), Size(3), Direction::Sell, Price(110)))),
+        ("10:00:07", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(7), Size(1), Direction::Sell, Price(110)))),
+        ("10:00:09", TraderRequest::ModifyLimitOrder(OrderID(4), Price(100), Size(6))),
+        // Sell reduces the long position
+        ("10:00:10", TraderRequest::ModifyLimitOrder(OrderID(6), Price(110), Size(6))),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange
+            .with_instrument_spec(InstrumentSpec::new(0.01))
+            .with_risk_limits(RiskLimits {
+                max_order_size: Some(Size(10)),
+                max_notional: Some(10.0),
+                max_open_orders: Some(2),
+                max_position: Some(Size(5)),
+                max_price_distance_bps: Some(1000),
+            }),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderPlacementDiscarded(OrderID(1), DiscardingReason::MaxOrderSizeExceeded),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(2), DiscardingReason::MaxNotionalExceeded),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(3), DiscardingReason::FatFingerPrice),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderAccepted(OrderID(5)),
+            ExchangeReply::OrderExecuted(OrderID(5), Size(3), Price(105), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderAccepted(OrderID(6)),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(7), DiscardingReason::MaxOpenOrdersExceeded),
+            ExchangeReply::CannotModifyOrder(OrderID(4), InabilityToModifyReason::MaxPositionExceeded),
+            ExchangeReply::OrderModified(OrderID(6), Price(110), Size(6)),
+            ExchangeReply::OrderCancelled(OrderID(4), CancellationReason::ExchangeClosed),
+            ExchangeReply::OrderCancelled(OrderID(6), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn loss_limits() {
+    fn run_with(limits: LossLimits) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+            .add_trade("10:00:10", 5, Direction::Sell)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3)
+            .add_ob_diff("2021-06-02 10:00:00", 10, Direction::Buy, 100, 4)
+            .add_ob_diff("2021-06-02 17:00:00", 1, Direction::Sell, 120, 5);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(5), Direction::Buy))),
+            ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(5), Direction::Sell, Price(110)))),
+            ("10:00:03", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(99)))),
+            ("10:00:20", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(4), Size(1), Direction::Buy, Price(99)))),
+            ("2021-06-02 10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(5), Size(1), Direction::Buy, Price(90)))),
+        ]);
+        run_scripted_with(
+            history,
+            &mut trader,
+            |exchange| exchange
+                .with_instrument_spec(InstrumentSpec::new(0.01))
+                .with_loss_limits(limits),
+        );
+        trader.replies
+    }
+
+    // Long 5 bought at 1.05 is marked to the trade at 1.00
+    let before_kill_switch = || vec![
+        ExchangeReply::OrderAccepted(OrderID(1)),
+        ExchangeReply::OrderExecuted(OrderID(1), Size(5), Price(105), Liquidity::Removed, Fee(0)),
+        ExchangeReply::OrderAccepted(OrderID(2)),
+        ExchangeReply::OrderAccepted(OrderID(3)),
+        ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::KillSwitch),
+        ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::KillSwitch),
+    ];
+    assert_eq!(
+        run_with(LossLimits { max_loss: Some(0.2), max_drawdown: None, scope: KillSwitchScope::Session }),
+        before_kill_switch().into_iter()
+            .chain([
+                ExchangeReply::KillSwitchEngaged(KillSwitchReason::MaxLoss),
+                ExchangeReply::OrderPlacementDiscarded(OrderID(4), DiscardingReason::KillSwitch),
+                ExchangeReply::OrderAccepted(OrderID(5)),
+                ExchangeReply::OrderCancelled(OrderID(5), CancellationReason::ExchangeClosed),
+            ])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        run_with(LossLimits { max_loss: None, max_drawdown: Some(0.2), scope: KillSwitchScope::Run }),
+        before_kill_switch().into_iter()
+            .chain([
+                ExchangeReply::KillSwitchEngaged(KillSwitchReason::MaxDrawdown),
+                ExchangeReply::OrderPlacementDiscarded(OrderID(4), DiscardingReason::KillSwitch),
+                ExchangeReply::OrderPlacementDiscarded(OrderID(5), DiscardingReason::KillSwitch),
+            ])
+            .collect::<Vec<_>>()
+    );
+}
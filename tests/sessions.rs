@@ -0,0 +1,238 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn auctions() {
+    let history = HistoryHolder::default()
+        // Crossed history orders accumulate during the opening auction
+        .add_ob_diff("10:00:01", 10, Direction::Buy, 102, 1)
+        .add_ob_diff("10:00:01", 10, Direction::Sell, 100, 2)
+        .add_trade("10:00:03", 10, Direction::Buy)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(101)))),
+        ("10:00:02", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(2), Size(3), Direction::Sell))),
+        (
+            "12:00:00",
+            TraderRequest::PlaceLimitOrder(
+                LimitOrder::new(OrderID(3), Size(2), Direction::Sell, Price(99)).with_time_in_force(TimeInForce::AtTheClose)
+            )
+        ),
+        (
+            "12:00:00",
+            TraderRequest::PlaceMarketOrder(
+                MarketOrder::new(OrderID(4), Size(1), Direction::Buy).with_time_in_force(TimeInForce::AtTheClose)
+            )
+        ),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange
+            .with_opening_auction(
+                CallAuction { duration: Duration::minutes(5), indicative_interval: Some(Duration::minutes(1)) }
+            )
+            .with_closing_auction(CallAuction { duration: Duration::minutes(10), indicative_interval: None }),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            // The most volume is matched at 100 and 101 with the same imbalance, and the lower price is chosen
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(3), Price(100), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(3), Price(100), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            // 99 and 101 are equally close to the opening price
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(1), Price(99), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(3), Size(2), Price(99), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(4), Size(1), Price(99), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    let indicative = AuctionInfo {
+        instrument: InstrumentID(0),
+        phase: AuctionPhase::Opening,
+        price: Some(Price(100)),
+        matched_size: Size(13),
+        imbalance: Size(2),
+        imbalance_side: Some(Direction::Buy),
+    };
+    assert_eq!(
+        trader.auction_infos,
+        ["10:01:00", "10:02:00", "10:03:00", "10:04:00", "10:05:00"].iter()
+            .map(|time| (parse_dt(time), indicative))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn price_bands_and_halts() {
+    fn run_with(resumption: HaltResumption) -> Vec<ExchangeReply> {
+        let history = HistoryHolder::default()
+            .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 101, 2)
+            .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 3)
+            .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+        let mut trader = ScriptedTrader::new(vec![
+            ("10:00:01", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(1), Direction::Buy))),
+            ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(1), Direction::Buy, Price(120)))),
+            ("10:00:03", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(3), Size(12), Direction::Buy))),
+            ("10:00:04", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(4), Size(2), Direction::Sell, Price(105)))),
+            ("10:00:04", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(5), Size(1), Direction::Buy))),
+        ]);
+        run_scripted_with(
+            history,
+            &mut trader,
+            |exchange| exchange
+                .with_price_band(PriceBand { width_bps: 500 })
+                .with_trading_halt(
+                    TradingHalt {
+                        trigger: HaltTrigger::Volatility { threshold_bps: 300, window: Duration::minutes(1) },
+                        duration: Duration::minutes(5),
+                        resumption,
+                    }
+                ),
+        );
+        trader.replies
+    }
+    let before_halt = || vec![
+        ExchangeReply::OrderAccepted(OrderID(1)),
+        ExchangeReply::OrderExecuted(OrderID(1), Size(1), Price(101), Liquidity::Removed, Fee(0)),
+        // 120 is more than 5% away from the last traded price
+        ExchangeReply::OrderPlacementDiscarded(OrderID(2), DiscardingReason::OutsidePriceBand),
+        ExchangeReply::OrderAccepted(OrderID(3)),
+        ExchangeReply::OrderPartiallyExecuted(OrderID(3), Size(9), Price(101), Liquidity::Removed, Fee(0)),
+        ExchangeReply::OrderExecuted(OrderID(3), Size(3), Price(110), Liquidity::Removed, Fee(0)),
+        ExchangeReply::TradingHalted(InstrumentID(0), HaltReason::Volatility),
+    ];
+
+    assert_eq!(
+        run_with(HaltResumption::Auction { indicative_interval: None }),
+        before_halt().into_iter().chain([
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderAccepted(OrderID(5)),
+            // The reopening auction uncrosses the orders placed during the halt
+            ExchangeReply::OrderPartiallyExecuted(OrderID(4), Size(1), Price(105), Liquidity::Auction, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(5), Size(1), Price(105), Liquidity::Auction, Fee(0)),
+            ExchangeReply::TradingResumed(InstrumentID(0)),
+            ExchangeReply::OrderCancelled(OrderID(4), CancellationReason::ExchangeClosed),
+        ]).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        run_with(HaltResumption::Continuous),
+        before_halt().into_iter().chain([
+            ExchangeReply::OrderPlacementDiscarded(OrderID(4), DiscardingReason::TradingHalted),
+            ExchangeReply::OrderPlacementDiscarded(OrderID(5), DiscardingReason::TradingHalted),
+            ExchangeReply::TradingResumed(InstrumentID(0)),
+        ]).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn stop_orders_after_halt() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 101, 2)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 3)
+        // Nothing is matched during the halt
+        .add_trade("10:01:00", 20, Direction::Sell)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        (
+            "10:00:01",
+            TraderRequest::PlaceStopOrder(
+                StopOrder::new(OrderID(1), Size(1), Direction::Buy, Price(105)).with_limit_price(Price(120))
+            )
+        ),
+        (
+            "10:00:01",
+            TraderRequest::PlaceStopOrder(
+                StopOrder::new(OrderID(2), Size(1), Direction::Buy, Price(105)).with_limit_price(Price(112))
+            )
+        ),
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(3), Size(1), Direction::Buy, Price(100)))),
+        ("10:00:02", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(4), Size(12), Direction::Buy))),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange
+            .with_price_band(PriceBand { width_bps: 500 })
+            .with_trading_halt(
+                TradingHalt {
+                    trigger: HaltTrigger::Volatility { threshold_bps: 300, window: Duration::minutes(1) },
+                    duration: Duration::minutes(5),
+                    resumption: HaltResumption::Continuous,
+                }
+            ),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderAccepted(OrderID(4)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(4), Size(10), Price(101), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(4), Size(2), Price(110), Liquidity::Removed, Fee(0)),
+            ExchangeReply::TradingHalted(InstrumentID(0), HaltReason::Volatility),
+            // The stop orders are triggered when the trading resumes
+            ExchangeReply::OrderTriggered(OrderID(1)),
+            ExchangeReply::OrderTriggered(OrderID(2)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(1), Price(110), Liquidity::Removed, Fee(0)),
+            // 120 is more than 5% away from the last traded price
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::OutsidePriceBand),
+            ExchangeReply::TradingResumed(InstrumentID(0)),
+            ExchangeReply::OrderCancelled(OrderID(3), CancellationReason::ExchangeClosed),
+        ]
+    );
+}
+
+#[test]
+fn halt_on_stop_order_cascade() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 101, 2)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 3)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 4);
+    let mut trader = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceStopOrder(StopOrder::new(OrderID(1), Size(12), Direction::Buy, Price(101)))),
+        ("10:00:01", TraderRequest::PlaceStopOrder(StopOrder::new(OrderID(2), Size(1), Direction::Buy, Price(105)))),
+        ("10:00:02", TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(3), Size(1), Direction::Buy))),
+    ]);
+    run_scripted_with(
+        history,
+        &mut trader,
+        |exchange| exchange
+            .with_trading_halt(
+                TradingHalt {
+                    trigger: HaltTrigger::CircuitBreaker { threshold_bps: 500 },
+                    duration: Duration::minutes(5),
+                    resumption: HaltResumption::Continuous,
+                }
+            ),
+    );
+    assert_eq!(
+        trader.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderAccepted(OrderID(3)),
+            ExchangeReply::OrderTriggered(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(9), Price(101), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(3), Price(110), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderExecuted(OrderID(3), Size(1), Price(101), Liquidity::Removed, Fee(0)),
+            // Only the triggered stop order trades beyond the threshold
+            ExchangeReply::TradingHalted(InstrumentID(0), HaltReason::CircuitBreaker),
+            // The other stop order is triggered when the trading resumes
+            ExchangeReply::OrderTriggered(OrderID(2)),
+            ExchangeReply::OrderExecuted(OrderID(2), Size(1), Price(110), Liquidity::Removed, Fee(0)),
+            ExchangeReply::TradingResumed(InstrumentID(0)),
+        ]
+    );
+}
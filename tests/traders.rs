@@ -0,0 +1,208 @@
+mod common;
+
+use exchange_backtester::prelude::*;
+
+use common::*;
+
+#[test]
+fn multiple_traders() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    // Both traders use the same order IDs
+    let mut first = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(102)))),
+        ("10:00:04", TraderRequest::CancelLimitOrder(OrderID(1))),
+    ]);
+    let mut second = ScriptedTrader::new(vec![
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(2), Direction::Sell, Price(102)))),
+        ("10:00:02", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(3), Direction::Sell, Price(110)))),
+        // Cancels the own orders only
+        ("10:00:03", TraderRequest::CancelAll),
+        // The second trader has never placed the order with this ID
+        ("10:00:05", TraderRequest::CancelLimitOrder(OrderID(3))),
+        ("10:00:05", TraderRequest::ModifyLimitOrder(OrderID(3), Price(101), Size(1))),
+    ]);
+    let get_next_open_dt = |datetime: DateTime| {
+        datetime.date().and_hms(10, 0, 0)
+    };
+    let get_next_close_dt = |datetime: DateTime| {
+        datetime.date().and_hms(18, 0, 0)
+    };
+    let exchange: ScriptedExchange = ExchangeBuilder::new::<true>(history, &mut first, get_next_open_dt, get_next_close_dt)
+        .ob_level_subscription_depth(lags::constant::ONE_MINUTE, 1)
+        .with_periodic_wakeup(lags::constant::ONE_SECOND);
+    // Self-trade prevention does not apply to the orders of different traders
+    exchange
+        .with_self_trade_prevention(SelfTradePrevention::CancelBoth)
+        .with_trader(&mut second)
+        .with_trader_subscriptions(TraderID(1), TraderSubscriptions { order_book: false, ..Default::default() })
+        .run_trades();
+    assert_eq!(
+        first.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(2), Price(102), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::TraderRequested),
+        ]
+    );
+    assert_eq!(
+        second.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderAccepted(OrderID(2)),
+            ExchangeReply::OrderExecuted(OrderID(1), Size(2), Price(102), Liquidity::Removed, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::TraderRequested),
+            ExchangeReply::MassCancelled(1),
+            ExchangeReply::CannotCancelOrder(OrderID(3), InabilityToCancelReason::OrderHasNotBeenSubmitted),
+            ExchangeReply::CannotModifyOrder(OrderID(3), InabilityToModifyReason::OrderHasNotBeenSubmitted),
+        ]
+    );
+    assert!(!first.ob_snapshots.is_empty());
+    assert!(second.ob_snapshots.is_empty());
+}
+
+/// Sends the scripted requests on its wakeups and records the replies with the moments they have been sent and
+/// delivered at
+struct LaggingTrader {
+    to_exchange_ns: u64,
+    to_trader_ns: u64,
+    script: Vec<(DateTime, TraderRequest)>,
+    replies: Vec<(DateTime, DateTime, ExchangeReply)>,
+}
+
+impl HandleSubscriptionUpdates for LaggingTrader {
+    fn handle_order_book_snapshot(&mut self, _: DateTime, _: DateTime, _: OrderBookSnapshot) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_trade_info_update(&mut self, _: DateTime, _: DateTime, _: Vec<OrderBookDiff>) -> Vec<TraderRequest> {
+        vec![]
+    }
+    fn handle_wakeup(&mut self, dt: DateTime) -> Vec<TraderRequest> {
+        let (due, script) = std::mem::take(&mut self.script).into_iter().partition(|(request_dt, _)| *request_dt <= dt);
+        self.script = script;
+        due.into_iter().map(|(_, request): (DateTime, TraderRequest)| request).collect()
+    }
+}
+
+impl Trader for LaggingTrader {
+    fn exchange_to_trader_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { self.to_trader_ns }
+    fn trader_to_exchange_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { self.to_exchange_ns }
+    fn handle_exchange_reply(&mut self, exchange_dt: DateTime, delivery_dt: DateTime, reply: ExchangeReply) -> Vec<TraderRequest> {
+        self.replies.push((exchange_dt, delivery_dt, reply));
+        vec![]
+    }
+    fn exchange_open(&mut self, _: DateTime, _: DateTime) {}
+    fn exchange_closed(&mut self, _: DateTime, _: DateTime) {}
+}
+
+#[test]
+fn traders_of_different_types() {
+    let history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let mut first = ScriptedTrader::new(vec![
+        ("10:00:01", TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(5), Direction::Buy, Price(102)))),
+    ]);
+    // Requests reach the exchange in one second and the replies reach the trader in two seconds
+    let mut second = LaggingTrader {
+        to_exchange_ns: 1_000_000_000,
+        to_trader_ns: 2_000_000_000,
+        script: vec![
+            (parse_dt("10:00:02"), TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(2), Direction::Sell, Price(102)))),
+            (parse_dt("10:00:02"), TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(2), Size(1), Direction::Sell, Price(110)))),
+        ],
+        replies: vec![],
+    };
+    let get_next_open_dt = |datetime: DateTime| {
+        datetime.date().and_hms(10, 0, 0)
+    };
+    let get_next_close_dt = |datetime: DateTime| {
+        datetime.date().and_hms(18, 0, 0)
+    };
+    let first_trader: &mut dyn Trader = &mut first;
+    let second_trader: &mut dyn Trader = &mut second;
+    ExchangeBuilder::new::<true>(history, first_trader, get_next_open_dt, get_next_close_dt)
+        .with_periodic_wakeup(lags::constant::ONE_SECOND)
+        .with_trader(second_trader)
+        .run_trades();
+    assert_eq!(
+        first.replies,
+        vec![
+            ExchangeReply::OrderAccepted(OrderID(1)),
+            ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(2), Price(102), Liquidity::Added, Fee(0)),
+            ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed),
+        ]
+    );
+    assert_eq!(
+        second.replies,
+        vec![
+            (parse_dt("10:00:03"), parse_dt("10:00:05"), ExchangeReply::OrderAccepted(OrderID(1))),
+            (parse_dt("10:00:03"), parse_dt("10:00:05"), ExchangeReply::OrderAccepted(OrderID(2))),
+            (
+                parse_dt("10:00:03"),
+                parse_dt("10:00:05"),
+                ExchangeReply::OrderExecuted(OrderID(1), Size(2), Price(102), Liquidity::Removed, Fee(0)),
+            ),
+            (
+                parse_dt("18:00:00"),
+                parse_dt("18:00:02"),
+                ExchangeReply::OrderCancelled(OrderID(2), CancellationReason::ExchangeClosed),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn multiple_venues() {
+    let first_history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 100, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 105, 2)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    let second_history = HistoryHolder::default()
+        .add_ob_diff("10:00:00", 10, Direction::Buy, 107, 1)
+        .add_ob_diff("10:00:00", 10, Direction::Sell, 110, 2)
+        .add_ob_diff("10:00:01", 5, Direction::Buy, 107, 1)
+        .add_ob_diff("17:00:00", 1, Direction::Sell, 120, 3);
+    // Buys on the first venue and sells on the second one, which is one second away
+    let mut trader = ScriptedVenueTrader::new(vec![
+        ("10:00:01", VenueID(0), TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(6), Direction::Buy))),
+        ("10:00:01", VenueID(1), TraderRequest::PlaceMarketOrder(MarketOrder::new(OrderID(1), Size(6), Direction::Sell))),
+        ("10:00:02", VenueID(2), TraderRequest::PlaceLimitOrder(LimitOrder::new(OrderID(1), Size(1), Direction::Buy, Price(100)))),
+    ]);
+    let get_next_open_dt = |datetime: DateTime| {
+        datetime.date().and_hms(10, 0, 0)
+    };
+    let get_next_close_dt = |datetime: DateTime| {
+        datetime.date().and_hms(18, 0, 0)
+    };
+    {
+        let connections = VenueConnections::new(&mut trader);
+        let mut first_link = connections.link(VenueID(0));
+        let mut second_link = connections.link(VenueID(1));
+        let first = ExchangeBuilder::new::<true>(first_history, &mut first_link, get_next_open_dt, get_next_close_dt)
+            .with_periodic_wakeup(lags::constant::ONE_SECOND);
+        let second = ExchangeBuilder::new::<true>(second_history, &mut second_link, get_next_open_dt, get_next_close_dt)
+            .with_periodic_wakeup(lags::constant::ONE_SECOND);
+        MultiVenue::new(&connections)
+            .with_venue(VenueID(0), TraderID(0), first)
+            .with_venue(VenueID(1), TraderID(0), second)
+            .run_trades();
+    }
+    assert_eq!(
+        trader.replies,
+        vec![
+            (VenueID(0), parse_dt("10:00:01"), ExchangeReply::OrderAccepted(OrderID(1))),
+            (VenueID(0), parse_dt("10:00:01"), ExchangeReply::OrderExecuted(OrderID(1), Size(6), Price(105), Liquidity::Removed, Fee(0))),
+            // There is no such venue
+            (VenueID(2), parse_dt("10:00:02"), ExchangeReply::OrderPlacementDiscarded(OrderID(1), DiscardingReason::ExchangeClosed)),
+            // The bid has shrunk by the time the order arrives
+            (VenueID(1), parse_dt("10:00:03"), ExchangeReply::OrderAccepted(OrderID(1))),
+            (VenueID(1), parse_dt("10:00:03"), ExchangeReply::OrderPartiallyExecuted(OrderID(1), Size(5), Price(107), Liquidity::Removed, Fee(0))),
+            (VenueID(1), parse_dt("10:00:03"), ExchangeReply::OrderRemainderPending(OrderID(1), Size(1))),
+            (VenueID(1), parse_dt("18:00:01"), ExchangeReply::OrderCancelled(OrderID(1), CancellationReason::ExchangeClosed)),
+        ]
+    );
+}
@@ -41,8 +41,8 @@ impl HandleSubscriptionUpdates for CustomTrader<'_> {
 }
 
 impl const Trader for CustomTrader<'_> {
-    fn exchange_to_trader_latency(_: &mut StdRng, _: DateTime) -> u64 { 0 }
-    fn trader_to_exchange_latency(_: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn exchange_to_trader_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
+    fn trader_to_exchange_latency(&self, _: &mut StdRng, _: DateTime) -> u64 { 0 }
     fn handle_exchange_reply(&mut self, _: DateTime, _: DateTime, _: ExchangeReply) -> Vec<TraderRequest> {
         vec![]
     }